@@ -0,0 +1,685 @@
+//! Analyze mode
+//! A small command language modelled on C++ Avida's `analyze.cfg` for post-processing runs
+//! without a live `World`: genomes are loaded into a batch, recalculated in the test CPU,
+//! filtered and written to detail files.
+//!
+//! ```text
+//! LOAD_CHECKPOINT run/checkpoint.json   # every genotype in the registry, extinct ancestors too
+//! FIND_LINEAGE                          # line of descent of the most abundant genotype
+//! RECALCULATE
+//! FILTER viable == 1
+//! DETAIL lineage.dat id parent_id fitness task.EQU sequence
+//! ```
+
+use crate::checkpoint;
+use crate::instruction::{genome_to_string, parse_genome, parse_organism_file, Instruction};
+use crate::knockout;
+use crate::landscape;
+use crate::spop;
+use crate::tasks::Task;
+use crate::test_cpu::{Phenotype, TestCpu};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Stats available to FILTER and DETAIL, as (name, description)
+/// Tasks are `task.<index>` or `task.<name>` (e.g. `task.8` or `task.EQU`)
+pub const STATS: &[(&str, &str)] = &[
+    ("id", "Genotype ID"),
+    ("parent_id", "Parent ID (-1 if none)"),
+    ("num_cpus", "Number of living organisms"),
+    ("total_cpus", "Total organisms ever"),
+    ("length", "Genome Length"),
+    ("update_born", "Update Born"),
+    ("depth", "Phylogenetic Depth"),
+    ("viable", "Is Viable (0/1)"),
+    ("self_replicating", "Breeds True (0/1)"),
+    ("copy_length", "Copied Length"),
+    ("exe_length", "Executed Length"),
+    ("gest_time", "Gestation Time"),
+    ("merit", "Merit"),
+    ("fitness", "Fitness"),
+    ("sequence", "Genome Sequence"),
+];
+
+/// Stats that need RECALCULATE first
+const PHENOTYPE_STATS: &[&str] = &[
+    "viable",
+    "self_replicating",
+    "copy_length",
+    "exe_length",
+    "gest_time",
+    "merit",
+    "fitness",
+];
+
+/// One genotype in the batch
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalyzeGenotype {
+    pub id: u64,
+    pub parent_id: Option<u64>,
+    pub num_cpus: usize,
+    pub total_cpus: u64,
+    /// Update the genotype first appeared (-1 if unknown)
+    pub update_born: i64,
+    pub depth: u32,
+    pub genome: Vec<Instruction>,
+    /// Test CPU results (None until RECALCULATE)
+    pub phenotype: Option<Phenotype>,
+}
+
+impl AnalyzeGenotype {
+    fn new(id: u64, genome: Vec<Instruction>) -> Self {
+        Self {
+            id,
+            parent_id: None,
+            num_cpus: 0,
+            total_cpus: 0,
+            update_born: -1,
+            depth: 0,
+            genome,
+            phenotype: None,
+        }
+    }
+}
+
+/// A stat value: numbers for everything except the sequence
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Task named by a `task.<index>` or `task.<name>` stat
+fn task_stat(name: &str) -> Option<Task> {
+    let task = name.strip_prefix("task.")?;
+    match task.parse::<usize>() {
+        Ok(index) => Task::all().get(index).copied(),
+        Err(_) => Task::from_name(task),
+    }
+}
+
+fn check_stat(name: &str) -> Result<(), String> {
+    if STATS.iter().any(|(stat, _)| *stat == name) || task_stat(name).is_some() {
+        Ok(())
+    } else {
+        Err(format!("unknown stat '{}'", name))
+    }
+}
+
+fn stat_description(name: &str) -> String {
+    match task_stat(name) {
+        Some(task) => format!("Performed {} (0/1)", task.name()),
+        None => STATS
+            .iter()
+            .find(|(stat, _)| *stat == name)
+            .map_or(name.to_string(), |(_, description)| description.to_string()),
+    }
+}
+
+/// Value of a stat for a genotype (the stat must have passed `check_stat`)
+pub fn stat(genotype: &AnalyzeGenotype, name: &str) -> Result<Value, String> {
+    let flag = |b: bool| Value::Number(if b { 1.0 } else { 0.0 });
+    if task_stat(name).is_some() || PHENOTYPE_STATS.contains(&name) {
+        let phenotype = genotype
+            .phenotype
+            .as_ref()
+            .ok_or_else(|| format!("stat '{}' needs RECALCULATE first", name))?;
+        if let Some(task) = task_stat(name) {
+            return Ok(flag(phenotype.has_task(task)));
+        }
+        return Ok(match name {
+            "viable" => flag(phenotype.is_viable()),
+            "self_replicating" => flag(phenotype.offspring.as_ref() == Some(&genotype.genome)),
+            "copy_length" => Value::Number(phenotype.copied_size as f64),
+            "exe_length" => Value::Number(phenotype.executed_size as f64),
+            "gest_time" => Value::Number(phenotype.gestation_time as f64),
+            "merit" => Value::Number(phenotype.merit),
+            _ => Value::Number(phenotype.fitness),
+        });
+    }
+
+    Ok(match name {
+        "id" => Value::Number(genotype.id as f64),
+        "parent_id" => Value::Number(genotype.parent_id.map_or(-1.0, |id| id as f64)),
+        "num_cpus" => Value::Number(genotype.num_cpus as f64),
+        "total_cpus" => Value::Number(genotype.total_cpus as f64),
+        "length" => Value::Number(genotype.genome.len() as f64),
+        "update_born" => Value::Number(genotype.update_born as f64),
+        "depth" => Value::Number(genotype.depth as f64),
+        "sequence" => Value::Text(genome_to_string(&genotype.genome)),
+        _ => return Err(format!("unknown stat '{}'", name)),
+    })
+}
+
+/// Comparison used by FILTER
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Relation {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "==" => Ok(Relation::Eq),
+            "!=" => Ok(Relation::Ne),
+            "<" => Ok(Relation::Lt),
+            "<=" => Ok(Relation::Le),
+            ">" => Ok(Relation::Gt),
+            ">=" => Ok(Relation::Ge),
+            _ => Err(format!(
+                "unknown relation '{}' (expected ==, !=, <, <=, > or >=)",
+                s
+            )),
+        }
+    }
+
+    fn holds(self, value: &Value, target: &str) -> Result<bool, String> {
+        match value {
+            Value::Number(n) => {
+                let target: f64 = target
+                    .parse()
+                    .map_err(|_| format!("invalid number '{}'", target))?;
+                Ok(match self {
+                    Relation::Eq => *n == target,
+                    Relation::Ne => *n != target,
+                    Relation::Lt => *n < target,
+                    Relation::Le => *n <= target,
+                    Relation::Gt => *n > target,
+                    Relation::Ge => *n >= target,
+                })
+            }
+            Value::Text(s) => match self {
+                Relation::Eq => Ok(s == target),
+                Relation::Ne => Ok(s != target),
+                _ => Err("text stats only support == and !=".to_string()),
+            },
+        }
+    }
+}
+
+/// Genotype picked by FIND_GENOTYPE and FIND_LINEAGE
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selector {
+    /// The genotype with the most living organisms (first in the batch on ties)
+    MostAbundant,
+    Id(u64),
+}
+
+/// One analyze command
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Add a genome given as a sequence string
+    LoadSequence(Vec<Instruction>),
+    /// Add a genome from a sequence or Avida .org file
+    LoadOrganism(PathBuf),
+    /// Add the genotypes of a .spop file
+    Load(PathBuf),
+    /// Add every genotype of a checkpoint's registry, including extinct ancestors
+    LoadCheckpoint(PathBuf),
+    /// Run every genotype in the test CPU
+    Recalculate,
+    /// Keep genotypes whose stat satisfies the relation
+    Filter {
+        stat: String,
+        relation: Relation,
+        value: String,
+    },
+    /// Keep a single genotype
+    FindGenotype(Selector),
+    /// Keep the line of descent of a genotype, ancestor first
+    FindLineage(Selector),
+    /// Write the chosen stats of every genotype to a file
+    Detail { file: PathBuf, stats: Vec<String> },
+    /// Write a knockout table for every genotype (`knockout-<id>.dat` in a directory)
+    Knockouts(Option<PathBuf>),
+    /// Write the mutational landscape of every genotype (`landscape-<id>.dat`),
+    /// sampling this many double mutants
+    Landscape(usize),
+    /// Empty the batch
+    PurgeBatch,
+}
+
+fn parse_selector(command: &str, args: &[&str]) -> Result<Selector, String> {
+    match args {
+        [] | ["num_cpus"] => Ok(Selector::MostAbundant),
+        [id] => id.parse().map(Selector::Id).map_err(|_| {
+            format!(
+                "{}: expected num_cpus or a genotype id, got '{}'",
+                command, id
+            )
+        }),
+        _ => Err(format!("{} takes at most one argument", command)),
+    }
+}
+
+fn parse_command(name: &str, args: &[&str]) -> Result<Command, String> {
+    let path = |command: &str| -> Result<PathBuf, String> {
+        match args {
+            [file] => Ok(PathBuf::from(file)),
+            _ => Err(format!("{} needs exactly one file", command)),
+        }
+    };
+    let no_args = |command: Command| -> Result<Command, String> {
+        if args.is_empty() {
+            Ok(command)
+        } else {
+            Err(format!("{} takes no arguments", name))
+        }
+    };
+
+    match name.to_ascii_uppercase().as_str() {
+        "LOAD_SEQUENCE" => match args {
+            [sequence] => Ok(Command::LoadSequence(parse_genome(sequence)?)),
+            _ => Err("LOAD_SEQUENCE needs exactly one sequence".to_string()),
+        },
+        "LOAD_ORGANISM" => Ok(Command::LoadOrganism(path("LOAD_ORGANISM")?)),
+        "LOAD" => Ok(Command::Load(path("LOAD")?)),
+        "LOAD_CHECKPOINT" => Ok(Command::LoadCheckpoint(path("LOAD_CHECKPOINT")?)),
+        "RECALCULATE" | "RECALC" => no_args(Command::Recalculate),
+        "FILTER" => match args {
+            [stat, relation, value] => {
+                check_stat(stat)?;
+                Ok(Command::Filter {
+                    stat: stat.to_string(),
+                    relation: Relation::parse(relation)?,
+                    value: value.to_string(),
+                })
+            }
+            _ => Err("FILTER needs '<stat> <relation> <value>'".to_string()),
+        },
+        "FIND_GENOTYPE" => Ok(Command::FindGenotype(parse_selector(
+            "FIND_GENOTYPE",
+            args,
+        )?)),
+        "FIND_LINEAGE" => Ok(Command::FindLineage(parse_selector("FIND_LINEAGE", args)?)),
+        "DETAIL" => match args {
+            [file, stats @ ..] if !stats.is_empty() => {
+                for stat in stats {
+                    check_stat(stat)?;
+                }
+                Ok(Command::Detail {
+                    file: PathBuf::from(file),
+                    stats: stats.iter().map(|stat| stat.to_string()).collect(),
+                })
+            }
+            _ => Err("DETAIL needs a file and at least one stat".to_string()),
+        },
+        "KNOCKOUTS" => match args {
+            [] => Ok(Command::Knockouts(None)),
+            [dir] => Ok(Command::Knockouts(Some(PathBuf::from(dir)))),
+            _ => Err("KNOCKOUTS takes at most one directory".to_string()),
+        },
+        "LANDSCAPE" => match args {
+            [] => Ok(Command::Landscape(0)),
+            [samples] => samples
+                .parse()
+                .map(Command::Landscape)
+                .map_err(|_| format!("LANDSCAPE: invalid sample count '{}'", samples)),
+            _ => Err("LANDSCAPE takes at most a double mutant sample count".to_string()),
+        },
+        "PURGE_BATCH" => no_args(Command::PurgeBatch),
+        _ => Err(format!("unknown command '{}'", name)),
+    }
+}
+
+/// Parse an analyze script
+pub fn parse(contents: &str) -> Result<Vec<Command>, String> {
+    let mut commands = Vec::new();
+    for (line_num, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let command = parse_command(parts[0], &parts[1..])
+            .map_err(|e| format!("line {}: {}", line_num + 1, e))?;
+        commands.push(command);
+    }
+    Ok(commands)
+}
+
+/// Runs analyze commands against a batch of genotypes
+pub struct Analyzer {
+    pub batch: Vec<AnalyzeGenotype>,
+    pub test_cpu: TestCpu,
+    /// Directory that relative input and output paths are resolved against
+    base_dir: PathBuf,
+}
+
+impl Analyzer {
+    pub fn new(base_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            batch: Vec::new(),
+            test_cpu: TestCpu::default(),
+            base_dir: base_dir.into(),
+        }
+    }
+
+    fn path(&self, file: &Path) -> PathBuf {
+        self.base_dir.join(file)
+    }
+
+    fn next_id(&self) -> u64 {
+        self.batch.iter().map(|g| g.id + 1).max().unwrap_or(1)
+    }
+
+    fn select(&self, selector: Selector) -> Result<usize, String> {
+        match selector {
+            Selector::MostAbundant => self
+                .batch
+                .iter()
+                .enumerate()
+                .rev()
+                .max_by_key(|(_, genotype)| genotype.num_cpus)
+                .map(|(i, _)| i)
+                .ok_or_else(|| "the batch is empty".to_string()),
+            Selector::Id(id) => self
+                .batch
+                .iter()
+                .position(|genotype| genotype.id == id)
+                .ok_or_else(|| format!("genotype {} is not in the batch", id)),
+        }
+    }
+
+    /// Run one command
+    pub fn execute(&mut self, command: &Command) -> Result<(), String> {
+        match command {
+            Command::LoadSequence(genome) => {
+                let genotype = AnalyzeGenotype::new(self.next_id(), genome.clone());
+                self.batch.push(genotype);
+            }
+            Command::LoadOrganism(file) => {
+                let path = self.path(file);
+                let contents = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                let genome = parse_organism_file(&contents)
+                    .map_err(|e| format!("{}: {}", path.display(), e))?;
+                let genotype = AnalyzeGenotype::new(self.next_id(), genome);
+                self.batch.push(genotype);
+            }
+            Command::Load(file) => {
+                let path = self.path(file);
+                let contents = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                let genotypes =
+                    spop::parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+                for genotype in genotypes {
+                    self.batch.push(AnalyzeGenotype {
+                        parent_id: genotype.parent_ids.first().copied(),
+                        num_cpus: genotype.num_units,
+                        update_born: genotype.update_born,
+                        ..AnalyzeGenotype::new(genotype.id, genotype.genome)
+                    });
+                }
+            }
+            Command::LoadCheckpoint(file) => {
+                let world = checkpoint::load(self.path(file))?;
+                for genotype in world.genotypes().iter() {
+                    self.batch.push(AnalyzeGenotype {
+                        parent_id: genotype.parent_id,
+                        num_cpus: genotype.abundance,
+                        total_cpus: genotype.total_organisms,
+                        update_born: genotype.update_born as i64,
+                        depth: genotype.depth,
+                        ..AnalyzeGenotype::new(genotype.id, genotype.genome.clone())
+                    });
+                }
+            }
+            Command::Recalculate => {
+                for genotype in &mut self.batch {
+                    genotype.phenotype = Some(self.test_cpu.evaluate(&genotype.genome));
+                }
+            }
+            Command::Filter {
+                stat: name,
+                relation,
+                value,
+            } => {
+                let keep = self
+                    .batch
+                    .iter()
+                    .map(|genotype| relation.holds(&stat(genotype, name)?, value))
+                    .collect::<Result<Vec<bool>, String>>()?;
+                let mut keep = keep.into_iter();
+                self.batch.retain(|_| keep.next().unwrap_or(false));
+            }
+            Command::FindGenotype(selector) => {
+                let genotype = self.batch.swap_remove(self.select(*selector)?);
+                self.batch = vec![genotype];
+            }
+            Command::FindLineage(selector) => {
+                let mut next = Some(self.batch[self.select(*selector)?].id);
+                let mut by_id: HashMap<u64, AnalyzeGenotype> = self
+                    .batch
+                    .drain(..)
+                    .map(|genotype| (genotype.id, genotype))
+                    .collect();
+                let mut lineage = Vec::new();
+                while let Some(genotype) = next.and_then(|id| by_id.remove(&id)) {
+                    next = genotype.parent_id;
+                    lineage.push(genotype);
+                }
+                lineage.reverse();
+                self.batch = lineage;
+            }
+            Command::Detail { file, stats } => {
+                let path = self.path(file);
+                std::fs::write(&path, self.detail(stats)?)
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            }
+            Command::Knockouts(dir) => {
+                let dir = self.path(dir.as_deref().unwrap_or(Path::new("")));
+                for genotype in &self.batch {
+                    let path = dir.join(format!("knockout-{}.dat", genotype.id));
+                    let report = knockout::knockout(&genotype.genome, &self.test_cpu);
+                    std::fs::write(&path, report.format())
+                        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+                }
+            }
+            Command::Landscape(samples) => {
+                let mut rng = ChaCha8Rng::seed_from_u64(self.test_cpu.seed);
+                for genotype in &self.batch {
+                    let landscape = landscape::landscape(&genotype.genome, &self.test_cpu);
+                    let doubles = (*samples > 0).then(|| {
+                        landscape.sample_double_mutants(&self.test_cpu, *samples, &mut rng)
+                    });
+                    let path = self.path(Path::new(&format!("landscape-{}.dat", genotype.id)));
+                    std::fs::write(&path, landscape.format(doubles.as_ref()))
+                        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+                }
+            }
+            Command::PurgeBatch => self.batch.clear(),
+        }
+        Ok(())
+    }
+
+    /// Detail table of the batch: numbered stat descriptions, a blank line, then one row per genotype
+    pub fn detail(&self, stats: &[String]) -> Result<String, String> {
+        let mut out = String::from("# avida-rs analyze detail\n");
+        for (i, name) in stats.iter().enumerate() {
+            let _ = writeln!(out, "# {:2}: {}", i + 1, stat_description(name));
+        }
+        out.push('\n');
+        for genotype in &self.batch {
+            let values = stats
+                .iter()
+                .map(|name| stat(genotype, name).map(|value| value.to_string()))
+                .collect::<Result<Vec<_>, _>>()?;
+            out.push_str(&values.join(" "));
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    /// Run commands in order, stopping at the first error
+    pub fn run(&mut self, commands: &[Command]) -> Result<(), String> {
+        for command in commands {
+            self.execute(command)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run an analyze script; relative paths in it are resolved against its directory
+pub fn run_file(path: impl AsRef<Path>) -> Result<Analyzer, String> {
+    let path = path.as_ref();
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let commands = parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut analyzer = Analyzer::new(path.parent().unwrap_or(Path::new(".")));
+    analyzer.run(&commands)?;
+    Ok(analyzer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lineage;
+    use crate::organism::Organism;
+    use crate::world::World;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("avida-rs-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse() {
+        let commands = parse(
+            "# comment\n\
+             load_sequence rutyabsvacccccccccccccbc\n\
+             RECALC\n\
+             FILTER task.EQU == 1\n\
+             FIND_LINEAGE 12\n\
+             DETAIL out.dat id fitness task.8\n",
+        )
+        .unwrap();
+        assert_eq!(commands.len(), 5);
+        assert_eq!(commands[1], Command::Recalculate);
+        assert_eq!(commands[3], Command::FindLineage(Selector::Id(12)));
+        assert_eq!(
+            commands[4],
+            Command::Detail {
+                file: PathBuf::from("out.dat"),
+                stats: vec!["id".into(), "fitness".into(), "task.8".into()],
+            }
+        );
+
+        assert!(parse("DETAIL out.dat bogus")
+            .unwrap_err()
+            .contains("line 1: unknown stat"));
+        assert!(parse("\nFROB")
+            .unwrap_err()
+            .starts_with("line 2: unknown command"));
+        assert!(parse("FILTER fitness ~ 1").is_err());
+        assert!(parse("RECALCULATE now").is_err());
+        assert!(parse("LOAD_SEQUENCE ab!").is_err());
+        assert!(parse("FIND_GENOTYPE best").is_err());
+        assert!(parse("LANDSCAPE many").is_err());
+    }
+
+    #[test]
+    fn test_recalculate_filter_detail() {
+        let dir = temp_dir("analyze");
+        let mut analyzer = Analyzer::new(&dir);
+        let commands = parse(&format!(
+            "LOAD_SEQUENCE {}\n\
+             LOAD_SEQUENCE cccccccccccccccccccc\n\
+             DETAIL before.dat id length\n\
+             RECALCULATE\n\
+             FILTER viable == 1\n\
+             DETAIL after.dat id viable self_replicating gest_time task.NOT sequence\n\
+             KNOCKOUTS\n\
+             LANDSCAPE 10\n",
+            Organism::ancestor().genome_string()
+        ))
+        .unwrap();
+        analyzer.run(&commands).unwrap();
+
+        assert_eq!(analyzer.batch.len(), 1);
+        let before = std::fs::read_to_string(dir.join("before.dat")).unwrap();
+        assert!(before.starts_with("# avida-rs analyze detail\n#  1: Genotype ID\n"));
+        assert!(before.ends_with("\n1 50\n2 20\n"));
+
+        let after = std::fs::read_to_string(dir.join("after.dat")).unwrap();
+        let gestation = TestCpu::default()
+            .evaluate(&Organism::ancestor().genome)
+            .gestation_time;
+        assert!(after.ends_with(&format!(
+            "\n1 1 1 {} 0 {}\n",
+            gestation,
+            Organism::ancestor().genome_string()
+        )));
+        let knockouts = std::fs::read_to_string(dir.join("knockout-1.dat")).unwrap();
+        assert_eq!(knockouts.lines().count(), 52);
+        let landscape = std::fs::read_to_string(dir.join("landscape-1.dat")).unwrap();
+        assert!(landscape.contains("mutants 1250\n"));
+        assert!(landscape.contains("double_samples 10\n"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_phenotype_stats_need_recalculate() {
+        let mut analyzer = Analyzer::new(".");
+        analyzer
+            .run(&parse("LOAD_SEQUENCE rutyabsvacccccccccccccbc").unwrap())
+            .unwrap();
+        let err = analyzer
+            .run(&parse("FILTER fitness > 0").unwrap())
+            .unwrap_err();
+        assert!(err.contains("needs RECALCULATE"));
+        assert!(analyzer
+            .run(&parse("FILTER sequence > abc").unwrap())
+            .is_err());
+        assert!(analyzer.run(&parse("FIND_GENOTYPE 7").unwrap()).is_err());
+        // Failed commands leave the batch untouched
+        assert_eq!(analyzer.batch.len(), 1);
+    }
+
+    #[test]
+    fn test_lineage_from_checkpoint() {
+        let mut world = World::with_dimensions_and_seed(20, 20, 6);
+        world.copy_mutation_rate = 0.02;
+        world.inject_ancestor_with_tasks();
+        for _ in 0..300 {
+            world.update();
+        }
+        let dir = temp_dir("analyze-checkpoint");
+        checkpoint::save(&world, dir.join("checkpoint.json")).unwrap();
+        std::fs::write(
+            dir.join("lineage.cfg"),
+            "LOAD_CHECKPOINT checkpoint.json\nFIND_LINEAGE num_cpus\n",
+        )
+        .unwrap();
+
+        let analyzer = run_file(dir.join("lineage.cfg")).unwrap();
+        let expected: Vec<u64> = lineage::dominant_line_of_descent(&world)
+            .iter()
+            .map(|step| step.genotype.id)
+            .collect();
+        let ids: Vec<u64> = analyzer.batch.iter().map(|g| g.id).collect();
+        assert_eq!(ids, expected);
+        assert_eq!(analyzer.batch[0].parent_id, None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,987 @@
+//! Headless batch runs configured from the command line
+//! Used by the `batch` binary to run replicate experiments without the egui front-end
+
+use crate::checkpoint;
+use crate::config::AvidaConfig;
+use crate::data::DataRecorder;
+use crate::environment;
+use crate::events::{self, EventSchedule};
+use crate::genotype::{self, GenotypeReport};
+use crate::instset::InstSet;
+use crate::lineage;
+use crate::organism::Organism;
+use crate::spop;
+use crate::systematics;
+use crate::tasks::{Task, TaskEnvironment};
+use crate::world::World;
+use rand::Rng;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: batch [OPTIONS]
+
+Options:
+  --config <FILE>              Load settings from an Avida avida.cfg file (other flags override it)
+  --set <KEY> <VALUE>          Override a single avida.cfg setting, e.g. --set WORLD_X 100
+  --updates <N>                Number of updates to run (default 10000)
+  --world-x <N>                World width in cells (default 60)
+  --world-y <N>                World height in cells (default 60)
+  --seed <N>                   Random seed (default: drawn from entropy and reported)
+  --copy-mutation-rate <P>     Per-instruction copy mutation probability (default 0.0075)
+  --insertion-rate <P>         Insertion probability per division (default 0.05)
+  --deletion-rate <P>          Deletion probability per division (default 0.05)
+  --death-method <0|1|2>       0 = none, 1 = fixed age, 2 = age limit x genome length (default 2)
+  --age-limit <N>              Age limit used by the death method (default 20)
+  --prefer-empty <true|false>  Prefer empty cells when placing offspring (default true)
+  --instset <FILE>             Avida instset file with the instructions, mutation weights and costs to use
+  --environment <FILE>         Avida environment.cfg with the resources and task reactions to use (or logic9 / logic77 / math for a built-in one)
+  --ancestor <FILE>            Ancestor genome file (sequence in the instruction set's letters or Avida .org listing)
+  --load-population <FILE>     Start from an Avida .spop population instead of a single ancestor
+  --events <FILE>              Avida events.cfg with scheduled actions (files are written to the output dir)
+  --output-dir <DIR>           Directory for run output (summary.txt, avida.cfg, instset.cfg, environment.cfg, population.spop, phylogeny.nwk/.csv, lineage.txt)
+  --data-interval <N>          Append to average.dat, count.dat, dominant.dat and tasks.dat every N updates, 0 to disable (default 100)
+  --data-csv <true|false>      Also write the data files as CSV (default false)
+  --checkpoint-interval <N>    Save a checkpoint every N updates and at the end, 0 to disable (default 0)
+  --load-checkpoint <FILE>     Continue from a saved checkpoint (world settings come from the checkpoint)
+  --print-interval <N>         Print progress every N updates, 0 to disable (default 100)
+  -h, --help                   Print this help";
+
+/// Options for a headless batch run
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchOptions {
+    pub updates: u64,
+    /// World settings (avida.cfg values, overridden by command-line flags)
+    pub config: AvidaConfig,
+    /// Instruction set file (the avida-rs set if None)
+    pub instset: Option<PathBuf>,
+    /// Environment file or preset name (the Logic-9 environment if None)
+    pub environment: Option<PathBuf>,
+    pub ancestor: Option<PathBuf>,
+    /// Structured population (.spop) to start from instead of the ancestor
+    pub population: Option<PathBuf>,
+    /// Events file with scheduled actions
+    pub events: Option<PathBuf>,
+    pub output_dir: Option<PathBuf>,
+    /// Append a row to the data files every N updates (0 = never)
+    pub data_interval: u64,
+    /// Write .csv copies of the data files
+    pub data_csv: bool,
+    pub print_interval: u64,
+    /// Save a checkpoint to the output directory every N updates (0 = never)
+    pub checkpoint_interval: u64,
+    /// Checkpoint to continue from instead of building a new world
+    pub load_checkpoint: Option<PathBuf>,
+    pub help: bool,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            updates: 10_000,
+            config: AvidaConfig::default(),
+            instset: None,
+            environment: None,
+            ancestor: None,
+            population: None,
+            events: None,
+            output_dir: None,
+            data_interval: 100,
+            data_csv: false,
+            print_interval: 100,
+            checkpoint_interval: 0,
+            load_checkpoint: None,
+            help: false,
+        }
+    }
+}
+
+/// Flags that change world settings, which come from the checkpoint with `--load-checkpoint`
+const WORLD_SETTING_FLAGS: &[&str] = &[
+    "--config",
+    "--set",
+    "--world-x",
+    "--world-y",
+    "--seed",
+    "--copy-mutation-rate",
+    "--insertion-rate",
+    "--deletion-rate",
+    "--death-method",
+    "--age-limit",
+    "--prefer-empty",
+    "--instset",
+    "--environment",
+];
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for {}", value, flag))
+}
+
+/// Split `--flag=value` into its parts
+fn split_flag(arg: &str) -> (String, Option<String>) {
+    match arg.split_once('=') {
+        Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+        None => (arg.to_string(), None),
+    }
+}
+
+impl BatchOptions {
+    /// Parse options from command-line arguments (excluding the program name)
+    /// Accepts both `--flag value` and `--flag=value`. A `--config` file is loaded
+    /// first regardless of its position, so other flags always override it.
+    pub fn parse<I, S>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let args: Vec<String> = args.into_iter().map(Into::into).collect();
+        let mut options = Self::default();
+
+        for (i, arg) in args.iter().enumerate() {
+            let (flag, inline_value) = split_flag(arg);
+            if flag == "--config" {
+                let path = inline_value
+                    .or_else(|| args.get(i + 1).cloned())
+                    .ok_or_else(|| "Missing value for --config".to_string())?;
+                options.config = AvidaConfig::from_file(path)?;
+            }
+        }
+
+        let mut world_setting_flag = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                options.help = true;
+                continue;
+            }
+
+            let (flag, inline_value) = split_flag(&arg);
+            if world_setting_flag.is_none() && WORLD_SETTING_FLAGS.contains(&flag.as_str()) {
+                world_setting_flag = Some(flag.clone());
+            }
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("Missing value for {}", flag))
+            };
+
+            let config = &mut options.config;
+            let mut setting = |key: &str, value: String| {
+                config
+                    .set(key, &value)
+                    .map_err(|e| format!("{}: {}", flag, e))
+            };
+
+            match flag.as_str() {
+                "--config" => {
+                    value()?;
+                }
+                "--set" => {
+                    let key = value()?;
+                    let setting_value = value()?;
+                    setting(&key, setting_value)?;
+                }
+                "--updates" => options.updates = parse_value(&flag, &value()?)?,
+                "--world-x" => setting("WORLD_X", value()?)?,
+                "--world-y" => setting("WORLD_Y", value()?)?,
+                "--seed" => setting("RANDOM_SEED", value()?)?,
+                "--copy-mutation-rate" => setting("COPY_MUT_PROB", value()?)?,
+                "--insertion-rate" => setting("DIVIDE_INS_PROB", value()?)?,
+                "--deletion-rate" => setting("DIVIDE_DEL_PROB", value()?)?,
+                "--death-method" => setting("DEATH_METHOD", value()?)?,
+                "--age-limit" => setting("AGE_LIMIT", value()?)?,
+                "--prefer-empty" => {
+                    let prefer: bool = parse_value(&flag, &value()?)?;
+                    setting("PREFER_EMPTY", (prefer as u8).to_string())?;
+                }
+                "--instset" => options.instset = Some(PathBuf::from(value()?)),
+                "--environment" => options.environment = Some(PathBuf::from(value()?)),
+                "--ancestor" => options.ancestor = Some(PathBuf::from(value()?)),
+                "--load-population" => options.population = Some(PathBuf::from(value()?)),
+                "--events" => options.events = Some(PathBuf::from(value()?)),
+                "--output-dir" => options.output_dir = Some(PathBuf::from(value()?)),
+                "--data-interval" => options.data_interval = parse_value(&flag, &value()?)?,
+                "--data-csv" => options.data_csv = parse_value(&flag, &value()?)?,
+                "--print-interval" => options.print_interval = parse_value(&flag, &value()?)?,
+                "--checkpoint-interval" => {
+                    options.checkpoint_interval = parse_value(&flag, &value()?)?
+                }
+                "--load-checkpoint" => options.load_checkpoint = Some(PathBuf::from(value()?)),
+                _ => return Err(format!("Unknown option: {}", arg)),
+            }
+        }
+
+        if let (Some(flag), Some(_)) = (world_setting_flag, &options.load_checkpoint) {
+            return Err(format!(
+                "{} cannot be combined with --load-checkpoint (world settings come from the checkpoint)",
+                flag
+            ));
+        }
+
+        Ok(options)
+    }
+
+    /// Load the instruction set (from file if given, otherwise the avida-rs set)
+    pub fn load_instset(&self) -> Result<InstSet, String> {
+        match &self.instset {
+            Some(path) => InstSet::from_file(path),
+            None => Ok(InstSet::default()),
+        }
+    }
+
+    /// Load the task environment (a preset or file if given, otherwise Logic-9), with
+    /// spatial resources sized to a `width` × `height` world
+    pub fn load_environment(&self, width: usize, height: usize) -> Result<TaskEnvironment, String> {
+        let mut environment = match &self.environment {
+            Some(path) => match path.to_str().and_then(environment::preset) {
+                Some(preset) => preset,
+                None => environment::load(path)?,
+            },
+            None => TaskEnvironment::default_logic9(),
+        };
+        environment.resize_resources(width, height);
+        Ok(environment)
+    }
+
+    /// Load the ancestor organism (from file if given, otherwise the default ancestor)
+    /// Sequences are read with the letters of `inst_set`
+    pub fn load_ancestor(&self, inst_set: &InstSet) -> Result<Organism, String> {
+        match &self.ancestor {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Failed to read ancestor {}: {}", path.display(), e))?;
+                let genome = inst_set
+                    .parse_organism(&contents)
+                    .map_err(|e| format!("Invalid ancestor {}: {}", path.display(), e))?;
+                if genome.is_empty() {
+                    return Err(format!("Ancestor {} is empty", path.display()));
+                }
+                Ok(Organism::new(genome))
+            }
+            None => Ok(Organism::ancestor()),
+        }
+    }
+
+    /// Build a world with these settings and the ancestor injected at the center
+    /// If no seed was configured one is drawn, so the returned config always
+    /// records the seed the world was created with
+    /// With `load_checkpoint` the world (and its settings) come from the checkpoint instead
+    pub fn build_world(&self) -> Result<(World, AvidaConfig), String> {
+        if let Some(path) = &self.load_checkpoint {
+            if self.ancestor.is_some() || self.population.is_some() {
+                return Err(
+                    "--ancestor and --load-population cannot be combined with --load-checkpoint"
+                        .to_string(),
+                );
+            }
+            let world = checkpoint::load(path)?;
+            let mut config = AvidaConfig::from_world(&world);
+            config.random_seed = i64::try_from(world.seed()).map_err(|_| {
+                format!(
+                    "Checkpoint seed {} does not fit in RANDOM_SEED (at most {})",
+                    world.seed(),
+                    i64::MAX
+                )
+            })?;
+            return Ok((world, config));
+        }
+
+        let mut config = self.config.clone();
+        if config.seed().is_none() {
+            config.random_seed = rand::thread_rng().gen_range(1..=i64::MAX);
+        }
+        let mut world = config.build_world()?;
+        world.inst_set = self.load_instset()?;
+        let (width, height) = world.dimensions();
+        world.task_env = self.load_environment(width, height)?;
+
+        if let Some(path) = &self.population {
+            if self.ancestor.is_some() {
+                return Err("--ancestor and --load-population cannot be combined".to_string());
+            }
+            spop::load(&mut world, path)?;
+        } else {
+            let ancestor = self.load_ancestor(&world.inst_set)?;
+            let (width, height) = world.dimensions();
+            world.inject_organism(ancestor, width / 2, height / 2);
+        }
+
+        Ok((world, config))
+    }
+}
+
+/// Final statistics of a batch run
+#[derive(Debug, Clone)]
+pub struct RunSummary {
+    pub seed: u64,
+    pub updates: u64,
+    pub population: usize,
+    pub total_births: u64,
+    pub total_deaths: u64,
+    pub average_genome_size: f64,
+    pub average_merit: f64,
+    pub average_fitness: f64,
+    /// Organisms that performed each of the environment's tasks
+    pub task_counts: Vec<(Task, usize)>,
+    /// Number of living genotypes
+    pub genotypes: usize,
+    /// The most abundant genotype at the end of the run
+    pub dominant: Option<GenotypeReport>,
+    pub extinct: bool,
+}
+
+impl RunSummary {
+    fn from_world(world: &World, seed: u64) -> Self {
+        Self {
+            seed,
+            updates: world.total_updates,
+            population: world.population_size,
+            total_births: world.total_births,
+            total_deaths: world.total_deaths,
+            average_genome_size: world.average_genome_size(),
+            average_merit: world.average_merit(),
+            average_fitness: world.average_fitness(),
+            task_counts: {
+                let counts = world.task_statistics();
+                world
+                    .task_env
+                    .tasks()
+                    .iter()
+                    .map(|task| (task, counts[task.index()]))
+                    .collect()
+            },
+            genotypes: world.genotypes().living_count(),
+            dominant: genotype::dominant_report(world),
+            extinct: world.population_size == 0,
+        }
+    }
+
+    /// Format the summary as `key value` lines
+    pub fn format(&self) -> String {
+        let mut out = String::from("# avida-rs batch run summary\n");
+        let _ = writeln!(out, "seed {}", self.seed);
+        let _ = writeln!(out, "updates {}", self.updates);
+        let _ = writeln!(out, "population {}", self.population);
+        let _ = writeln!(out, "total_births {}", self.total_births);
+        let _ = writeln!(out, "total_deaths {}", self.total_deaths);
+        let _ = writeln!(out, "average_genome_size {:.4}", self.average_genome_size);
+        let _ = writeln!(out, "average_merit {:.4}", self.average_merit);
+        let _ = writeln!(out, "average_fitness {:.6}", self.average_fitness);
+        for (task, count) in &self.task_counts {
+            let _ = writeln!(out, "task_{} {}", task.name(), count);
+        }
+        let _ = writeln!(out, "genotypes {}", self.genotypes);
+        if let Some(dominant) = &self.dominant {
+            for line in dominant.format().lines() {
+                let _ = writeln!(out, "dominant_{}", line);
+            }
+        }
+        let _ = writeln!(out, "extinct {}", self.extinct);
+        out
+    }
+}
+
+/// Run the configured experiment to completion
+/// Stops early if the population goes extinct
+pub fn run(options: &BatchOptions) -> Result<RunSummary, String> {
+    if options.checkpoint_interval > 0 && options.output_dir.is_none() {
+        return Err("--checkpoint-interval requires --output-dir".to_string());
+    }
+
+    let (mut world, config) = options.build_world()?;
+    let seed = world.seed();
+
+    if let Some(path) = &options.events {
+        world.events = EventSchedule::new(events::load(path, &world.inst_set)?);
+        world.events.output_dir = options.output_dir.clone();
+    }
+
+    let mut recorder = None;
+    if let Some(dir) = &options.output_dir {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create output dir {}: {}", dir.display(), e))?;
+
+        // Record the exact settings (including the seed) so the run can be repeated
+        let path = dir.join("avida.cfg");
+        std::fs::write(&path, config.format())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        let path = dir.join("instset.cfg");
+        std::fs::write(&path, world.inst_set.format())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        let path = dir.join("environment.cfg");
+        std::fs::write(&path, environment::format(&world.task_env))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+        if options.data_interval > 0 {
+            recorder = Some(DataRecorder::create(
+                dir,
+                options.data_interval,
+                options.data_csv,
+                world.task_env.tasks(),
+            )?);
+        }
+    }
+
+    let record = |world: &World| match &recorder {
+        Some(recorder) if recorder.is_due(world.total_updates) => recorder.record(world),
+        _ => Ok(()),
+    };
+    record(&world)?;
+
+    for _ in 0..options.updates {
+        world.update();
+        if let Some(e) = world.events.errors.first() {
+            return Err(format!("Event failed at {}", e));
+        }
+        if world.events.exit_requested {
+            break;
+        }
+        record(&world)?;
+
+        if options.print_interval > 0 && world.total_updates.is_multiple_of(options.print_interval)
+        {
+            println!(
+                "update {} pop {} births {} deaths {} avg_size {:.1} avg_merit {:.2}",
+                world.total_updates,
+                world.population_size,
+                world.total_births,
+                world.total_deaths,
+                world.average_genome_size(),
+                world.average_merit()
+            );
+        }
+
+        if let Some(dir) = &options.output_dir {
+            if options.checkpoint_interval > 0
+                && world
+                    .total_updates
+                    .is_multiple_of(options.checkpoint_interval)
+            {
+                let path = dir.join(format!("checkpoint-{}.json", world.total_updates));
+                checkpoint::save(&world, path)?;
+            }
+        }
+
+        if world.population_size == 0 {
+            break;
+        }
+    }
+
+    if let Some(dir) = &options.output_dir {
+        if options.checkpoint_interval > 0 {
+            checkpoint::save(&world, dir.join("checkpoint.json"))?;
+        }
+    }
+
+    let summary = RunSummary::from_world(&world, seed);
+
+    if let Some(dir) = &options.output_dir {
+        spop::save(&world, dir.join("population.spop"))?;
+        systematics::save_newick(&world, dir.join("phylogeny.nwk"))?;
+        systematics::save_csv(&world, dir.join("phylogeny.csv"))?;
+        lineage::save_dominant(&world, dir.join("lineage.txt"))?;
+
+        let path = dir.join("summary.txt");
+        std::fs::write(&path, summary.format())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_defaults() {
+        let options = BatchOptions::parse(Vec::<String>::new()).unwrap();
+        assert_eq!(options, BatchOptions::default());
+        assert_eq!(options.updates, 10_000);
+        assert_eq!(options.config.seed(), None);
+    }
+
+    #[test]
+    fn test_parse_all_options() {
+        let options = BatchOptions::parse([
+            "--updates",
+            "500",
+            "--seed=7",
+            "--world-x",
+            "10",
+            "--world-y=20",
+            "--copy-mutation-rate",
+            "0.01",
+            "--insertion-rate",
+            "0",
+            "--deletion-rate=0",
+            "--death-method",
+            "1",
+            "--age-limit",
+            "5000",
+            "--prefer-empty",
+            "false",
+            "--set",
+            "VERSION_ID",
+            "2.14.0",
+            "--instset",
+            "instset.cfg",
+            "--environment",
+            "environment.cfg",
+            "--ancestor",
+            "anc.org",
+            "--load-population",
+            "start.spop",
+            "--events",
+            "events.cfg",
+            "--output-dir",
+            "out",
+            "--print-interval",
+            "0",
+            "--checkpoint-interval",
+            "250",
+            "--data-interval",
+            "50",
+            "--data-csv=true",
+        ])
+        .unwrap();
+
+        assert_eq!(options.updates, 500);
+        assert_eq!(options.config.seed(), Some(7));
+        assert_eq!((options.config.world_x, options.config.world_y), (10, 20));
+        assert_eq!(options.config.copy_mut_prob, 0.01);
+        assert_eq!(options.config.divide_ins_prob, 0.0);
+        assert_eq!(options.config.divide_del_prob, 0.0);
+        assert_eq!(options.config.death_method, 1);
+        assert_eq!(options.config.age_limit, 5000);
+        assert!(!options.config.prefer_empty);
+        assert_eq!(options.config.ignored, vec!["VERSION_ID".to_string()]);
+        assert_eq!(options.instset, Some(PathBuf::from("instset.cfg")));
+        assert_eq!(options.environment, Some(PathBuf::from("environment.cfg")));
+        assert_eq!(options.ancestor, Some(PathBuf::from("anc.org")));
+        assert_eq!(options.population, Some(PathBuf::from("start.spop")));
+        assert_eq!(options.events, Some(PathBuf::from("events.cfg")));
+        assert_eq!(options.output_dir, Some(PathBuf::from("out")));
+        assert_eq!(options.print_interval, 0);
+        assert_eq!(options.checkpoint_interval, 250);
+        assert_eq!(options.data_interval, 50);
+        assert!(options.data_csv);
+
+        let options = BatchOptions::parse(["--load-checkpoint", "run.json", "--updates", "10"]);
+        assert_eq!(
+            options.unwrap().load_checkpoint,
+            Some(PathBuf::from("run.json"))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(BatchOptions::parse(["--bogus"])
+            .unwrap_err()
+            .contains("Unknown option"));
+        assert!(BatchOptions::parse(["--updates"])
+            .unwrap_err()
+            .contains("Missing value"));
+        assert!(BatchOptions::parse(["--updates", "lots"])
+            .unwrap_err()
+            .contains("Invalid value"));
+        assert!(BatchOptions::parse(["--copy-mutation-rate", "1.5"]).is_err());
+        assert!(BatchOptions::parse(["--death-method", "3"]).is_err());
+        assert!(BatchOptions::parse(["--world-x", "0"]).is_err());
+        assert!(BatchOptions::parse(["--set", "NOT_A_SETTING", "1"])
+            .unwrap_err()
+            .contains("unknown setting"));
+        assert!(
+            BatchOptions::parse(["--load-checkpoint", "run.json", "--world-x", "10"])
+                .unwrap_err()
+                .contains("--world-x cannot be combined with --load-checkpoint")
+        );
+        assert!(
+            BatchOptions::parse(["--copy-mutation-rate=0.1", "--load-checkpoint=run.json"])
+                .unwrap_err()
+                .contains("--copy-mutation-rate cannot be combined")
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_combinations_rejected() {
+        let dir = std::env::temp_dir().join(format!("avida-rs-batch-ckpt-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("checkpoint.json");
+
+        let options = BatchOptions {
+            load_checkpoint: Some(path.clone()),
+            ..BatchOptions::default()
+        };
+        checkpoint::save(&World::with_dimensions_and_seed(5, 5, u64::MAX), &path).unwrap();
+        assert!(options
+            .build_world()
+            .err()
+            .unwrap()
+            .contains("does not fit in RANDOM_SEED"));
+
+        let with_ancestor = BatchOptions {
+            ancestor: Some(PathBuf::from("anc.org")),
+            ..options.clone()
+        };
+        assert!(with_ancestor
+            .build_world()
+            .err()
+            .unwrap()
+            .contains("cannot be combined with --load-checkpoint"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_help() {
+        assert!(BatchOptions::parse(["--help"]).unwrap().help);
+        assert!(BatchOptions::parse(["-h"]).unwrap().help);
+    }
+
+    #[test]
+    fn test_config_file_overridden_by_flags() {
+        let dir = std::env::temp_dir().join(format!("avida-rs-batch-cfg-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cfg = dir.join("avida.cfg");
+        std::fs::write(&cfg, "COPY_MUT_PROB 0.02\nAGE_LIMIT 30\n").unwrap();
+
+        let options =
+            BatchOptions::parse(["--age-limit", "40", "--config", cfg.to_str().unwrap()]).unwrap();
+        assert_eq!(options.config.copy_mut_prob, 0.02);
+        assert_eq!(options.config.age_limit, 40);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_build_world_applies_settings() {
+        let options = BatchOptions::parse([
+            "--seed",
+            "3",
+            "--copy-mutation-rate",
+            "0.02",
+            "--death-method",
+            "0",
+            "--world-x",
+            "100",
+            "--world-y",
+            "100",
+        ])
+        .unwrap();
+        let (world, config) = options.build_world().unwrap();
+
+        assert_eq!(config.seed(), Some(3));
+        assert_eq!(world.dimensions(), (100, 100));
+        assert!(world.get_organism(50, 50).is_some());
+        assert_eq!(world.copy_mutation_rate, 0.02);
+        assert_eq!(world.death_method, 0);
+        assert_eq!(world.population_size, 1);
+    }
+
+    #[test]
+    fn test_build_world_with_instset() {
+        let dir = std::env::temp_dir().join(format!("avida-rs-batch-inst-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut inst_set = InstSet::heads_default().format();
+        inst_set = inst_set.replace("INST h-alloc:redundancy=1:cost=1", "INST h-alloc:cost=5");
+        std::fs::write(dir.join("instset.cfg"), inst_set).unwrap();
+        // The C++ Avida default ancestor, in heads_default letters
+        std::fs::write(dir.join("ancestor.txt"), "wzcagcccccccccccczvfcaxgab\n").unwrap();
+
+        let options = BatchOptions::parse([
+            "--instset",
+            dir.join("instset.cfg").to_str().unwrap(),
+            "--ancestor",
+            dir.join("ancestor.txt").to_str().unwrap(),
+        ])
+        .unwrap();
+        let (world, _) = options.build_world().unwrap();
+        assert_eq!(world.inst_set.name(), "heads_default");
+        assert_eq!(
+            world.inst_set.cost(crate::instruction::Instruction::HAlloc),
+            5
+        );
+        let (width, height) = world.dimensions();
+        let ancestor = world.get_organism(width / 2, height / 2).unwrap();
+        assert_eq!(
+            ancestor.genome,
+            crate::instruction::parse_heads_default_genome("wzcagcccccccccccczvfcaxgab").unwrap()
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_build_world_with_environment() {
+        let dir = std::env::temp_dir().join(format!("avida-rs-batch-env-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("environment.cfg"),
+            "RESOURCE glucose:inflow=100:outflow=0.01:geometry=grid\n\
+             REACTION NOT not process:resource=glucose:value=2.0:frac=0.1\n",
+        )
+        .unwrap();
+
+        let options = BatchOptions::parse([
+            "--environment",
+            dir.join("environment.cfg").to_str().unwrap(),
+        ])
+        .unwrap();
+        let (world, _) = options.build_world().unwrap();
+        assert_eq!(world.task_env.reactions().len(), 1);
+        let spatial = world.task_env.resources[0].spatial.as_ref().unwrap();
+        assert_eq!((spatial.width, spatial.height), world.dimensions());
+
+        let options = BatchOptions::parse(["--environment", "missing-environment.cfg"]).unwrap();
+        assert!(options.build_world().is_err());
+
+        let options = BatchOptions::parse(["--environment", "logic77"]).unwrap();
+        let (world, _) = options.build_world().unwrap();
+        assert_eq!(world.task_env.tasks().len(), 77);
+
+        let options = BatchOptions::parse(["--environment", "math"]).unwrap();
+        let (world, _) = options.build_world().unwrap();
+        assert_eq!(world.task_env.tasks(), Task::math().into_iter().collect());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_build_world_draws_seed() {
+        let (_, config) = BatchOptions::default().build_world().unwrap();
+        assert!(config.seed().is_some());
+    }
+
+    #[test]
+    fn test_build_world_missing_ancestor_file() {
+        let options = BatchOptions::parse(["--ancestor", "/nonexistent/ancestor.org"]).unwrap();
+        assert!(options.build_world().is_err());
+    }
+
+    #[test]
+    fn test_run_writes_summary() {
+        let dir = std::env::temp_dir().join(format!("avida-rs-batch-{}", std::process::id()));
+        let ancestor = dir.join("ancestor.txt");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            &ancestor,
+            "rutyabsvacccccccccccccccccccccccccccccccccccccccbc\n",
+        )
+        .unwrap();
+
+        let options = BatchOptions::parse([
+            "--updates",
+            "20",
+            "--seed",
+            "1",
+            "--print-interval",
+            "0",
+            "--data-interval",
+            "5",
+            "--data-csv",
+            "true",
+            "--ancestor",
+            ancestor.to_str().unwrap(),
+            "--output-dir",
+            dir.to_str().unwrap(),
+        ])
+        .unwrap();
+        let summary = run(&options).unwrap();
+        assert_eq!(summary.updates, 20);
+        assert!(summary.population >= 1);
+
+        let written = std::fs::read_to_string(dir.join("summary.txt")).unwrap();
+        assert!(written.contains("seed 1"));
+        assert!(written.contains("updates 20"));
+        assert!(written.contains("task_EQU"));
+
+        let config = AvidaConfig::from_file(dir.join("avida.cfg")).unwrap();
+        assert_eq!(config, options.config);
+        let inst_set = InstSet::from_file(dir.join("instset.cfg")).unwrap();
+        assert_eq!(inst_set, InstSet::default());
+        let environment = environment::load(dir.join("environment.cfg")).unwrap();
+        assert_eq!(environment, TaskEnvironment::default_logic9());
+
+        let population = std::fs::read_to_string(dir.join("population.spop")).unwrap();
+        let genotypes = spop::parse(&population).unwrap();
+        let units: usize = genotypes.iter().map(|g| g.num_units).sum();
+        assert_eq!(units, summary.population);
+
+        let phylogeny = std::fs::read_to_string(dir.join("phylogeny.csv")).unwrap();
+        assert!(phylogeny.starts_with("id,ancestor_list,origin_time,"));
+        assert!(std::fs::read_to_string(dir.join("phylogeny.nwk"))
+            .unwrap()
+            .ends_with(";\n"));
+        let lineage = std::fs::read_to_string(dir.join("lineage.txt")).unwrap();
+        assert!(lineage.lines().nth(2).unwrap().starts_with("1 (none) 0 "));
+
+        // Rows at updates 0, 5, 10, 15 and 20
+        for name in ["average.dat", "count.dat", "tasks.dat"] {
+            let contents = std::fs::read_to_string(dir.join(name)).unwrap();
+            let rows = contents
+                .lines()
+                .filter(|line| !line.is_empty() && !line.starts_with('#'));
+            assert_eq!(rows.count(), 5, "{}", name);
+        }
+        let count = std::fs::read_to_string(dir.join("count.csv")).unwrap();
+        let last = count.lines().last().unwrap();
+        assert!(last.starts_with(&format!("20,{},", summary.population)));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_with_events() {
+        let dir =
+            std::env::temp_dir().join(format!("avida-rs-batch-events-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let events = dir.join("events.cfg");
+        std::fs::write(&events, "u 5 SaveCheckpoint\nu 10 Exit\n").unwrap();
+
+        let options = BatchOptions::parse([
+            "--updates",
+            "100",
+            "--seed",
+            "3",
+            "--print-interval",
+            "0",
+            "--events",
+            events.to_str().unwrap(),
+            "--output-dir",
+            dir.to_str().unwrap(),
+        ])
+        .unwrap();
+        let summary = run(&options).unwrap();
+        assert_eq!(summary.updates, 10);
+        assert_eq!(
+            checkpoint::load(dir.join("checkpoint-5.json"))
+                .unwrap()
+                .total_updates,
+            5
+        );
+
+        std::fs::write(&events, "u 2 SavePopulation /nonexistent/dir/pop.spop\n").unwrap();
+        let err = run(&options).err().unwrap();
+        assert!(err.contains("Event failed at update 2"), "{}", err);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_build_world_from_population() {
+        let dir = std::env::temp_dir().join(format!("avida-rs-batch-spop-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("start.spop");
+        std::fs::write(
+            &path,
+            "#format id num_units sequence cells\n\
+             1 2 wzcagcccccccccccccccccccccccccccccccccccczvfcaxgab 0,99\n",
+        )
+        .unwrap();
+
+        let options = BatchOptions::parse([
+            "--world-x",
+            "10",
+            "--world-y",
+            "10",
+            "--load-population",
+            path.to_str().unwrap(),
+        ])
+        .unwrap();
+        let (world, _) = options.build_world().unwrap();
+        assert_eq!(world.population_size, 2);
+        assert!(world.get_organism(0, 0).is_some());
+        assert!(world.get_organism(9, 9).is_some());
+
+        let mut both = options.clone();
+        both.ancestor = Some(PathBuf::from("anc.org"));
+        assert!(both
+            .build_world()
+            .err()
+            .unwrap()
+            .contains("cannot be combined"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_checkpoint_interval_requires_output_dir() {
+        let options = BatchOptions::parse(["--checkpoint-interval", "10"]).unwrap();
+        assert!(run(&options).unwrap_err().contains("--output-dir"));
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint_matches_uninterrupted_run() {
+        let dir =
+            std::env::temp_dir().join(format!("avida-rs-batch-resume-{}", std::process::id()));
+        let full_dir = dir.join("full");
+        let first_dir = dir.join("first");
+        let second_dir = dir.join("second");
+        let common = [
+            "--seed",
+            "8",
+            "--world-x",
+            "20",
+            "--world-y",
+            "20",
+            "--print-interval",
+            "0",
+        ];
+
+        let full = BatchOptions::parse(
+            common
+                .iter()
+                .copied()
+                .chain(["--updates", "120", "--checkpoint-interval", "60"])
+                .chain(["--output-dir", full_dir.to_str().unwrap()]),
+        )
+        .unwrap();
+        let full_summary = run(&full).unwrap();
+        assert!(full_dir.join("checkpoint-60.json").exists());
+        assert!(full_dir.join("checkpoint-120.json").exists());
+
+        let first = BatchOptions::parse(
+            common
+                .iter()
+                .copied()
+                .chain(["--updates", "60", "--checkpoint-interval", "60"])
+                .chain(["--output-dir", first_dir.to_str().unwrap()]),
+        )
+        .unwrap();
+        run(&first).unwrap();
+
+        let resume_from = first_dir.join("checkpoint.json");
+        let second = BatchOptions::parse([
+            "--updates",
+            "60",
+            "--print-interval",
+            "0",
+            "--checkpoint-interval",
+            "60",
+            "--load-checkpoint",
+            resume_from.to_str().unwrap(),
+            "--output-dir",
+            second_dir.to_str().unwrap(),
+        ])
+        .unwrap();
+        let second_summary = run(&second).unwrap();
+
+        assert_eq!(second_summary.seed, 8);
+        assert_eq!(second_summary.updates, full_summary.updates);
+        assert_eq!(second_summary.total_births, full_summary.total_births);
+        assert_eq!(
+            std::fs::read_to_string(second_dir.join("checkpoint.json")).unwrap(),
+            std::fs::read_to_string(full_dir.join("checkpoint.json")).unwrap()
+        );
+
+        let config = AvidaConfig::from_file(second_dir.join("avida.cfg")).unwrap();
+        assert_eq!((config.world_x, config.world_y), (20, 20));
+        assert_eq!(config.seed(), Some(8));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,50 @@
+//! Analyze mode - post-process genomes, populations and checkpoints with an analyze script
+//! Run with: cargo run --bin analyze --release -- analyze.cfg
+
+use avida_rs::analyze;
+
+const USAGE: &str = "Usage: analyze <SCRIPT>
+
+Runs the commands of an analyze script. Relative paths in the script are resolved
+against the script's directory.
+
+Commands:
+  LOAD_SEQUENCE <SEQUENCE>       Add a genome given as a sequence string
+  LOAD_ORGANISM <FILE>           Add a genome from a sequence or Avida .org file
+  LOAD <FILE>                    Add the genotypes of a .spop file
+  LOAD_CHECKPOINT <FILE>         Add every genotype of a checkpoint, extinct ancestors included
+  RECALCULATE                    Run every genotype in the test CPU
+  FILTER <STAT> <REL> <VALUE>    Keep genotypes where STAT REL VALUE (==, !=, <, <=, >, >=)
+  FIND_GENOTYPE [num_cpus|ID]    Keep only the most abundant genotype, or the one with ID
+  FIND_LINEAGE [num_cpus|ID]     Keep the line of descent of that genotype, ancestor first
+  DETAIL <FILE> <STAT>...        Write the chosen stats of every genotype
+  KNOCKOUTS [DIR]                Write knockout-<ID>.dat site tables for every genotype
+  LANDSCAPE [SAMPLES]            Write landscape-<ID>.dat point-mutant statistics for every genotype,
+                                 sampling SAMPLES double mutants for epistasis (default 0)
+  PURGE_BATCH                    Empty the batch
+
+Stats: id parent_id num_cpus total_cpus length update_born depth viable self_replicating
+       copy_length exe_length gest_time merit fitness sequence task.<N|NAME>";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let script = match args.as_slice() {
+        [flag] if flag == "-h" || flag == "--help" => {
+            println!("{}", USAGE);
+            return;
+        }
+        [script] => script,
+        _ => {
+            eprintln!("error: expected one analyze script\n\n{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    match analyze::run_file(script) {
+        Ok(analyzer) => println!("batch holds {} genotypes", analyzer.batch.len()),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
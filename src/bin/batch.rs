@@ -0,0 +1,40 @@
+//! Headless batch runner - run an experiment without the egui front-end
+//! Run with: cargo run --bin batch --release -- --updates 10000 --seed 42 --output-dir runs/rep01
+
+use avida_rs::batch::{self, BatchOptions};
+
+fn main() {
+    let options = match BatchOptions::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, batch::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if options.help {
+        println!("{}", batch::USAGE);
+        return;
+    }
+
+    avida_rs::debug::init();
+
+    match batch::run(&options) {
+        Ok(summary) => {
+            println!(
+                "seed {} finished at update {}: pop {} births {} deaths {} avg_merit {:.2}{}",
+                summary.seed,
+                summary.updates,
+                summary.population,
+                summary.total_births,
+                summary.total_deaths,
+                summary.average_merit,
+                if summary.extinct { " (extinct)" } else { "" }
+            );
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
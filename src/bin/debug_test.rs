@@ -57,12 +57,10 @@ fn main() {
     // Print task statistics
     println!("\nTask completions:");
     let task_stats = world.task_statistics();
-    let task_names = [
-        "NOT", "NAND", "AND", "ORN", "OR", "ANDN", "NOR", "XOR", "EQU",
-    ];
-    for (i, count) in task_stats.iter().enumerate() {
-        if *count > 0 {
-            println!("  {}: {}", task_names[i], count);
+    for task in avida_rs::tasks::Task::all() {
+        let count = task_stats[task.index()];
+        if count > 0 {
+            println!("  {}: {}", task.name(), count);
         }
     }
 
@@ -0,0 +1,277 @@
+//! Sexual reproduction
+//! Offspring of `divide-sex` wait in the birth chamber until another sexual offspring arrives
+//! (Avida's global birth chamber). The pair is then recombined by crossover and both are born
+//! next to their own parents.
+
+use crate::instruction::Instruction;
+use crate::organism::{Mutation, Organism, MINIMUM_GENOME_SIZE};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Recombination settings (Avida RECOMBINATION_GROUP)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Recombination {
+    /// RECOMBINATION_PROB: probability that a mating pair recombines
+    /// (otherwise both offspring are born unchanged)
+    pub probability: f64,
+    /// CROSSOVER_POINTS: 1 = single-point (the tails after the point are swapped),
+    /// 2 = two-point (the region between the points is swapped)
+    pub crossover_points: u8,
+    /// MODULE_NUM: number of equal-size modules crossover points fall between
+    /// (0 = non-modular, points anywhere)
+    pub modules: usize,
+    /// TWO_FOLD_COST_SEX: only one of the two recombinants is born
+    pub two_fold_cost: bool,
+}
+
+impl Default for Recombination {
+    /// Avida defaults: always recombine, two-point, non-modular, both offspring born
+    fn default() -> Self {
+        Self {
+            probability: 1.0,
+            crossover_points: 2,
+            modules: 0,
+            two_fold_cost: false,
+        }
+    }
+}
+
+/// An offspring of divide-sex waiting for a mate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaitingOffspring {
+    pub offspring: Organism,
+    /// Cell of the parent; the offspring is born next to it
+    pub parent_position: (usize, usize),
+    pub parent_genotype: Option<u64>,
+}
+
+/// Holds at most one offspring until a mate arrives
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BirthChamber {
+    waiting: Option<WaitingOffspring>,
+}
+
+impl BirthChamber {
+    /// The offspring waiting for a mate, if any
+    pub fn waiting(&self) -> Option<&WaitingOffspring> {
+        self.waiting.as_ref()
+    }
+
+    /// Submit an offspring of divide-sex
+    /// Returns the offspring to be born now: none while waiting for a mate, otherwise
+    /// the mated pair (or one of them with a two-fold cost of sex)
+    pub fn submit<R: Rng + ?Sized>(
+        &mut self,
+        mut arriving: WaitingOffspring,
+        settings: &Recombination,
+        rng: &mut R,
+    ) -> Vec<WaitingOffspring> {
+        let Some(mut waiting) = self.waiting.take() else {
+            self.waiting = Some(arriving);
+            return Vec::new();
+        };
+
+        if rng.gen::<f64>() < settings.probability {
+            let (a, b) = crossover(
+                &waiting.offspring.genome,
+                &arriving.offspring.genome,
+                settings,
+                rng,
+            );
+            for (entry, mutations) in [(&mut waiting, a), (&mut arriving, b)] {
+                for mutation in &mutations {
+                    mutation.apply(&mut entry.offspring.genome);
+                }
+                entry.offspring.mutations.extend(mutations);
+            }
+        }
+
+        if settings.two_fold_cost {
+            if rng.gen::<bool>() {
+                vec![waiting]
+            } else {
+                vec![arriving]
+            }
+        } else {
+            vec![waiting, arriving]
+        }
+    }
+}
+
+/// Cross two genomes over
+/// Returns the mutations turning each genome into its recombinant; both are empty when a
+/// recombinant would be shorter than the minimum genome size
+pub fn crossover<R: Rng + ?Sized>(
+    a: &[Instruction],
+    b: &[Instruction],
+    settings: &Recombination,
+    rng: &mut R,
+) -> (Vec<Mutation>, Vec<Mutation>) {
+    // Points are fractions of the genome length, so genomes of different lengths
+    // exchange corresponding regions
+    let mut point = || {
+        if settings.modules > 0 {
+            rng.gen_range(0..=settings.modules) as f64 / settings.modules as f64
+        } else {
+            rng.gen::<f64>()
+        }
+    };
+    let (start, end) = if settings.crossover_points == 1 {
+        (point(), 1.0)
+    } else {
+        let (x, y) = (point(), point());
+        (x.min(y), x.max(y))
+    };
+    let region = |len: usize| {
+        let at = |fraction: f64| (fraction * len as f64).round() as usize;
+        at(start)..at(end)
+    };
+    let (region_a, region_b) = (region(a.len()), region(b.len()));
+
+    let swapped = region_b.len() as isize - region_a.len() as isize;
+    if (a.len() as isize + swapped) < MINIMUM_GENOME_SIZE as isize
+        || (b.len() as isize - swapped) < MINIMUM_GENOME_SIZE as isize
+    {
+        return (Vec::new(), Vec::new());
+    }
+
+    (
+        replace_region(a, region_a.clone(), &b[region_b.clone()]),
+        replace_region(b, region_b, &a[region_a]),
+    )
+}
+
+/// Mutations replacing `genome[region]` with `replacement`: point mutations over the
+/// common length, then insertions or deletions for the difference
+fn replace_region(
+    genome: &[Instruction],
+    region: Range<usize>,
+    replacement: &[Instruction],
+) -> Vec<Mutation> {
+    let old = &genome[region.clone()];
+    let common = old.len().min(replacement.len());
+    let mut mutations: Vec<Mutation> = (0..common)
+        .filter(|&i| old[i] != replacement[i])
+        .map(|i| Mutation::Point {
+            pos: region.start + i,
+            from: old[i],
+            to: replacement[i],
+        })
+        .collect();
+
+    let pos = region.start + common;
+    for (i, &inst) in replacement[common..].iter().enumerate() {
+        mutations.push(Mutation::Insertion { pos: pos + i, inst });
+    }
+    for &inst in &old[common..] {
+        mutations.push(Mutation::Deletion { pos, inst });
+    }
+    mutations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::parse_genome;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn apply(genome: &[Instruction], mutations: &[Mutation]) -> Vec<Instruction> {
+        let mut genome = genome.to_vec();
+        for mutation in mutations {
+            mutation.apply(&mut genome);
+        }
+        genome
+    }
+
+    #[test]
+    fn test_single_point_crossover_swaps_tails() {
+        let a = parse_genome(&"a".repeat(20)).unwrap();
+        let b = parse_genome(&"b".repeat(30)).unwrap();
+        let settings = Recombination {
+            crossover_points: 1,
+            ..Recombination::default()
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        for _ in 0..50 {
+            let (ma, mb) = crossover(&a, &b, &settings, &mut rng);
+            let (ra, rb) = (apply(&a, &ma), apply(&b, &mb));
+            // Every instruction ends up in exactly one recombinant
+            assert_eq!(ra.len() + rb.len(), 50);
+            // Each recombinant is a head of its own genome followed by the other's tail
+            let head = ra.iter().take_while(|&&i| i == Instruction::NopA).count();
+            assert!(ra[head..].iter().all(|&i| i == Instruction::NopB));
+            let head = rb.iter().take_while(|&&i| i == Instruction::NopB).count();
+            assert!(rb[head..].iter().all(|&i| i == Instruction::NopA));
+        }
+    }
+
+    #[test]
+    fn test_two_point_modular_crossover() {
+        let a = parse_genome(&"a".repeat(40)).unwrap();
+        let b = parse_genome(&"b".repeat(40)).unwrap();
+        let settings = Recombination {
+            modules: 4,
+            ..Recombination::default()
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        let mut recombined = false;
+        for _ in 0..50 {
+            let (ma, mb) = crossover(&a, &b, &settings, &mut rng);
+            let ra = apply(&a, &ma);
+            assert_eq!(apply(&b, &mb).len(), 40);
+            // Whole modules of 10 instructions are exchanged
+            for module in ra.chunks(10) {
+                assert!(module.iter().all(|&i| i == module[0]));
+            }
+            recombined |= !ma.is_empty();
+        }
+        assert!(recombined);
+    }
+
+    #[test]
+    fn test_replace_region_mutations() {
+        let genome = parse_genome("abcabc").unwrap();
+        let longer = replace_region(&genome, 1..3, &parse_genome("bqq").unwrap());
+        assert_eq!(apply(&genome, &longer), parse_genome("abqqabc").unwrap());
+        assert_eq!(longer.len(), 2);
+        let shorter = replace_region(&genome, 1..4, &parse_genome("c").unwrap());
+        assert_eq!(apply(&genome, &shorter), parse_genome("acbc").unwrap());
+    }
+
+    #[test]
+    fn test_birth_chamber_pairs_offspring() {
+        let entry = |genome: &str, x| WaitingOffspring {
+            offspring: Organism::new(parse_genome(genome).unwrap()),
+            parent_position: (x, 0),
+            parent_genotype: None,
+        };
+        let a = "a".repeat(30);
+        let b = "b".repeat(30);
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let mut chamber = BirthChamber::default();
+        let settings = Recombination::default();
+
+        assert!(chamber.submit(entry(&a, 1), &settings, &mut rng).is_empty());
+        assert!(chamber.waiting().is_some());
+        let born = chamber.submit(entry(&b, 2), &settings, &mut rng);
+        assert!(chamber.waiting().is_none());
+        assert_eq!(born.len(), 2);
+        assert_eq!(born[0].parent_position, (1, 0));
+        assert_eq!(born[1].parent_position, (2, 0));
+        let total: usize = born.iter().map(|e| e.offspring.genome.len()).sum();
+        assert_eq!(total, 60);
+
+        // Without recombination and with a two-fold cost, one unchanged offspring is born
+        let settings = Recombination {
+            probability: 0.0,
+            two_fold_cost: true,
+            ..Recombination::default()
+        };
+        chamber.submit(entry(&a, 1), &settings, &mut rng);
+        let born = chamber.submit(entry(&b, 2), &settings, &mut rng);
+        assert_eq!(born.len(), 1);
+        assert!(born[0].offspring.mutations.is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+//! Population checkpoints
+//! Saves the complete simulation state (every organism with its CPU, the task detectors,
+//! world settings, counters and RNG state) so a run can continue exactly where it left off
+
+use crate::tasks::Task;
+use crate::world::World;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Version of the checkpoint format, bumped whenever the saved state changes
+/// 2: genotypes, 3: tasks of each genotype's dead organisms, 4: mutations from the parent
+/// and genotype merit, 5: instruction set, 6: instruction costs being paid,
+/// 7: recombination settings and the birth chamber, 8: parasites, 9: reactions and resources,
+/// 10: spatial resources, 11: reaction requisites and task counts, 12: three-input tasks,
+/// 13: math tasks
+pub const CHECKPOINT_VERSION: u32 = 13;
+
+/// Oldest checkpoint version that still loads
+/// State added since then is missing from such checkpoints and takes its default value.
+pub const OLDEST_CHECKPOINT_VERSION: u32 = 2;
+
+#[derive(Serialize)]
+struct CheckpointRef<'a> {
+    version: u32,
+    world: &'a World,
+}
+
+#[derive(Deserialize)]
+struct CheckpointHeader {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct Checkpoint {
+    world: World,
+}
+
+/// Serialize a world to checkpoint JSON
+pub fn to_json(world: &World) -> Result<String, String> {
+    serde_json::to_string(&CheckpointRef {
+        version: CHECKPOINT_VERSION,
+        world,
+    })
+    .map_err(|e| format!("Failed to serialize checkpoint: {}", e))
+}
+
+/// Restore a world from checkpoint JSON
+pub fn from_json(contents: &str) -> Result<World, String> {
+    let header: CheckpointHeader =
+        serde_json::from_str(contents).map_err(|e| format!("Invalid checkpoint: {}", e))?;
+    if !(OLDEST_CHECKPOINT_VERSION..=CHECKPOINT_VERSION).contains(&header.version) {
+        return Err(format!(
+            "Unsupported checkpoint version {} (supported: {} to {})",
+            header.version, OLDEST_CHECKPOINT_VERSION, CHECKPOINT_VERSION
+        ));
+    }
+
+    let mut checkpoint: Checkpoint =
+        serde_json::from_str(contents).map_err(|e| format!("Invalid checkpoint: {}", e))?;
+    if header.version < 11 {
+        // Tasks completed before task counts were saved count once, so they are not
+        // rewarded again
+        for org in checkpoint.world.grid.iter_mut().flatten() {
+            org.task_counts.resize(Task::COUNT, 0);
+            for task in org.tasks_completed.iter() {
+                org.task_counts[task.index()] = 1;
+            }
+        }
+    }
+    checkpoint
+        .world
+        .validate()
+        .map_err(|e| format!("Invalid checkpoint: {}", e))?;
+    Ok(checkpoint.world)
+}
+
+/// Write a checkpoint of the world to a file
+pub fn save(world: &World, path: impl AsRef<Path>) -> Result<(), String> {
+    let path = path.as_ref();
+    let contents = to_json(world)?;
+    std::fs::write(path, contents)
+        .map_err(|e| format!("Failed to write checkpoint {}: {}", path.display(), e))
+}
+
+/// Load a world from a checkpoint file
+pub fn load(path: impl AsRef<Path>) -> Result<World, String> {
+    let path = path.as_ref();
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read checkpoint {}: {}", path.display(), e))?;
+    from_json(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::organism::Organism;
+
+    /// Full observable state of every organism, including CPU and gestation progress
+    fn snapshot(world: &World) -> Vec<String> {
+        world
+            .grid
+            .iter()
+            .map(|cell| format!("{:?}", cell))
+            .collect()
+    }
+
+    fn run(world: &mut World, updates: usize) {
+        for _ in 0..updates {
+            world.update();
+        }
+    }
+
+    /// Checkpoint of `world` in the version 2 format: without the state added since
+    fn version_2_checkpoint(world: &World) -> String {
+        let mut json: serde_json::Value = serde_json::from_str(&to_json(world).unwrap()).unwrap();
+        json["version"] = 2.into();
+        let world = &mut json["world"];
+        for key in [
+            "inst_set",
+            "recombination",
+            "birth_chamber",
+            "parasites",
+            "total_infections",
+        ] {
+            world.as_object_mut().unwrap().remove(key);
+        }
+        for key in ["reactions", "resources"] {
+            world["task_env"].as_object_mut().unwrap().remove(key);
+        }
+        for genotype in world["genotypes"]["genotypes"].as_array_mut().unwrap() {
+            for key in ["tasks", "merit", "mutations"] {
+                genotype.as_object_mut().unwrap().remove(key);
+            }
+        }
+        for org in world["grid"].as_array_mut().unwrap() {
+            if let Some(org) = org.as_object_mut() {
+                for key in ["mutations", "parasites", "task_counts"] {
+                    org.remove(key);
+                }
+                let cpu = org["cpu"].as_object_mut().unwrap();
+                for key in ["cost_remaining", "initial_costs_paid"] {
+                    cpu.remove(key);
+                }
+            }
+        }
+        json.to_string()
+    }
+
+    #[test]
+    fn test_roundtrip_preserves_state() {
+        let mut world = World::with_dimensions_and_seed(20, 20, 11);
+        world.copy_mutation_rate = 0.02;
+        world.prefer_empty = false;
+        world.task_env.task_enabled[3] = false;
+        world.inject_ancestor_with_tasks();
+        run(&mut world, 150);
+
+        let restored = from_json(&to_json(&world).unwrap()).unwrap();
+        assert_eq!(snapshot(&restored), snapshot(&world));
+        assert_eq!(restored.dimensions(), (20, 20));
+        assert_eq!(restored.seed(), 11);
+        assert_eq!(restored.copy_mutation_rate, 0.02);
+        assert!(!restored.prefer_empty);
+        assert!(!restored.task_env.task_enabled[3]);
+        assert_eq!(restored.total_updates, world.total_updates);
+        assert_eq!(restored.total_births, world.total_births);
+        assert_eq!(restored.total_deaths, world.total_deaths);
+        assert_eq!(restored.population_size, world.population_size);
+    }
+
+    #[test]
+    fn test_restored_world_continues_identically() {
+        let mut original = World::with_dimensions_and_seed(20, 20, 5);
+        original.inject_ancestor_with_tasks();
+        run(&mut original, 100);
+
+        let mut restored = from_json(&to_json(&original).unwrap()).unwrap();
+        run(&mut original, 200);
+        run(&mut restored, 200);
+
+        assert!(original.total_births > 0);
+        assert_eq!(snapshot(&restored), snapshot(&original));
+        assert_eq!(restored.total_births, original.total_births);
+        assert_eq!(
+            restored.average_merit().to_bits(),
+            original.average_merit().to_bits()
+        );
+    }
+
+    #[test]
+    fn test_mid_gestation_state_is_saved() {
+        let mut world = World::with_seed(1);
+        let mut org = Organism::ancestor();
+        org.allocate_child();
+        org.cpu.registers = [7, -3, 12];
+        org.cpu.push(42);
+        org.child_copy_progress = 17;
+        org.merit = 4.0;
+        org.complete_task(2);
+        world.inject_organism(org, 3, 4);
+
+        let restored = from_json(&to_json(&world).unwrap()).unwrap();
+        let org = restored.get_organism(3, 4).unwrap();
+        assert!(org.child_genome.is_some());
+        assert_eq!(org.cpu.registers, [7, -3, 12]);
+        assert_eq!(org.cpu.active_stack_ref(), &vec![42]);
+        assert_eq!(org.child_copy_progress, 17);
+        assert_eq!(org.merit, 4.0);
+        assert!(org.has_completed_task(2));
+    }
+
+    #[test]
+    fn test_save_and_load_file() {
+        let dir = std::env::temp_dir().join(format!("avida-rs-checkpoint-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("checkpoint.json");
+
+        let mut world = World::with_dimensions_and_seed(10, 10, 3);
+        world.inject_ancestor();
+        run(&mut world, 20);
+        save(&world, &path).unwrap();
+
+        let restored = load(&path).unwrap();
+        assert_eq!(snapshot(&restored), snapshot(&world));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_loads_version_2_checkpoint() {
+        let mut world = World::with_dimensions_and_seed(10, 10, 3);
+        world.inject_ancestor_with_tasks();
+        run(&mut world, 100);
+
+        // Everything but the state missing from version 2
+        let state = |world: &World| -> Vec<String> {
+            world
+                .grid
+                .iter()
+                .flatten()
+                .map(|org| format!("{:?} {:?} {}", org.genome, org.cpu, org.merit))
+                .collect()
+        };
+        let mut restored = from_json(&version_2_checkpoint(&world)).unwrap();
+        assert_eq!(state(&restored), state(&world));
+        run(&mut world, 50);
+        run(&mut restored, 50);
+        assert_eq!(state(&restored), state(&world));
+        assert_eq!(restored.total_births, world.total_births);
+        assert_eq!(restored.inst_set, world.inst_set);
+    }
+
+    #[test]
+    fn test_invalid_checkpoints() {
+        assert!(from_json("not json").err().unwrap().contains("Invalid"));
+        assert!(from_json(r#"{"version": 999, "world": null}"#)
+            .err()
+            .unwrap()
+            .contains("Unsupported checkpoint version 999 (supported: 2 to"));
+
+        let mut world = World::with_dimensions_and_seed(4, 4, 1);
+        world.inject_ancestor();
+        let json = to_json(&world)
+            .unwrap()
+            .replace("\"population_size\":1", "\"population_size\":5");
+        assert!(from_json(&json).err().unwrap().contains("population size"));
+
+        let mut json: serde_json::Value = serde_json::from_str(&to_json(&world).unwrap()).unwrap();
+        json["world"]["task_detectors"][2 * 4 + 2] = serde_json::Value::Null;
+        let err = from_json(&json.to_string()).err().unwrap();
+        assert!(
+            err.contains("organism at (2, 2) has no task detector"),
+            "{}",
+            err
+        );
+
+        assert!(load("/nonexistent/checkpoint.json").is_err());
+    }
+}
@@ -0,0 +1,498 @@
+//! Avida-style configuration files (avida.cfg)
+//! Parses the classic `KEY value` format into a typed configuration that builds a World
+
+use crate::birth_chamber::Recombination;
+use crate::parasite::ParasiteSettings;
+use crate::world::World;
+use std::path::Path;
+
+/// Settings from the default Avida avida.cfg that avida-rs accepts but does not use.
+/// They are collected in `AvidaConfig::ignored` so existing configs load unchanged.
+const IGNORED_SETTINGS: &[&str] = &[
+    "VERSION_ID",
+    "VERBOSITY",
+    "BIRTH_METHOD",
+    "AVE_TIME_SLICE",
+    "SPECULATIVE",
+    "POPULATION_CAP",
+    "POP_CAP_ELDEST",
+    "WORLD_GEOMETRY",
+    "SCALE_FACTOR",
+    "DATA_DIR",
+    "EVENT_FILE",
+    "ANALYZE_FILE",
+    "ENVIRONMENT_FILE",
+    "START_ORGANISM",
+    "COPY_INS_PROB",
+    "COPY_DEL_PROB",
+    "COPY_UNIFORM_PROB",
+    "COPY_SLIP_PROB",
+    "POINT_MUT_PROB",
+    "DIV_MUT_PROB",
+    "DIV_INS_PROB",
+    "DIV_DEL_PROB",
+    "DIV_UNIFORM_PROB",
+    "DIV_SLIP_PROB",
+    "DIVIDE_MUT_PROB",
+    "DIVIDE_UNIFORM_PROB",
+    "DIVIDE_SLIP_PROB",
+    "DIVIDE_POISSON_MUT_MEAN",
+    "DIVIDE_POISSON_INS_MEAN",
+    "DIVIDE_POISSON_DEL_MEAN",
+    "DIVIDE_POISSON_SLIP_MEAN",
+    "DIVIDE_INS_PROB_CORRECTION",
+    "INJECT_INS_PROB",
+    "INJECT_DEL_PROB",
+    "INJECT_MUT_PROB",
+    "SLIP_FILL_MODE",
+    "SLIP_COPY_MODE",
+    "PARENT_MUT_PROB",
+    "SPECIAL_MUT_LINE",
+    "META_COPY_MUT",
+    "META_STD_DEV",
+    "MUT_RATE_SOURCE",
+    "DIVIDE_FAILURE_RESETS",
+    "ALLOW_PARENT",
+    "DISPERSAL_RATE",
+    "DEATH_PROB",
+    "AGE_DEVIATION",
+    "ALLOC_METHOD",
+    "DIVIDE_METHOD",
+    "EPIGENETIC_METHOD",
+    "GENERATION_INC_METHOD",
+    "RESET_INPUTS_ON_DIVIDE",
+    "REPRO_METHOD",
+    "OFFSPRING_SIZE_RANGE",
+    "MIN_COPIED_LINES",
+    "MIN_EXE_LINES",
+    "MIN_GENOME_SIZE",
+    "MAX_GENOME_SIZE",
+    "MIN_CYCLES",
+    "REQUIRE_ALLOCATE",
+    "REQUIRED_TASK",
+    "IMMUNITY_TASK",
+    "REQUIRED_REACTION",
+    "IMMUNITY_REACTION",
+    "REQUIRE_SINGLE_REACTION",
+    "REQUIRED_BONUS",
+    "REQUIRE_EXACT_COPY",
+    "IMPLICIT_REPRO_BONUS",
+    "IMPLICIT_REPRO_CPU_CYCLES",
+    "IMPLICIT_REPRO_TIME",
+    "IMPLICIT_REPRO_END",
+    "IMPLICIT_REPRO_ENERGY",
+    "SLICING_METHOD",
+    "BASE_MERIT_METHOD",
+    "BASE_CONST_MERIT",
+    "MERIT_DEFAULT_BONUS",
+    "MERIT_BONUS_INST",
+    "MERIT_BONUS_EFFECT",
+    "FITNESS_VALLEY",
+    "FITNESS_VALLEY_START",
+    "FITNESS_VALLEY_STOP",
+    "DEFAULT_BONUS",
+    "MERIT_INC_APPLY_IMMEDIATE",
+    "TASK_REFRACTORY_PERIOD",
+    "FITNESS_METHOD",
+    "FITNESS_COEFF_1",
+    "FITNESS_COEFF_2",
+    "THREAD_SLICING_METHOD",
+    "NO_CPU_CYCLE_TIME",
+    "MAX_LABEL_EXE_SIZE",
+    "MAX_UNIQUE_TASK_COUNT",
+    "MAX_NUM_TASKS_REWARDED",
+    "TRACK_MAIN_LINEAGE",
+    "TRACK_CCLADES",
+    "TRACK_CCLADES_IDS",
+    "THRESHOLD",
+    "TEST_CPU_TIME_MOD",
+    "MAX_BIRTH_WAIT_TIME",
+    "CONT_REC_REGS",
+    "CORESPOND_REC_REGS",
+    "SAME_LENGTH_SEX",
+];
+
+/// Typed contents of an avida.cfg file
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvidaConfig {
+    /// WORLD_X / WORLD_Y: world dimensions
+    pub world_x: usize,
+    pub world_y: usize,
+    /// RANDOM_SEED: seed for the world RNG (0 or negative = based on entropy)
+    pub random_seed: i64,
+    /// COPY_MUT_PROB: per-instruction copy mutation probability
+    pub copy_mut_prob: f64,
+    /// DIVIDE_INS_PROB / DIVIDE_DEL_PROB: insertion/deletion probability per division
+    pub divide_ins_prob: f64,
+    pub divide_del_prob: f64,
+    /// DEATH_METHOD: 0 = no age death, 1 = fixed age limit, 2 = age limit × genome length
+    pub death_method: u8,
+    /// AGE_LIMIT: age limit (or multiplier) used by the death method
+    pub age_limit: u64,
+    /// PREFER_EMPTY: prefer empty cells when placing offspring
+    pub prefer_empty: bool,
+    /// RECOMBINATION_PROB: probability that offspring of divide-sex recombine
+    pub recombination_prob: f64,
+    /// MODULE_NUM: modules crossover points fall between (0 = anywhere)
+    pub module_num: usize,
+    /// CROSSOVER_POINTS: 1 = single-point, 2 = two-point crossover (avida-rs setting)
+    pub crossover_points: u8,
+    /// TWO_FOLD_COST_SEX: only one recombinant of each mating is born
+    pub two_fold_cost_sex: bool,
+    /// MAX_CPU_THREADS: threads per host, counting its own (parasites need at least 2)
+    pub max_cpu_threads: usize,
+    /// PARASITE_VIRULENCE: share of a host's cycles a parasite of merit 1 takes
+    pub parasite_virulence: f64,
+    /// PARASITE_TASK_REWARDS: parasite merit multiplier per task (avida-rs setting)
+    pub parasite_task_rewards: Vec<f64>,
+    /// Recognized Avida settings that avida-rs does not use
+    pub ignored: Vec<String>,
+}
+
+impl Default for AvidaConfig {
+    fn default() -> Self {
+        Self::from_world(&World::new())
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' for {}", value, key))
+}
+
+fn parse_probability(key: &str, value: &str) -> Result<f64, String> {
+    let p: f64 = parse_number(key, value)?;
+    if !(0.0..=1.0).contains(&p) {
+        return Err(format!("{} must be between 0 and 1, got {}", key, p));
+    }
+    Ok(p)
+}
+
+fn parse_dimension(key: &str, value: &str) -> Result<usize, String> {
+    let size: usize = parse_number(key, value)?;
+    if size == 0 {
+        return Err(format!("{} must be at least 1", key));
+    }
+    Ok(size)
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "0" | "false" => Ok(false),
+        "1" | "true" => Ok(true),
+        _ => Err(format!(
+            "invalid value '{}' for {} (expected 0 or 1)",
+            value, key
+        )),
+    }
+}
+
+impl AvidaConfig {
+    /// Parse the contents of an avida.cfg file
+    /// Lines are `KEY value`; `#` starts a comment (including `#include` directives)
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+
+        for (line_num, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let key = parts.next().unwrap_or("");
+            let value = parts
+                .next()
+                .ok_or_else(|| format!("line {}: missing value for {}", line_num + 1, key))?;
+
+            config
+                .set(key, value)
+                .map_err(|e| format!("line {}: {}", line_num + 1, e))?;
+        }
+
+        Ok(config)
+    }
+
+    /// Read and parse an avida.cfg file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Settings of an existing world (RANDOM_SEED is left unset)
+    pub fn from_world(world: &World) -> Self {
+        let (world_x, world_y) = world.dimensions();
+        Self {
+            world_x,
+            world_y,
+            random_seed: 0,
+            copy_mut_prob: world.copy_mutation_rate,
+            divide_ins_prob: world.insertion_rate,
+            divide_del_prob: world.deletion_rate,
+            death_method: world.death_method,
+            age_limit: world.age_limit,
+            prefer_empty: world.prefer_empty,
+            recombination_prob: world.recombination.probability,
+            module_num: world.recombination.modules,
+            crossover_points: world.recombination.crossover_points,
+            two_fold_cost_sex: world.recombination.two_fold_cost,
+            max_cpu_threads: world.parasites.max_threads,
+            parasite_virulence: world.parasites.virulence,
+            parasite_task_rewards: world.parasites.task_rewards.clone(),
+            ignored: Vec::new(),
+        }
+    }
+
+    /// Set a single setting by its Avida name
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "WORLD_X" => self.world_x = parse_dimension(key, value)?,
+            "WORLD_Y" => self.world_y = parse_dimension(key, value)?,
+            "RANDOM_SEED" => self.random_seed = parse_number(key, value)?,
+            "COPY_MUT_PROB" => self.copy_mut_prob = parse_probability(key, value)?,
+            "DIVIDE_INS_PROB" => self.divide_ins_prob = parse_probability(key, value)?,
+            "DIVIDE_DEL_PROB" => self.divide_del_prob = parse_probability(key, value)?,
+            "DEATH_METHOD" => {
+                let method = parse_number(key, value)?;
+                if method > 2 {
+                    return Err(format!("DEATH_METHOD must be 0, 1 or 2, got {}", method));
+                }
+                self.death_method = method;
+            }
+            "AGE_LIMIT" => self.age_limit = parse_number(key, value)?,
+            "PREFER_EMPTY" => self.prefer_empty = parse_bool(key, value)?,
+            "RECOMBINATION_PROB" => self.recombination_prob = parse_probability(key, value)?,
+            "MODULE_NUM" => self.module_num = parse_number(key, value)?,
+            "CROSSOVER_POINTS" => {
+                let points = parse_number(key, value)?;
+                if !(1..=2).contains(&points) {
+                    return Err(format!("CROSSOVER_POINTS must be 1 or 2, got {}", points));
+                }
+                self.crossover_points = points;
+            }
+            "TWO_FOLD_COST_SEX" => self.two_fold_cost_sex = parse_bool(key, value)?,
+            "MAX_CPU_THREADS" => self.max_cpu_threads = parse_dimension(key, value)?,
+            "PARASITE_VIRULENCE" => self.parasite_virulence = parse_probability(key, value)?,
+            "PARASITE_TASK_REWARDS" => {
+                self.parasite_task_rewards = ParasiteSettings::parse_task_rewards(value)
+                    .map_err(|e| format!("PARASITE_TASK_REWARDS: {}", e))?
+            }
+            _ if IGNORED_SETTINGS.contains(&key) => self.ignored.push(key.to_string()),
+            _ => return Err(format!("unknown setting '{}'", key)),
+        }
+        Ok(())
+    }
+
+    /// Format the supported settings as an avida.cfg file
+    pub fn format(&self) -> String {
+        format!(
+            "# avida-rs configuration\n\
+             WORLD_X {}\n\
+             WORLD_Y {}\n\
+             RANDOM_SEED {}\n\
+             COPY_MUT_PROB {}\n\
+             DIVIDE_INS_PROB {}\n\
+             DIVIDE_DEL_PROB {}\n\
+             PREFER_EMPTY {}\n\
+             DEATH_METHOD {}\n\
+             AGE_LIMIT {}\n\
+             RECOMBINATION_PROB {}\n\
+             MODULE_NUM {}\n\
+             CROSSOVER_POINTS {}\n\
+             TWO_FOLD_COST_SEX {}\n\
+             MAX_CPU_THREADS {}\n\
+             PARASITE_VIRULENCE {}\n\
+             PARASITE_TASK_REWARDS {}\n",
+            self.world_x,
+            self.world_y,
+            self.random_seed,
+            self.copy_mut_prob,
+            self.divide_ins_prob,
+            self.divide_del_prob,
+            self.prefer_empty as u8,
+            self.death_method,
+            self.age_limit,
+            self.recombination_prob,
+            self.module_num,
+            self.crossover_points,
+            self.two_fold_cost_sex as u8,
+            self.max_cpu_threads,
+            self.parasite_virulence,
+            self.parasite_settings().format_task_rewards()
+        )
+    }
+
+    /// Parasite settings of the world built from this configuration
+    fn parasite_settings(&self) -> ParasiteSettings {
+        ParasiteSettings {
+            max_threads: self.max_cpu_threads,
+            virulence: self.parasite_virulence,
+            task_rewards: self.parasite_task_rewards.clone(),
+        }
+    }
+
+    /// Seed for the world RNG, if one was configured
+    pub fn seed(&self) -> Option<u64> {
+        (self.random_seed > 0).then_some(self.random_seed as u64)
+    }
+
+    /// Build an empty world with these settings
+    pub fn build_world(&self) -> Result<World, String> {
+        if self.world_x == 0 || self.world_y == 0 {
+            return Err(format!(
+                "WORLD_X/WORLD_Y must be at least 1, got {}x{}",
+                self.world_x, self.world_y
+            ));
+        }
+
+        let mut world = match self.seed() {
+            Some(seed) => World::with_dimensions_and_seed(self.world_x, self.world_y, seed),
+            None => World::with_dimensions(self.world_x, self.world_y),
+        };
+
+        world.copy_mutation_rate = self.copy_mut_prob;
+        world.insertion_rate = self.divide_ins_prob;
+        world.deletion_rate = self.divide_del_prob;
+        world.death_method = self.death_method;
+        world.age_limit = self.age_limit;
+        world.prefer_empty = self.prefer_empty;
+        world.parasites = self.parasite_settings();
+        world.recombination = Recombination {
+            probability: self.recombination_prob,
+            crossover_points: self.crossover_points,
+            modules: self.module_num,
+            two_fold_cost: self.two_fold_cost_sex,
+        };
+
+        Ok(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_empty_gives_defaults() {
+        let config = AvidaConfig::parse("").unwrap();
+        assert_eq!(config, AvidaConfig::default());
+        assert_eq!(config.copy_mut_prob, 0.0075);
+        assert_eq!(config.death_method, 2);
+    }
+
+    #[test]
+    fn test_parse_settings() {
+        let contents = "\
+### GENERAL_GROUP ###
+VERSION_ID 2.14.0   # Do not change this value.
+RANDOM_SEED 101     # Random number seed (0 for based on time)
+
+### MUTATION_GROUP ###
+COPY_MUT_PROB 0.0025
+DIVIDE_INS_PROB 0.0
+DIVIDE_DEL_PROB 0.0
+
+### REPRODUCTION_GROUP ###
+BIRTH_METHOD 4
+PREFER_EMPTY 0
+DEATH_METHOD 1
+AGE_LIMIT 5000
+AVE_TIME_SLICE 50
+";
+        let config = AvidaConfig::parse(contents).unwrap();
+        assert_eq!(config.random_seed, 101);
+        assert_eq!(config.seed(), Some(101));
+        assert_eq!(config.copy_mut_prob, 0.0025);
+        assert_eq!(config.divide_ins_prob, 0.0);
+        assert_eq!(config.divide_del_prob, 0.0);
+        assert!(!config.prefer_empty);
+        assert_eq!(config.death_method, 1);
+        assert_eq!(config.age_limit, 5000);
+        assert_eq!(
+            config.ignored,
+            vec![
+                "VERSION_ID".to_string(),
+                "BIRTH_METHOD".to_string(),
+                "AVE_TIME_SLICE".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_key_is_error() {
+        let err = AvidaConfig::parse("WORLD_X 60\nCOPY_MUT_PRB 0.1\n").unwrap_err();
+        assert!(err.contains("line 2"), "{}", err);
+        assert!(err.contains("unknown setting 'COPY_MUT_PRB'"), "{}", err);
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        assert!(AvidaConfig::parse("COPY_MUT_PROB lots")
+            .unwrap_err()
+            .contains("invalid value"));
+        assert!(AvidaConfig::parse("COPY_MUT_PROB 2.0").is_err());
+        assert!(AvidaConfig::parse("DEATH_METHOD 7").is_err());
+        assert!(AvidaConfig::parse("PREFER_EMPTY yes").is_err());
+        assert!(AvidaConfig::parse("CROSSOVER_POINTS 3").is_err());
+        assert!(AvidaConfig::parse("RECOMBINATION_PROB 1.5").is_err());
+        assert!(AvidaConfig::parse("MAX_CPU_THREADS 0").is_err());
+        assert!(AvidaConfig::parse("PARASITE_TASK_REWARDS nand")
+            .unwrap_err()
+            .contains("PARASITE_TASK_REWARDS"));
+        assert!(AvidaConfig::parse("WORLD_X")
+            .unwrap_err()
+            .contains("missing value"));
+    }
+
+    #[test]
+    fn test_format_roundtrip() {
+        let mut config = AvidaConfig::parse("RANDOM_SEED 5\nPREFER_EMPTY 0\n").unwrap();
+        config.copy_mut_prob = 0.0125;
+        config.recombination_prob = 0.5;
+        config.crossover_points = 1;
+        config.module_num = 3;
+        config.two_fold_cost_sex = true;
+        config.max_cpu_threads = 3;
+        config.parasite_virulence = 0.75;
+        config.parasite_task_rewards[2] = 4.0;
+        assert_eq!(AvidaConfig::parse(&config.format()).unwrap(), config);
+    }
+
+    #[test]
+    fn test_non_positive_seed_uses_entropy() {
+        assert_eq!(AvidaConfig::parse("RANDOM_SEED 0").unwrap().seed(), None);
+        assert_eq!(AvidaConfig::parse("RANDOM_SEED -1").unwrap().seed(), None);
+    }
+
+    #[test]
+    fn test_build_world() {
+        let config = AvidaConfig::parse(
+            "COPY_MUT_PROB 0.01\nPREFER_EMPTY 0\nDEATH_METHOD 0\nMODULE_NUM 4\n",
+        )
+        .unwrap();
+        let world = config.build_world().unwrap();
+        assert_eq!(world.copy_mutation_rate, 0.01);
+        assert!(!world.prefer_empty);
+        assert_eq!(world.death_method, 0);
+        assert_eq!(world.recombination.modules, 4);
+        assert_eq!(world.recombination.probability, 1.0);
+        assert_eq!(world.population_size, 0);
+    }
+
+    #[test]
+    fn test_build_world_dimensions() {
+        let config = AvidaConfig::parse("WORLD_X 100\nWORLD_Y 200\n").unwrap();
+        let world = config.build_world().unwrap();
+        assert_eq!(world.dimensions(), (100, 200));
+    }
+
+    #[test]
+    fn test_zero_dimension_is_error() {
+        assert!(AvidaConfig::parse("WORLD_X 0")
+            .unwrap_err()
+            .contains("at least 1"));
+    }
+}
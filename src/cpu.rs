@@ -1,5 +1,5 @@
 use crate::instruction::Instruction;
-use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 const STACK_MAX_DEPTH: usize = 10;
 
@@ -13,7 +13,7 @@ pub enum HeadType {
 }
 
 /// Virtual CPU for an Avida organism
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CPU {
     /// Three 32-bit registers: AX (0), BX (1), CX (2)
     pub registers: [i32; 3],
@@ -37,7 +37,7 @@ pub struct CPU {
     /// Flow-Head - position marker for jumps
     pub flow_head: usize,
 
-    /// Input buffer for task I/O
+    /// Pending inputs for task I/O (IO draws from the world RNG once this is empty)
     pub input_buffer: Vec<i32>,
 
     /// Output buffer for task I/O
@@ -48,13 +48,19 @@ pub struct CPU {
 
     /// Skip next instruction flag (for conditionals)
     pub skip_next: bool,
+
+    /// Cycles still to pay before the instruction at the IP executes
+    /// (0 when no instruction is being paid for)
+    #[serde(default)]
+    pub cost_remaining: u32,
+
+    /// Instructions whose initial cost has been paid during the current gestation
+    #[serde(default)]
+    pub initial_costs_paid: Vec<Instruction>,
 }
 
 impl CPU {
     pub fn new() -> Self {
-        let mut rng = rand::thread_rng();
-        let input_buffer = (0..3).map(|_| rng.gen::<i32>()).collect();
-
         Self {
             registers: [0, 0, 0], // AX=0, BX=1, CX=2
             stack1: Vec::with_capacity(STACK_MAX_DEPTH),
@@ -64,10 +70,12 @@ impl CPU {
             read_head: 0,
             write_head: 0,
             flow_head: 0,
-            input_buffer,
+            input_buffer: Vec::new(),
             output_buffer: Vec::new(),
             last_copied_label: Vec::new(),
             skip_next: false,
+            cost_remaining: 0,
+            initial_costs_paid: Vec::new(),
         }
     }
 
@@ -102,6 +110,33 @@ impl CPU {
         self.active_stack_mut().pop().unwrap_or(0)
     }
 
+    /// Push value onto the inactive stack
+    pub fn push_other(&mut self, value: i32) {
+        let stack = if self.active_stack {
+            &mut self.stack1
+        } else {
+            &mut self.stack2
+        };
+        if stack.len() < STACK_MAX_DEPTH {
+            stack.push(value);
+        }
+    }
+
+    /// Pop value from the inactive stack
+    pub fn pop_other(&mut self) -> i32 {
+        let stack = if self.active_stack {
+            &mut self.stack1
+        } else {
+            &mut self.stack2
+        };
+        stack.pop().unwrap_or(0)
+    }
+
+    /// Register after `index` (AX -> BX -> CX -> AX)
+    pub fn next_register(index: usize) -> usize {
+        (index + 1) % 3
+    }
+
     /// Advance a head position with circular wrapping
     pub fn advance_head(&self, head: usize, genome_size: usize) -> usize {
         (head + 1) % genome_size
@@ -248,6 +283,8 @@ impl CPU {
         self.output_buffer.clear();
         self.last_copied_label.clear();
         self.skip_next = false;
+        self.cost_remaining = 0;
+        self.initial_costs_paid.clear();
     }
 }
 
@@ -296,7 +333,7 @@ mod tests {
         let mut cpu = CPU::new();
         cpu.push(1);
         cpu.push(2);
-        assert_eq!(cpu.active_stack, false);
+        assert!(!cpu.active_stack);
 
         cpu.active_stack = true;
         cpu.push(10);
@@ -481,6 +518,8 @@ mod tests {
         cpu.flow_head = 20;
         cpu.push(42);
         cpu.skip_next = true;
+        cpu.cost_remaining = 3;
+        cpu.initial_costs_paid.push(Instruction::HAlloc);
 
         cpu.reset();
 
@@ -492,5 +531,7 @@ mod tests {
         assert_eq!(cpu.stack1.len(), 0);
         assert_eq!(cpu.stack2.len(), 0);
         assert!(!cpu.skip_next);
+        assert_eq!(cpu.cost_remaining, 0);
+        assert!(cpu.initial_costs_paid.is_empty());
     }
 }
@@ -0,0 +1,437 @@
+//! Time-series data files
+//! Appends one row per sample to Avida-style `.dat` files (`average.dat`, `count.dat`,
+//! `dominant.dat`, `tasks.dat`) and optionally matching `.csv` files, so runs can be plotted afterwards
+
+use crate::genotype;
+use crate::instruction::Instruction;
+use crate::organism::Organism;
+use crate::tasks::TaskSet;
+use crate::world::World;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+
+/// One data file: a title, its columns and how to compute a row
+pub struct DataFile {
+    /// File name without extension (e.g. "average")
+    pub name: &'static str,
+    /// Title written on the first line of the .dat file
+    pub title: &'static str,
+    /// Columns as (CSV header name, .dat description)
+    pub columns: Vec<(String, String)>,
+    row: fn(&World) -> Vec<f64>,
+}
+
+fn column(name: &str, description: &str) -> (String, String) {
+    (name.to_string(), description.to_string())
+}
+
+/// Average of `value` over all living organisms (0 for an empty world)
+fn average(world: &World, value: fn(&Organism) -> f64) -> f64 {
+    let (total, count) = world
+        .grid
+        .iter()
+        .flatten()
+        .fold((0.0, 0usize), |(total, count), org| {
+            (total + value(org), count + 1)
+        });
+    if count > 0 {
+        total / count as f64
+    } else {
+        0.0
+    }
+}
+
+/// Number of distinct genomes and their Shannon diversity (natural log)
+pub fn genotype_diversity(world: &World) -> (usize, f64) {
+    let mut abundances: HashMap<&[Instruction], usize> = HashMap::new();
+    for org in world.grid.iter().flatten() {
+        *abundances.entry(org.genome.as_slice()).or_insert(0) += 1;
+    }
+
+    let total = abundances.values().sum::<usize>() as f64;
+    let diversity = abundances
+        .values()
+        .map(|&count| {
+            let p = count as f64 / total;
+            -p * p.ln()
+        })
+        .sum::<f64>();
+    // A single genotype gives -0.0, which `+ 0.0` turns into 0
+    (abundances.len(), diversity.max(0.0) + 0.0)
+}
+
+impl DataFile {
+    /// Population averages (Avida average.dat)
+    pub fn average() -> Self {
+        Self {
+            name: "average",
+            title: "Avida Average Data",
+            columns: vec![
+                column("update", "Update"),
+                column("merit", "Merit"),
+                column("fitness", "Fitness"),
+                column("genome_length", "Genome Length"),
+                column("generation", "Generation"),
+                column("age", "Age (instructions executed)"),
+            ],
+            row: |world| {
+                vec![
+                    world.total_updates as f64,
+                    world.average_merit(),
+                    world.average_fitness(),
+                    world.average_genome_size(),
+                    world.average_generation(),
+                    average(world, |org| org.age() as f64),
+                ]
+            },
+        }
+    }
+
+    /// Population counts and diversity (Avida count.dat)
+    pub fn count() -> Self {
+        Self {
+            name: "count",
+            title: "Avida Count Data",
+            columns: vec![
+                column("update", "Update"),
+                column("organisms", "Number of organisms"),
+                column("genotypes", "Number of genotypes"),
+                column("diversity", "Shannon diversity of genotypes"),
+                column("births", "Total births"),
+                column("deaths", "Total deaths"),
+            ],
+            row: |world| {
+                let (genotypes, diversity) = genotype_diversity(world);
+                vec![
+                    world.total_updates as f64,
+                    world.population_size as f64,
+                    genotypes as f64,
+                    diversity,
+                    world.total_births as f64,
+                    world.total_deaths as f64,
+                ]
+            },
+        }
+    }
+
+    /// The most abundant genotype (Avida dominant.dat); all zero for an empty world
+    pub fn dominant() -> Self {
+        Self {
+            name: "dominant",
+            title: "Avida Dominant Data",
+            columns: vec![
+                column("update", "Update"),
+                column("merit", "Average Merit of the Dominant Genotype"),
+                column(
+                    "gestation_time",
+                    "Average Gestation Time of the Dominant Genotype",
+                ),
+                column("fitness", "Average Fitness of the Dominant Genotype"),
+                column("abundance", "Abundance of Dominant Genotype"),
+                column("genome_length", "Genotype Length"),
+                column("max_abundance", "Max Abundance of Dominant Genotype"),
+                column("update_born", "Update Born of Dominant Genotype"),
+                column("depth", "Phylogenetic Depth of Dominant Genotype"),
+                column("id", "ID of Dominant Genotype"),
+                column("tasks", "Number of Tasks Performed by Dominant Genotype"),
+            ],
+            row: |world| {
+                let mut row = vec![world.total_updates as f64];
+                match genotype::dominant_report(world) {
+                    Some(report) => {
+                        let genotype = &report.genotype;
+                        row.extend([
+                            report.merit,
+                            report.gestation_time,
+                            report.fitness,
+                            genotype.abundance as f64,
+                            genotype.genome.len() as f64,
+                            genotype.max_abundance as f64,
+                            genotype.update_born as f64,
+                            genotype.depth as f64,
+                            genotype.id as f64,
+                            report.tasks().len() as f64,
+                        ]);
+                    }
+                    None => row.extend([0.0; 10]),
+                }
+                row
+            },
+        }
+    }
+
+    /// Number of organisms that have performed each of `tasks`, the tasks of the world's
+    /// environment (Avida tasks.dat)
+    pub fn tasks(tasks: TaskSet) -> Self {
+        let mut columns = vec![column("update", "Update")];
+        for task in tasks.iter() {
+            columns.push((
+                task.name().to_lowercase(),
+                format!("Number of organisms that performed {}", task.name()),
+            ));
+        }
+
+        Self {
+            name: "tasks",
+            title: "Avida Tasks Data",
+            columns,
+            row: |world| {
+                let counts = world.task_statistics();
+                std::iter::once(world.total_updates as f64)
+                    .chain(
+                        world
+                            .task_env
+                            .tasks()
+                            .iter()
+                            .map(|task| counts[task.index()] as f64),
+                    )
+                    .collect()
+            },
+        }
+    }
+
+    /// The files written by default: average, count, dominant and tasks (of `tasks`)
+    pub fn standard(tasks: TaskSet) -> Vec<Self> {
+        vec![
+            Self::average(),
+            Self::count(),
+            Self::dominant(),
+            Self::tasks(tasks),
+        ]
+    }
+
+    /// Look up one of the standard files by name ("average", "count", "dominant" or "tasks")
+    pub fn by_name(name: &str, tasks: TaskSet) -> Option<Self> {
+        Self::standard(tasks)
+            .into_iter()
+            .find(|file| file.name == name)
+    }
+
+    /// Append the current row to a .dat file, writing the header if the file is new
+    pub fn append_to(&self, path: &Path, world: &World) -> Result<(), String> {
+        if !path.exists() {
+            std::fs::write(path, self.dat_header())
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+        append(path, &format_row(&self.row(world), " "))
+    }
+
+    /// Current values of every column
+    pub fn row(&self, world: &World) -> Vec<f64> {
+        (self.row)(world)
+    }
+
+    /// Header of the .dat file: title, numbered column descriptions and a blank line
+    pub fn dat_header(&self) -> String {
+        let mut out = format!("# {}\n", self.title);
+        for (i, (_, description)) in self.columns.iter().enumerate() {
+            let _ = writeln!(out, "# {:2}: {}", i + 1, description);
+        }
+        out.push('\n');
+        out
+    }
+
+    /// Header line of the CSV file
+    pub fn csv_header(&self) -> String {
+        let names: Vec<&str> = self.columns.iter().map(|(name, _)| name.as_str()).collect();
+        format!("{}\n", names.join(","))
+    }
+}
+
+fn format_row(values: &[f64], separator: &str) -> String {
+    let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    format!("{}\n", values.join(separator))
+}
+
+fn append(path: &Path, contents: &str) -> Result<(), String> {
+    OpenOptions::new()
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Writes the data files of a run into an output directory
+pub struct DataRecorder {
+    dir: PathBuf,
+    /// Record every N updates (0 = only when asked)
+    pub interval: u64,
+    /// Also write a .csv copy of each file
+    pub csv: bool,
+    files: Vec<DataFile>,
+}
+
+impl DataRecorder {
+    /// Create (or truncate) the data files in `dir` and write their headers, with a tasks
+    /// column for each of `tasks`
+    pub fn create(
+        dir: impl Into<PathBuf>,
+        interval: u64,
+        csv: bool,
+        tasks: TaskSet,
+    ) -> Result<Self, String> {
+        let recorder = Self {
+            dir: dir.into(),
+            interval,
+            csv,
+            files: DataFile::standard(tasks),
+        };
+
+        for file in &recorder.files {
+            let path = recorder.dat_path(file.name);
+            std::fs::write(&path, file.dat_header())
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            if csv {
+                let path = recorder.csv_path(file.name);
+                std::fs::write(&path, file.csv_header())
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            }
+        }
+
+        Ok(recorder)
+    }
+
+    /// Path of a .dat file (e.g. `dat_path("average")`)
+    pub fn dat_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.dat", name))
+    }
+
+    /// Path of a .csv file
+    pub fn csv_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.csv", name))
+    }
+
+    /// Whether a row is due at this update
+    pub fn is_due(&self, update: u64) -> bool {
+        self.interval > 0 && update.is_multiple_of(self.interval)
+    }
+
+    /// Append the current state of the world to every data file
+    pub fn record(&self, world: &World) -> Result<(), String> {
+        for file in &self.files {
+            let values = file.row(world);
+            append(&self.dat_path(file.name), &format_row(&values, " "))?;
+            if self.csv {
+                append(&self.csv_path(file.name), &format_row(&values, ","))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tasks::TaskEnvironment;
+
+    #[test]
+    fn test_columns_match_rows() {
+        let mut world = World::with_dimensions_and_seed(10, 10, 1);
+        world.inject_ancestor();
+        for file in DataFile::standard(world.task_env.tasks()) {
+            assert_eq!(file.row(&world).len(), file.columns.len(), "{}", file.name);
+            assert_eq!(file.columns[0].0, "update");
+        }
+
+        // tasks.dat follows the environment's tasks
+        world.task_env = TaskEnvironment::default_logic77();
+        let tasks = DataFile::tasks(world.task_env.tasks());
+        assert_eq!(tasks.columns.len(), 78);
+        assert_eq!(tasks.row(&world).len(), 78);
+        assert_eq!(tasks.columns[77].0, "logic_3cp");
+    }
+
+    #[test]
+    fn test_dat_header() {
+        let header = DataFile::average().dat_header();
+        assert!(header.starts_with("# Avida Average Data\n#  1: Update\n#  2: Merit\n"));
+        assert!(header.ends_with("\n\n"));
+        assert_eq!(
+            DataFile::tasks(TaskEnvironment::default_logic9().tasks()).csv_header(),
+            "update,not,nand,and,orn,or,andn,nor,xor,equ\n"
+        );
+    }
+
+    #[test]
+    fn test_genotype_diversity() {
+        let mut world = World::with_dimensions_and_seed(10, 10, 1);
+        assert_eq!(genotype_diversity(&world), (0, 0.0));
+
+        world.inject_organism(Organism::ancestor(), 0, 0);
+        world.inject_organism(Organism::ancestor(), 1, 0);
+        assert_eq!(genotype_diversity(&world), (1, 0.0));
+        // Written as "0", not "-0"
+        assert!(genotype_diversity(&world).1.is_sign_positive());
+
+        world.inject_organism(Organism::ancestor_with_tasks(), 2, 0);
+        world.inject_organism(Organism::ancestor_with_tasks(), 3, 0);
+        let (genotypes, diversity) = genotype_diversity(&world);
+        assert_eq!(genotypes, 2);
+        assert!((diversity - 2.0_f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_recorder_appends_rows() {
+        let dir = std::env::temp_dir().join(format!("avida-rs-data-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut world = World::with_dimensions_and_seed(10, 10, 2);
+        world.inject_ancestor();
+        let recorder = DataRecorder::create(&dir, 10, true, world.task_env.tasks()).unwrap();
+        for _ in 0..30 {
+            if recorder.is_due(world.total_updates) {
+                recorder.record(&world).unwrap();
+            }
+            world.update();
+        }
+
+        let count = std::fs::read_to_string(dir.join("count.dat")).unwrap();
+        let rows: Vec<&str> = count
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].starts_with("0 1 1 0 "));
+        assert!(rows[2].starts_with("20 "));
+
+        let dominant = std::fs::read_to_string(dir.join("dominant.dat")).unwrap();
+        let first = dominant
+            .lines()
+            .find(|line| line.starts_with("0 "))
+            .unwrap();
+        assert_eq!(first.split(' ').count(), DataFile::dominant().columns.len());
+        // Abundance 1 of genotype 1, the injected ancestor
+        assert_eq!(first.split(' ').nth(4), Some("1"));
+        assert_eq!(first.split(' ').nth(9), Some("1"));
+
+        let csv = std::fs::read_to_string(dir.join("tasks.csv")).unwrap();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().nth(1).unwrap().starts_with("0,0,"));
+
+        // Recreating the recorder starts fresh files
+        DataRecorder::create(&dir, 10, false, world.task_env.tasks()).unwrap();
+        let average = std::fs::read_to_string(dir.join("average.dat")).unwrap();
+        assert_eq!(average, DataFile::average().dat_header());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_is_due() {
+        let recorder = DataRecorder {
+            dir: std::env::temp_dir(),
+            interval: 0,
+            csv: false,
+            files: Vec::new(),
+        };
+        assert!(!recorder.is_due(0));
+        let recorder = DataRecorder {
+            interval: 50,
+            ..recorder
+        };
+        assert!(recorder.is_due(0) && recorder.is_due(100) && !recorder.is_due(75));
+    }
+}
@@ -0,0 +1,525 @@
+//! Environment files
+//! Reads and writes Avida `environment.cfg` files: `RESOURCE` lines define global and spatial
+//! resources, `REACTION` lines reward tasks through processes and requisites, and `REQUISITE`
+//! lines add requisites to a reaction defined earlier.
+
+use crate::resources::{Region, Resource, Spatial};
+use crate::tasks::{Reaction, Requisite, RewardType, Task, TaskEnvironment};
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Parse an Avida environment file into a task environment
+/// Lines are `RESOURCE name:key=value...` (several resources may share a line),
+/// `REACTION name task [process:key=value...]... [requisite:key=value...]...` and
+/// `REQUISITE reaction [requisite:]key=value...`; `#` starts a comment.
+/// Every task is enabled and capped only by the requisites. Spatial resources
+/// (`geometry=grid` or `geometry=torus`) have no cells until sized with
+/// `TaskEnvironment::resize_resources`.
+pub fn parse(contents: &str) -> Result<TaskEnvironment, String> {
+    let mut env = TaskEnvironment::with_reactions(Vec::new());
+
+    for (line_num, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        parse_line(&mut env, line).map_err(|e| format!("line {}: {}", line_num + 1, e))?;
+    }
+
+    for reaction in env.reactions() {
+        for requisite in &reaction.requisites {
+            for name in requisite.reactions.iter().chain(&requisite.noreactions) {
+                if !env.reactions().iter().any(|r| &r.name == name) {
+                    return Err(format!(
+                        "requisite of {} refers to unknown reaction '{}'",
+                        reaction.name, name
+                    ));
+                }
+            }
+        }
+    }
+    Ok(env)
+}
+
+/// Read and parse an environment file
+pub fn load(path: impl AsRef<Path>) -> Result<TaskEnvironment, String> {
+    let path = path.as_ref();
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Built-in environment by name: `logic9`, `logic77` or `math`
+pub fn preset(name: &str) -> Option<TaskEnvironment> {
+    match name {
+        "logic9" => Some(TaskEnvironment::default_logic9()),
+        "logic77" => Some(TaskEnvironment::default_logic77()),
+        "math" => Some(TaskEnvironment::default_math()),
+        _ => None,
+    }
+}
+
+/// Write an environment in the format read by `parse`
+/// Disabled tasks and `max_task_count` limits are not part of the file.
+pub fn format(env: &TaskEnvironment) -> String {
+    let mut out = String::new();
+    for resource in &env.resources {
+        let _ = writeln!(out, "RESOURCE {}", format_resource(resource));
+    }
+    if !env.resources.is_empty() {
+        out.push('\n');
+    }
+
+    // Consecutive reactions with the same name are the processes of one REACTION
+    let mut i = 0;
+    while i < env.reactions().len() {
+        let first = &env.reactions()[i];
+        let mut line = format!(
+            "REACTION {} {}",
+            first.name,
+            first.task.name().to_lowercase()
+        );
+        while i < env.reactions().len() && env.reactions()[i].name == first.name {
+            line.push(' ');
+            line.push_str(&format_process(&env.reactions()[i]));
+            i += 1;
+        }
+        for requisite in &first.requisites {
+            line.push(' ');
+            line.push_str(&format_requisite(requisite));
+        }
+        let _ = writeln!(out, "{}", line);
+    }
+    out
+}
+
+fn parse_line(env: &mut TaskEnvironment, line: &str) -> Result<(), String> {
+    let mut parts = line.split_whitespace();
+    match parts.next().unwrap_or("") {
+        "RESOURCE" => {
+            let mut any = false;
+            for spec in parts {
+                let resource = parse_resource(spec)?;
+                if env.resource(&resource.name).is_some() {
+                    return Err(format!("resource '{}' is defined twice", resource.name));
+                }
+                env.resources.push(resource);
+                any = true;
+            }
+            if !any {
+                return Err("missing resource after RESOURCE".to_string());
+            }
+        }
+        "REACTION" => {
+            let name = parts
+                .next()
+                .ok_or_else(|| "missing reaction name after REACTION".to_string())?;
+            if env.reactions().iter().any(|r| r.name == name) {
+                return Err(format!("reaction '{}' is defined twice", name));
+            }
+            let task_name = parts
+                .next()
+                .ok_or_else(|| format!("missing task for reaction {}", name))?;
+            let task = Task::from_name(task_name)
+                .ok_or_else(|| format!("unknown task '{}'", task_name))?;
+
+            let mut processes = Vec::new();
+            let mut requisites = Vec::new();
+            for entry in parts {
+                let (kind, fields) = entry.split_once(':').unwrap_or((entry, ""));
+                match kind {
+                    "process" => processes.push(parse_process(Reaction::new(task, 1.0), fields)?),
+                    "requisite" => requisites.push(parse_requisite(fields)?),
+                    _ => return Err(format!("unknown reaction entry '{}'", entry)),
+                }
+            }
+            if processes.is_empty() {
+                processes.push(Reaction::new(task, 1.0));
+            }
+            for mut reaction in processes {
+                if let Some(resource) = &reaction.resource {
+                    if env.resource(resource).is_none() {
+                        return Err(format!("unknown resource '{}'", resource));
+                    }
+                }
+                reaction.name = name.to_string();
+                reaction.requisites = requisites.clone();
+                env.update_reactions(|reactions| reactions.push(reaction));
+            }
+        }
+        "REQUISITE" => {
+            let name = parts
+                .next()
+                .ok_or_else(|| "missing reaction name after REQUISITE".to_string())?;
+            if !env.reactions().iter().any(|r| r.name == name) {
+                return Err(format!("unknown reaction '{}'", name));
+            }
+            let mut any = false;
+            for entry in parts {
+                let requisite = parse_requisite(entry.strip_prefix("requisite:").unwrap_or(entry))?;
+                env.update_reactions(|reactions| {
+                    for reaction in reactions.iter_mut().filter(|r| r.name == name) {
+                        reaction.requisites.push(requisite.clone());
+                    }
+                });
+                any = true;
+            }
+            if !any {
+                return Err(format!("missing requisite for reaction {}", name));
+            }
+        }
+        command => return Err(format!("unknown command '{}'", command)),
+    }
+    Ok(())
+}
+
+/// Split `key=value:key=value` fields
+fn fields(spec: &str) -> impl Iterator<Item = Result<(&str, &str), String>> {
+    spec.split(':')
+        .filter(|field| !field.is_empty())
+        .map(|field| {
+            field
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got '{}'", field))
+        })
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' for {}", value, key))
+}
+
+/// Parse a number that must lie within `range`
+fn parse_in(key: &str, value: &str, range: std::ops::RangeInclusive<f64>) -> Result<f64, String> {
+    let number: f64 = parse_number(key, value)?;
+    if !range.contains(&number) {
+        return Err(format!(
+            "{} must be between {} and {}, got {}",
+            key,
+            range.start(),
+            range.end(),
+            value
+        ));
+    }
+    Ok(number)
+}
+
+/// Parse the fields of a `process:` entry into `reaction`
+fn parse_process(mut reaction: Reaction, spec: &str) -> Result<Reaction, String> {
+    for field in fields(spec) {
+        match field? {
+            ("value", value) => reaction.value = parse_number("value", value)?,
+            ("type", "add") => reaction.reward_type = RewardType::Add,
+            ("type", "mult") => reaction.reward_type = RewardType::Mult,
+            ("type", "pow") => reaction.reward_type = RewardType::Pow,
+            ("type", value) => {
+                return Err(format!(
+                    "process type '{}' is not supported (add, mult or pow)",
+                    value
+                ))
+            }
+            ("resource", name) => reaction.resource = Some(name.to_string()),
+            ("frac", value) => reaction.frac = parse_in("frac", value, 0.0..=1.0)?,
+            ("max", value) => reaction.max = parse_in("max", value, 0.0..=f64::MAX)?,
+            ("min", value) => reaction.min = parse_in("min", value, 0.0..=f64::MAX)?,
+            (key, _) => return Err(format!("unknown process setting '{}'", key)),
+        }
+    }
+    Ok(reaction)
+}
+
+/// Parse the fields of a `requisite:` entry
+fn parse_requisite(spec: &str) -> Result<Requisite, String> {
+    let mut requisite = Requisite::default();
+    for field in fields(spec) {
+        match field? {
+            ("reaction", name) => requisite.reactions.push(name.to_string()),
+            ("noreaction", name) => requisite.noreactions.push(name.to_string()),
+            ("min_count", value) => requisite.min_count = parse_number("min_count", value)?,
+            ("max_count", value) => requisite.max_count = parse_number("max_count", value)?,
+            (key, _) => return Err(format!("unknown requisite setting '{}'", key)),
+        }
+    }
+    Ok(requisite)
+}
+
+/// Parse `name:key=value...` from a RESOURCE line
+fn parse_resource(spec: &str) -> Result<Resource, String> {
+    let (name, spec) = spec.split_once(':').unwrap_or((spec, ""));
+    if name.is_empty() {
+        return Err("missing resource name".to_string());
+    }
+    let mut resource = Resource::new(name, 0.0, 0.0, 0.0);
+    let mut spatial = Spatial::new(0, 0);
+    let mut geometry = "global";
+    let mut spatial_setting = None;
+    // x1, x2, y1, y2 of the inflow and outflow regions
+    let mut inflow = [None; 4];
+    let mut outflow = [None; 4];
+
+    for field in fields(spec) {
+        let (key, value) = field?;
+        let corner = ["x1", "x2", "y1", "y2"].iter().position(|&c| {
+            key.strip_prefix("inflow")
+                .or_else(|| key.strip_prefix("outflow"))
+                == Some(c)
+        });
+        match (key, corner) {
+            ("initial", _) => resource.initial = parse_in(key, value, 0.0..=f64::MAX)?,
+            ("inflow", _) => resource.inflow = parse_in(key, value, 0.0..=f64::MAX)?,
+            ("outflow", _) => resource.outflow = parse_in(key, value, 0.0..=1.0)?,
+            ("geometry", _) => match value {
+                "global" | "grid" | "torus" => geometry = value,
+                _ => {
+                    return Err(format!(
+                        "geometry '{}' is not supported (global, grid or torus)",
+                        value
+                    ))
+                }
+            },
+            (_, Some(corner)) => {
+                let coordinate = Some(parse_number(key, value)?);
+                if key.starts_with("inflow") {
+                    inflow[corner] = coordinate;
+                } else {
+                    outflow[corner] = coordinate;
+                }
+                spatial_setting = Some(key);
+            }
+            ("xdiffuse" | "ydiffuse" | "xgravity" | "ygravity", _) => {
+                let number = match key {
+                    "xdiffuse" | "ydiffuse" => parse_in(key, value, 0.0..=1.0)?,
+                    _ => parse_in(key, value, -1.0..=1.0)?,
+                };
+                match key {
+                    "xdiffuse" => spatial.xdiffuse = number,
+                    "ydiffuse" => spatial.ydiffuse = number,
+                    "xgravity" => spatial.xgravity = number,
+                    _ => spatial.ygravity = number,
+                }
+                spatial_setting = Some(key);
+            }
+            _ => return Err(format!("unknown resource setting '{}'", key)),
+        }
+    }
+
+    if geometry == "global" {
+        if let Some(key) = spatial_setting {
+            return Err(format!(
+                "{} needs a spatial resource (geometry=grid or geometry=torus)",
+                key
+            ));
+        }
+        resource.amount = resource.initial;
+        return Ok(resource);
+    }
+    spatial.torus = geometry == "torus";
+    spatial.inflow_region = region(inflow);
+    spatial.outflow_region = region(outflow);
+    Ok(resource.with_spatial(spatial))
+}
+
+/// A region from optional x1, x2, y1, y2 (None if none is given)
+/// Missing starts are 0 and missing ends equal their start, as in Avida
+fn region([x1, x2, y1, y2]: [Option<usize>; 4]) -> Option<Region> {
+    if [x1, x2, y1, y2].iter().all(Option::is_none) {
+        return None;
+    }
+    let (x1, y1) = (x1.unwrap_or(0), y1.unwrap_or(0));
+    Some(Region {
+        x1,
+        y1,
+        x2: x2.unwrap_or(x1),
+        y2: y2.unwrap_or(y1),
+    })
+}
+
+fn format_resource(resource: &Resource) -> String {
+    let mut out = format!(
+        "{}:initial={}:inflow={}:outflow={}",
+        resource.name, resource.initial, resource.inflow, resource.outflow
+    );
+    if let Some(spatial) = &resource.spatial {
+        let geometry = if spatial.torus { "torus" } else { "grid" };
+        let _ = write!(out, ":geometry={}", geometry);
+        for (prefix, region) in [
+            ("inflow", spatial.inflow_region),
+            ("outflow", spatial.outflow_region),
+        ] {
+            if let Some(r) = region {
+                let _ = write!(
+                    out,
+                    ":{p}x1={}:{p}x2={}:{p}y1={}:{p}y2={}",
+                    r.x1,
+                    r.x2,
+                    r.y1,
+                    r.y2,
+                    p = prefix
+                );
+            }
+        }
+        let _ = write!(
+            out,
+            ":xdiffuse={}:ydiffuse={}:xgravity={}:ygravity={}",
+            spatial.xdiffuse, spatial.ydiffuse, spatial.xgravity, spatial.ygravity
+        );
+    }
+    out
+}
+
+fn format_process(reaction: &Reaction) -> String {
+    let reward_type = match reaction.reward_type {
+        RewardType::Add => "add",
+        RewardType::Mult => "mult",
+        RewardType::Pow => "pow",
+    };
+    let mut out = format!(
+        "process:value={}:type={}:frac={}:max={}:min={}",
+        reaction.value, reward_type, reaction.frac, reaction.max, reaction.min
+    );
+    if let Some(resource) = &reaction.resource {
+        let _ = write!(out, ":resource={}", resource);
+    }
+    out
+}
+
+fn format_requisite(requisite: &Requisite) -> String {
+    let mut out = "requisite".to_string();
+    for name in &requisite.reactions {
+        let _ = write!(out, ":reaction={}", name);
+    }
+    for name in &requisite.noreactions {
+        let _ = write!(out, ":noreaction={}", name);
+    }
+    if requisite.min_count > 0 {
+        let _ = write!(out, ":min_count={}", requisite.min_count);
+    }
+    if requisite.max_count != u32::MAX {
+        let _ = write!(out, ":max_count={}", requisite.max_count);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Avida's default Logic-9 environment.cfg
+    const LOGIC9: &str = "\
+        REACTION  NOT  not   process:value=1.0:type=pow  requisite:max_count=1\n\
+        REACTION  NAND nand  process:value=1.0:type=pow  requisite:max_count=1\n\
+        REACTION  AND  and   process:value=2.0:type=pow  requisite:max_count=1\n\
+        REACTION  ORN  orn   process:value=2.0:type=pow  requisite:max_count=1\n\
+        REACTION  OR   or    process:value=3.0:type=pow  requisite:max_count=1\n\
+        REACTION  ANDN andn  process:value=3.0:type=pow  requisite:max_count=1\n\
+        REACTION  NOR  nor   process:value=4.0:type=pow  requisite:max_count=1\n\
+        REACTION  XOR  xor   process:value=4.0:type=pow  requisite:max_count=1\n\
+        REACTION  EQU  equ   process:value=5.0:type=pow  requisite:max_count=1\n";
+
+    #[test]
+    fn test_parse_logic9() {
+        let env = parse(LOGIC9).unwrap();
+        assert_eq!(env.reactions().len(), 9);
+        let default = TaskEnvironment::default_logic9();
+        // The same reactions as the built-in environment, except that Avida rewards EQU with 2^5
+        for (parsed, built_in) in env.reactions().iter().zip(default.reactions()) {
+            if parsed.task == Task::Equ {
+                assert_eq!(parsed.value, 5.0);
+            } else {
+                assert_eq!(parsed, built_in);
+            }
+        }
+        assert_eq!(env.max_task_count, default.max_task_count);
+    }
+
+    #[test]
+    fn test_parse_resources_and_requisites() {
+        let env = parse(
+            "# limited glucose, spatial sugar flowing in at the left edge\n\
+             RESOURCE glucose:inflow=100:outflow=0.01:initial=1000 oxygen\n\
+             RESOURCE sugar:geometry=torus:inflowx1=0:inflowy1=0:inflowy2=9:xdiffuse=0.5:ygravity=-0.25\n\
+             REACTION NOT not process:resource=glucose:value=0.5:frac=0.0025:max=5:min=1\n\
+             REACTION AND and process:resource=sugar:type=mult process:value=2\n\
+             REQUISITE AND reaction=NOT:noreaction=XOR requisite:min_count=2\n\
+             REACTION XOR xor\n",
+        )
+        .unwrap();
+
+        let glucose = env.resource("glucose").unwrap();
+        assert_eq!(
+            (
+                glucose.initial,
+                glucose.inflow,
+                glucose.outflow,
+                glucose.amount
+            ),
+            (1000.0, 100.0, 0.01, 1000.0)
+        );
+        assert!(glucose.spatial.is_none());
+        assert_eq!(env.resource("oxygen").unwrap().inflow, 0.0);
+        let sugar = env.resource("sugar").unwrap().spatial.as_ref().unwrap();
+        assert!(sugar.torus);
+        assert_eq!(
+            sugar.inflow_region,
+            Some(Region {
+                x1: 0,
+                x2: 0,
+                y1: 0,
+                y2: 9
+            })
+        );
+        assert_eq!(sugar.outflow_region, None);
+        assert_eq!((sugar.xdiffuse, sugar.ydiffuse), (0.5, 1.0));
+        assert_eq!(sugar.ygravity, -0.25);
+
+        let not = &env.reactions()[0];
+        assert_eq!(not.resource.as_deref(), Some("glucose"));
+        assert_eq!(
+            (not.value, not.frac, not.max, not.min),
+            (0.5, 0.0025, 5.0, 1.0)
+        );
+        assert_eq!(not.reward_type, RewardType::Add);
+        // Two processes of AND, both with the two requisites added by REQUISITE
+        let and: Vec<&Reaction> = env.reactions().iter().filter(|r| r.name == "AND").collect();
+        assert_eq!(and.len(), 2);
+        assert_eq!(and[0].reward_type, RewardType::Mult);
+        assert_eq!(and[1].value, 2.0);
+        assert_eq!(and[1].resource, None);
+        assert_eq!(and[0].requisites, and[1].requisites);
+        assert_eq!(and[0].requisites.len(), 2);
+        assert_eq!(and[0].requisites[0].reactions, vec!["NOT".to_string()]);
+        assert_eq!(and[0].requisites[0].noreactions, vec!["XOR".to_string()]);
+        assert_eq!(and[0].requisites[1].min_count, 2);
+        assert_eq!(and[0].requisites[1].max_count, u32::MAX);
+
+        // Round trip through the file format
+        assert_eq!(parse(&format(&env)).unwrap(), env);
+        for preset in ["logic9", "math"] {
+            let env = super::preset(preset).unwrap();
+            assert_eq!(parse(&format(&env)).unwrap(), env);
+        }
+
+        let mut sized = env;
+        sized.resize_resources(10, 10);
+        let sugar = sized.resource("sugar").unwrap();
+        assert_eq!(sugar.spatial.as_ref().unwrap().cells.len(), 100);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |contents: &str| parse(contents).unwrap_err();
+        assert!(error("REACTION NOT negate").contains("unknown task 'negate'"));
+        assert!(error("REACTION NOT not process:resource=glucose").contains("unknown resource"));
+        assert!(error("REACTION NOT not process:type=lin").contains("not supported"));
+        assert!(error("REACTION NOT not process:frac=2").contains("between 0 and 1"));
+        assert!(error("REACTION NOT not process:bogus=1").contains("unknown process setting"));
+        assert!(error("REACTION NOT not\nREACTION NOT nand").contains("defined twice"));
+        assert!(error("REACTION NOT not requisite:reaction=AND").contains("unknown reaction"));
+        assert!(error("REQUISITE NOT max_count=1").starts_with("line 1: unknown reaction"));
+        assert!(error("RESOURCE glucose:xdiffuse=0.5").contains("needs a spatial resource"));
+        assert!(error("RESOURCE glucose:geometry=hex").contains("not supported"));
+        assert!(error("RESOURCE\n").contains("missing resource"));
+        assert!(error("\nCELL 1 2").starts_with("line 2: unknown command 'CELL'"));
+    }
+}
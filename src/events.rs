@@ -0,0 +1,711 @@
+//! Scheduled events (Avida events.cfg)
+//! Each line is `<trigger> <timing> <action> [args...]`: `u 100 Exit`,
+//! `u 0:100:end PrintAverageData` or `g 50 SetCopyMutProb 0.01`.
+//! Events run at the start of `World::update()`, before organisms execute.
+
+use crate::checkpoint;
+use crate::data::DataFile;
+use crate::instruction::Instruction;
+use crate::instset::InstSet;
+use crate::organism::Organism;
+use crate::spop;
+use crate::tasks::Task;
+use crate::world::World;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::path::{Path, PathBuf};
+
+/// When an event fires: `begin[:interval[:end]]` in updates or average generations
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trigger {
+    Update {
+        begin: u64,
+        interval: Option<u64>,
+        end: Option<u64>,
+    },
+    Generation {
+        begin: f64,
+        interval: Option<f64>,
+        end: Option<f64>,
+    },
+}
+
+/// What an event does
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Place an organism in a cell (Avida cell id, y * width + x)
+    Inject {
+        genome: Vec<Instruction>,
+        cell: usize,
+    },
+    /// Infect the host in a cell with a parasite
+    InjectParasite {
+        genome: Vec<Instruction>,
+        cell: usize,
+    },
+    SetCopyMutProb(f64),
+    SetDivideInsProb(f64),
+    SetDivideDelProb(f64),
+    /// Enable or disable rewards for a task
+    SetTask {
+        task: Task,
+        enabled: bool,
+    },
+    /// Kill each organism independently with this probability
+    KillProb(f64),
+    /// Kill exactly this fraction of the population (rounded), chosen at random
+    KillFraction(f64),
+    /// Save a checkpoint (default `checkpoint-<update>.json`)
+    SaveCheckpoint(Option<String>),
+    /// Save a .spop population (default `detail-<update>.spop`)
+    SavePopulation(Option<String>),
+    /// Append a row of a standard data file (default `<name>.dat`)
+    PrintData {
+        name: &'static str,
+        file: Option<String>,
+    },
+    Exit,
+}
+
+/// A trigger with its action
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub trigger: Trigger,
+    pub action: Action,
+    /// Next average generation a generation trigger fires at (None once finished)
+    next_generation: Option<f64>,
+}
+
+impl Event {
+    pub fn new(trigger: Trigger, action: Action) -> Self {
+        let next_generation = match trigger {
+            Trigger::Generation { begin, .. } => Some(begin),
+            Trigger::Update { .. } => None,
+        };
+        Self {
+            trigger,
+            action,
+            next_generation,
+        }
+    }
+
+    /// Whether the event fires now; advances generation triggers past `generation`
+    fn is_due(&mut self, update: u64, generation: f64) -> bool {
+        match self.trigger {
+            Trigger::Update {
+                begin,
+                interval,
+                end,
+            } => {
+                update >= begin
+                    && end.is_none_or(|end| update <= end)
+                    && match interval {
+                        Some(interval) => (update - begin).is_multiple_of(interval),
+                        None => update == begin,
+                    }
+            }
+            Trigger::Generation { interval, end, .. } => {
+                let Some(next) = self.next_generation else {
+                    return false;
+                };
+                if generation < next {
+                    return false;
+                }
+                self.next_generation = interval.map(|interval| {
+                    let mut next = next;
+                    while next <= generation {
+                        next += interval;
+                    }
+                    next
+                });
+                if let (Some(next), Some(end)) = (self.next_generation, end) {
+                    if next > end {
+                        self.next_generation = None;
+                    }
+                }
+                true
+            }
+        }
+    }
+}
+
+/// The events of a run and where their output goes
+#[derive(Debug, Clone, Default)]
+pub struct EventSchedule {
+    pub events: Vec<Event>,
+    /// Directory for files written by events (current directory if None)
+    pub output_dir: Option<PathBuf>,
+    /// Set by the Exit action; `World::update()` does nothing once set
+    pub exit_requested: bool,
+    /// Errors from failed actions (e.g. unwritable files), for the caller to report
+    pub errors: Vec<String>,
+}
+
+impl EventSchedule {
+    pub fn new(events: Vec<Event>) -> Self {
+        Self {
+            events,
+            ..Self::default()
+        }
+    }
+
+    fn output_path(&self, file: &Option<String>, default: String) -> PathBuf {
+        let file = file.clone().unwrap_or(default);
+        match &self.output_dir {
+            Some(dir) => dir.join(file),
+            None => PathBuf::from(file),
+        }
+    }
+
+    /// Run every event due at the world's current update, in file order
+    pub fn process(&mut self, world: &mut World) {
+        if self.events.is_empty() {
+            return;
+        }
+        let update = world.total_updates;
+        let generation = world.average_generation();
+
+        for i in 0..self.events.len() {
+            if !self.events[i].is_due(update, generation) {
+                continue;
+            }
+            let action = self.events[i].action.clone();
+            if let Err(e) = self.apply(&action, world) {
+                crate::debug::log_event(format!("[EVENT] update {}: {}", update, e));
+                self.errors.push(format!("update {}: {}", update, e));
+            }
+        }
+    }
+
+    fn apply(&mut self, action: &Action, world: &mut World) -> Result<(), String> {
+        let update = world.total_updates;
+        match action {
+            Action::Inject { genome, cell } => {
+                let (width, height) = world.dimensions();
+                if *cell >= width * height {
+                    return Err(format!(
+                        "Inject: cell {} is outside the {}x{} world",
+                        cell, width, height
+                    ));
+                }
+                world.inject_organism(Organism::new(genome.clone()), cell % width, cell / width);
+            }
+            Action::InjectParasite { genome, cell } => {
+                let (width, height) = world.dimensions();
+                if *cell >= width * height {
+                    return Err(format!(
+                        "InjectParasite: cell {} is outside the {}x{} world",
+                        cell, width, height
+                    ));
+                }
+                if !world.inject_parasite(genome.clone(), cell % width, cell / width) {
+                    return Err(format!(
+                        "InjectParasite: cell {} has no host with a free thread",
+                        cell
+                    ));
+                }
+            }
+            Action::SetCopyMutProb(p) => world.copy_mutation_rate = *p,
+            Action::SetDivideInsProb(p) => world.insertion_rate = *p,
+            Action::SetDivideDelProb(p) => world.deletion_rate = *p,
+            Action::SetTask { task, enabled } => {
+                world.task_env.set_task_enabled(*task, *enabled);
+            }
+            Action::KillProb(p) => {
+                let cells: Vec<usize> = world.occupied_cells().collect();
+                for cell in cells {
+                    if world.rng().gen::<f64>() < *p {
+                        world.kill_organism(cell);
+                    }
+                }
+            }
+            Action::KillFraction(fraction) => {
+                let cells: Vec<usize> = world.occupied_cells().collect();
+                let count = (cells.len() as f64 * fraction).round() as usize;
+                let victims: Vec<usize> =
+                    cells.choose_multiple(world.rng(), count).copied().collect();
+                for cell in victims {
+                    world.kill_organism(cell);
+                }
+            }
+            Action::SaveCheckpoint(file) => {
+                let path = self.output_path(file, format!("checkpoint-{}.json", update));
+                checkpoint::save(world, path)?;
+            }
+            Action::SavePopulation(file) => {
+                let path = self.output_path(file, format!("detail-{}.spop", update));
+                spop::save(world, path)?;
+            }
+            Action::PrintData { name, file } => {
+                let data = DataFile::by_name(name, world.task_env.tasks())
+                    .ok_or_else(|| format!("unknown data file '{}'", name))?;
+                let path = self.output_path(file, format!("{}.dat", name));
+                data.append_to(&path, world)?;
+            }
+            Action::Exit => self.exit_requested = true,
+        }
+        Ok(())
+    }
+}
+
+fn parse_timing<T: std::str::FromStr>(
+    value: &str,
+    begin_value: T,
+) -> Result<(T, Option<T>, Option<T>), String> {
+    let parse = |part: &str| -> Result<Option<T>, String> {
+        match part {
+            "end" => Ok(None),
+            _ => part
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("invalid timing '{}'", value)),
+        }
+    };
+
+    let parts: Vec<&str> = value.split(':').collect();
+    if parts.len() > 3 || parts.iter().any(|part| part.is_empty()) {
+        return Err(format!("invalid timing '{}'", value));
+    }
+    let begin = match parts[0] {
+        "begin" => begin_value,
+        part => parse(part)?.ok_or_else(|| format!("invalid timing '{}'", value))?,
+    };
+    let interval = match parts.get(1) {
+        Some(part) => Some(parse(part)?.ok_or_else(|| format!("invalid interval in '{}'", value))?),
+        None => None,
+    };
+    let end = match parts.get(2) {
+        Some(part) => parse(part)?,
+        None => None,
+    };
+    Ok((begin, interval, end))
+}
+
+fn parse_trigger(kind: &str, timing: &str) -> Result<Trigger, String> {
+    match kind {
+        "u" => {
+            let (begin, interval, end) = parse_timing::<u64>(timing, 0)?;
+            if interval == Some(0) {
+                return Err(format!("interval must be positive in '{}'", timing));
+            }
+            Ok(Trigger::Update {
+                begin,
+                interval,
+                end,
+            })
+        }
+        "g" => {
+            let (begin, interval, end) = parse_timing::<f64>(timing, 0.0)?;
+            if interval.is_some_and(|interval| interval <= 0.0) {
+                return Err(format!("interval must be positive in '{}'", timing));
+            }
+            Ok(Trigger::Generation {
+                begin,
+                interval,
+                end,
+            })
+        }
+        _ => Err(format!("unknown trigger '{}' (expected u or g)", kind)),
+    }
+}
+
+fn parse_probability(action: &str, args: &[&str]) -> Result<f64, String> {
+    let value = args
+        .first()
+        .ok_or_else(|| format!("{} needs a probability", action))?;
+    let p: f64 = value
+        .parse()
+        .map_err(|_| format!("invalid probability '{}' for {}", value, action))?;
+    if !(0.0..=1.0).contains(&p) {
+        return Err(format!("{} probability {} is outside [0, 1]", action, p));
+    }
+    Ok(p)
+}
+
+/// Parse `[genome file [cell]]` of an inject action, reading the genome with `inst_set`
+fn parse_inject(
+    action: &str,
+    args: &[&str],
+    base_dir: &Path,
+    inst_set: &InstSet,
+    default: fn() -> Organism,
+) -> Result<(Vec<Instruction>, usize), String> {
+    let genome = match args.first() {
+        Some(file) => {
+            let path = base_dir.join(file);
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| format!("{}: failed to read {}: {}", action, path.display(), e))?;
+            let genome = inst_set
+                .parse_organism(&contents)
+                .map_err(|e| format!("{}: {}: {}", action, path.display(), e))?;
+            if genome.is_empty() {
+                return Err(format!("{}: {} is empty", action, path.display()));
+            }
+            genome
+        }
+        None => default().genome,
+    };
+    let cell = match args.get(1) {
+        Some(cell) => cell
+            .parse()
+            .map_err(|_| format!("{}: invalid cell '{}'", action, cell))?,
+        None => 0,
+    };
+    Ok((genome, cell))
+}
+
+fn parse_action(
+    name: &str,
+    args: &[&str],
+    base_dir: &Path,
+    inst_set: &InstSet,
+) -> Result<Action, String> {
+    let file = || args.first().map(|file| file.to_string());
+    let action = match name.to_ascii_lowercase().as_str() {
+        "inject" => {
+            let (genome, cell) =
+                parse_inject("Inject", args, base_dir, inst_set, Organism::ancestor)?;
+            Action::Inject { genome, cell }
+        }
+        "injectparasite" => {
+            let (genome, cell) = parse_inject(
+                "InjectParasite",
+                args,
+                base_dir,
+                inst_set,
+                Organism::parasite_ancestor,
+            )?;
+            Action::InjectParasite { genome, cell }
+        }
+        "setcopymutprob" => Action::SetCopyMutProb(parse_probability(name, args)?),
+        "setdivideinsprob" => Action::SetDivideInsProb(parse_probability(name, args)?),
+        "setdividedelprob" => Action::SetDivideDelProb(parse_probability(name, args)?),
+        "enabletask" | "disabletask" => {
+            let task_name = args
+                .first()
+                .ok_or_else(|| format!("{} needs a task name", name))?;
+            let task = Task::from_name(task_name)
+                .ok_or_else(|| format!("{}: unknown task '{}'", name, task_name))?;
+            Action::SetTask {
+                task,
+                enabled: name.eq_ignore_ascii_case("enabletask"),
+            }
+        }
+        "killprob" => Action::KillProb(parse_probability(name, args)?),
+        "killfraction" => Action::KillFraction(parse_probability(name, args)?),
+        "savecheckpoint" => Action::SaveCheckpoint(file()),
+        "savepopulation" => Action::SavePopulation(file()),
+        "printaveragedata" => Action::PrintData {
+            name: "average",
+            file: file(),
+        },
+        "printcountdata" => Action::PrintData {
+            name: "count",
+            file: file(),
+        },
+        "printtasksdata" => Action::PrintData {
+            name: "tasks",
+            file: file(),
+        },
+        "exit" => Action::Exit,
+        _ => return Err(format!("unknown action '{}'", name)),
+    };
+    Ok(action)
+}
+
+/// Parse the contents of an events file
+/// Genome files named by Inject are resolved relative to `base_dir` and read with the
+/// letters and instructions of `inst_set`
+pub fn parse(contents: &str, base_dir: &Path, inst_set: &InstSet) -> Result<Vec<Event>, String> {
+    let mut events = Vec::new();
+
+    for (line_num, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let context = |e: String| format!("line {}: {}", line_num + 1, e);
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 3 {
+            return Err(context(format!(
+                "expected '<trigger> <timing> <action> [args]', got '{}'",
+                line
+            )));
+        }
+
+        let trigger = parse_trigger(parts[0], parts[1]).map_err(context)?;
+        let action = parse_action(parts[2], &parts[3..], base_dir, inst_set).map_err(context)?;
+        events.push(Event::new(trigger, action));
+    }
+
+    Ok(events)
+}
+
+/// Load an events file, reading injected genomes with `inst_set`
+pub fn load(path: impl AsRef<Path>, inst_set: &InstSet) -> Result<Vec<Event>, String> {
+    let path = path.as_ref();
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read events {}: {}", path.display(), e))?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    parse(&contents, base_dir, inst_set).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(contents: &str) -> Result<Vec<Event>, String> {
+        parse(contents, Path::new("."), &InstSet::default())
+    }
+
+    #[test]
+    fn test_parse_triggers() {
+        let events = parse_str(
+            "# comment\n\
+             u begin Inject\n\
+             u 0:100:end PrintAverageData\n\
+             u 50:10:80 KillProb 0.5\n\
+             g 10.5 SetCopyMutProb 0.01 # trailing comment\n\
+             u 1000 exit\n",
+        )
+        .unwrap();
+
+        assert_eq!(events.len(), 5);
+        assert_eq!(
+            events[0].trigger,
+            Trigger::Update {
+                begin: 0,
+                interval: None,
+                end: None
+            }
+        );
+        assert_eq!(
+            events[0].action,
+            Action::Inject {
+                genome: Organism::ancestor().genome,
+                cell: 0
+            }
+        );
+        assert_eq!(
+            events[1].trigger,
+            Trigger::Update {
+                begin: 0,
+                interval: Some(100),
+                end: None
+            }
+        );
+        assert_eq!(
+            events[2].trigger,
+            Trigger::Update {
+                begin: 50,
+                interval: Some(10),
+                end: Some(80)
+            }
+        );
+        assert_eq!(
+            events[3].trigger,
+            Trigger::Generation {
+                begin: 10.5,
+                interval: None,
+                end: None
+            }
+        );
+        assert_eq!(events[3].action, Action::SetCopyMutProb(0.01));
+        assert_eq!(events[4].action, Action::Exit);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_str("u 10\n").unwrap_err();
+        assert!(err.contains("line 1"), "{}", err);
+        assert!(parse_str("x 10 Exit")
+            .unwrap_err()
+            .contains("unknown trigger"));
+        assert!(parse_str("u ten Exit")
+            .unwrap_err()
+            .contains("invalid timing"));
+        assert!(parse_str("u 0:0 Exit").unwrap_err().contains("positive"));
+        assert!(parse_str("u 0 Explode")
+            .unwrap_err()
+            .contains("unknown action"));
+        assert!(parse_str("u 0 KillProb 2").unwrap_err().contains("outside"));
+        assert!(parse_str("u 0 DisableTask FOO")
+            .unwrap_err()
+            .contains("unknown task"));
+        assert!(parse_str("u 0 Inject missing.org").is_err());
+    }
+
+    #[test]
+    fn test_update_schedule() {
+        let mut event = Event::new(
+            Trigger::Update {
+                begin: 50,
+                interval: Some(10),
+                end: Some(80),
+            },
+            Action::Exit,
+        );
+        let fired: Vec<u64> = (0..100).filter(|&u| event.is_due(u, 0.0)).collect();
+        assert_eq!(fired, vec![50, 60, 70, 80]);
+    }
+
+    #[test]
+    fn test_generation_schedule() {
+        let mut event = Event::new(
+            Trigger::Generation {
+                begin: 2.0,
+                interval: Some(2.0),
+                end: Some(6.0),
+            },
+            Action::Exit,
+        );
+        assert!(!event.is_due(0, 1.5));
+        assert!(event.is_due(1, 2.1));
+        assert!(!event.is_due(2, 3.9));
+        // Skipping past several thresholds fires once
+        assert!(event.is_due(3, 5.0));
+        assert!(event.is_due(4, 6.0));
+        assert!(!event.is_due(5, 100.0));
+    }
+
+    #[test]
+    fn test_events_run_in_update() {
+        let mut world = World::with_dimensions_and_seed(10, 10, 1);
+        world.events = EventSchedule::new(
+            parse_str(
+                "u 0 Inject\n\
+                 u 3 SetCopyMutProb 0.2\n\
+                 u 3 DisableTask NAND\n\
+                 u 5 Exit\n",
+            )
+            .unwrap(),
+        );
+
+        world.update();
+        assert_eq!(world.population_size, 1);
+        assert!(world.get_organism(0, 0).is_some());
+
+        for _ in 0..10 {
+            world.update();
+        }
+        assert_eq!(world.total_updates, 5);
+        assert!(world.events.exit_requested);
+        assert_eq!(world.copy_mutation_rate, 0.2);
+        assert!(!world.task_env.task_enabled[Task::Nand.index()]);
+    }
+
+    #[test]
+    fn test_inject_parasite() {
+        let mut world = World::with_dimensions_and_seed(10, 10, 1);
+        world.events = EventSchedule::new(
+            parse_str(
+                "u 0 Inject\n\
+                 u 0 InjectParasite\n\
+                 u 1 InjectParasite\n",
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            world.events.events[1].action,
+            Action::InjectParasite {
+                genome: Organism::parasite_ancestor().genome,
+                cell: 0
+            }
+        );
+
+        // Single-threaded hosts cannot be infected
+        world.update();
+        assert_eq!(world.parasite_count(), 0);
+        assert_eq!(world.events.errors.len(), 1);
+
+        world.parasites.max_threads = 2;
+        world.update();
+        assert_eq!(world.parasite_count(), 1);
+        assert_eq!(world.total_infections, 1);
+    }
+
+    #[test]
+    fn test_kill_actions() {
+        let mut world = World::with_dimensions_and_seed(10, 10, 2);
+        for cell in 0..100 {
+            world.inject_organism(Organism::ancestor(), cell % 10, cell / 10);
+        }
+        let mut schedule = EventSchedule::new(vec![Event::new(
+            Trigger::Update {
+                begin: 0,
+                interval: None,
+                end: None,
+            },
+            Action::KillFraction(0.25),
+        )]);
+        schedule.process(&mut world);
+        assert_eq!(world.population_size, 75);
+        assert_eq!(world.total_deaths, 25);
+
+        schedule.events[0].action = Action::KillProb(1.0);
+        schedule.process(&mut world);
+        assert_eq!(world.population_size, 0);
+    }
+
+    #[test]
+    fn test_file_actions() {
+        let dir = std::env::temp_dir().join(format!("avida-rs-events-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("anc.org"), "h-alloc\nh-search\nnop-C\nnop-A\n").unwrap();
+        let path = dir.join("events.cfg");
+        std::fs::write(
+            &path,
+            "u begin Inject anc.org 15\n\
+             u 0:1 PrintAverageData\n\
+             u 2 SaveCheckpoint\n\
+             u 2 SavePopulation saved.spop\n",
+        )
+        .unwrap();
+        let events = load(&path, &InstSet::default()).unwrap();
+
+        let mut world = World::with_dimensions_and_seed(10, 10, 3);
+        world.events = EventSchedule::new(events);
+        world.events.output_dir = Some(dir.clone());
+        for _ in 0..3 {
+            world.update();
+        }
+
+        assert!(world.events.errors.is_empty(), "{:?}", world.events.errors);
+        assert_eq!(world.get_organism(5, 1).unwrap().genome_size(), 4);
+        assert!(dir.join("checkpoint-2.json").exists());
+        assert!(dir.join("saved.spop").exists());
+        let average = std::fs::read_to_string(dir.join("average.dat")).unwrap();
+        assert!(average.starts_with("# Avida Average Data"));
+        assert_eq!(
+            average
+                .lines()
+                .filter(|l| l.starts_with(|c: char| c.is_ascii_digit()))
+                .count(),
+            3
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_inject_uses_instruction_set_letters() {
+        let dir = std::env::temp_dir().join(format!("avida-rs-events-set-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("org.seq"), "ba\n").unwrap();
+        let inst_set = InstSet::parse("h-alloc\nh-copy\nh-divide\n").unwrap();
+
+        let events = parse("u begin Inject org.seq", &dir, &inst_set).unwrap();
+        match &events[0].action {
+            Action::Inject { genome, .. } => {
+                assert_eq!(genome, &vec![Instruction::HCopy, Instruction::HAlloc])
+            }
+            action => panic!("unexpected action {:?}", action),
+        }
+        // Instructions outside the set are rejected
+        std::fs::write(dir.join("org.seq"), "bd\n").unwrap();
+        assert!(parse("u begin Inject org.seq", &dir, &inst_set).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,7 +1,10 @@
-use crate::cpu::HeadType;
+use crate::cpu::{HeadType, CPU};
 use crate::instruction::Instruction;
+use crate::instset::InstSet;
 use crate::organism::Organism;
-use crate::tasks::{Task, TaskDetector};
+use crate::parasite::ParasiteSettings;
+use crate::tasks::{Task, TaskDetector, TaskEnvironment, TaskSet};
+use rand::Rng;
 use std::sync::atomic::{AtomicU32, Ordering};
 
 /// Track instruction execution for monitoring
@@ -10,10 +13,63 @@ static INSTRUCTIONS_EXECUTED: AtomicU32 = AtomicU32::new(0);
 /// Execute a single instruction for an organism
 /// Returns (should_divide, completed_task)
 /// The organism's counters (instruction_count, gestation_cycles) are incremented
-pub fn execute_instruction(
+/// All randomness (IO inputs, copy mutations) is drawn from `rng`
+/// Copy mutations draw replacement instructions from `inst_set`
+/// Tasks are rewarded by the reactions of `environment`, consuming its resources
+pub fn execute_instruction<R: Rng + ?Sized>(
     organism: &mut Organism,
     task_detector: &mut TaskDetector,
+    environment: &mut TaskEnvironment,
     copy_mutation_rate: f64,
+    inst_set: &InstSet,
+    rng: &mut R,
+) -> (bool, Option<Task>) {
+    execute(
+        organism,
+        task_detector,
+        Reward::Host(environment),
+        copy_mutation_rate,
+        inst_set,
+        rng,
+    )
+}
+
+/// Execute a single instruction of a parasite thread, like `execute_instruction`
+/// Tasks are only detected, and rewarded from the parasite's own task table without
+/// using the environment's reactions or resources
+pub fn execute_parasite_instruction<R: Rng + ?Sized>(
+    parasite: &mut Organism,
+    task_detector: &mut TaskDetector,
+    settings: &ParasiteSettings,
+    copy_mutation_rate: f64,
+    inst_set: &InstSet,
+    rng: &mut R,
+) -> (bool, Option<Task>) {
+    execute(
+        parasite,
+        task_detector,
+        Reward::Parasite(settings),
+        copy_mutation_rate,
+        inst_set,
+        rng,
+    )
+}
+
+/// How a completed task changes the merit of the organism performing it
+enum Reward<'a> {
+    /// The environment's reaction, which may consume resources
+    Host(&'a mut TaskEnvironment),
+    /// The parasite's multiplier for the task
+    Parasite(&'a ParasiteSettings),
+}
+
+fn execute<R: Rng + ?Sized>(
+    organism: &mut Organism,
+    task_detector: &mut TaskDetector,
+    mut reward: Reward,
+    copy_mutation_rate: f64,
+    inst_set: &InstSet,
+    rng: &mut R,
 ) -> (bool, Option<Task>) {
     let mut should_divide = false;
     let mut completed_task = None;
@@ -201,26 +257,35 @@ pub fn execute_instruction(
                 let output = organism.cpu.registers[1];
                 organism.cpu.output_buffer.push(output);
 
-                // Check for task completion
-                if let Some((task, _inputs)) = task_detector.check_output(output) {
-                    let task_idx = task as u8;
+                // Check for completion of the tasks that can be rewarded
+                let tasks = match &reward {
+                    Reward::Host(environment) => environment.tasks(),
+                    Reward::Parasite(_) => TaskSet::all(),
+                };
+                if let Some((task, _inputs)) = task_detector.check_output(output, &tasks) {
+                    let task_idx = task.index() as u8;
+                    // Apply the reward with cap to prevent overflow
+                    let old_merit = organism.merit;
+                    organism.merit = match &mut reward {
+                        Reward::Host(environment) => environment.reward(
+                            task,
+                            old_merit,
+                            organism.position,
+                            &organism.task_counts,
+                        ),
+                        // Parasites earn their table's bonus the first time only
+                        Reward::Parasite(_) if organism.has_completed_task(task_idx) => old_merit,
+                        Reward::Parasite(settings) => old_merit * settings.task_reward(task),
+                    }
+                    .min(1000.0); // Cap merit to prevent infinity
                     if !organism.has_completed_task(task_idx) {
-                        organism.complete_task(task_idx);
-                        // Apply merit bonus with cap to prevent overflow
-                        let multiplier = task.merit_multiplier();
-                        organism.merit *= multiplier;
-                        organism.merit = organism.merit.min(1000.0); // Cap merit to prevent infinity
                         completed_task = Some(task);
-
                         crate::debug::log_event(format!(
-                            "[TASK] Organism gen:{} completed {:?} - merit: {} -> {} ({}x)",
-                            organism.generation,
-                            task,
-                            organism.merit / multiplier,
-                            organism.merit,
-                            multiplier
+                            "[TASK] Organism gen:{} completed {:?} - merit: {} -> {}",
+                            organism.generation, task, old_merit, organism.merit
                         ));
                     }
+                    organism.complete_task(task_idx);
                 }
 
                 // Input new value into BX
@@ -230,8 +295,7 @@ pub fn execute_instruction(
                     task_detector.add_input(input);
                 } else {
                     // Generate new random input if buffer is empty
-                    use rand::Rng;
-                    let input = rand::thread_rng().gen::<i32>();
+                    let input = rng.gen::<i32>();
                     organism.cpu.registers[1] = input;
                     task_detector.add_input(input);
                 }
@@ -242,8 +306,15 @@ pub fn execute_instruction(
                 organism.allocate_child();
             }
 
-            Instruction::HDivide => {
+            Instruction::MaxAlloc => {
+                // Room for the offspring to grow to twice the parent's size
+                organism.allocate_child_sized(2 * organism.genome.len());
+            }
+
+            Instruction::HDivide | Instruction::DivideSex | Instruction::Inject => {
                 // Check if organism is ready to divide
+                // (the world sends offspring of divide-sex to the birth chamber and
+                // offspring of inject to a neighboring host)
                 if organism.child_genome.is_some()
                     && organism.child_copy_progress >= organism.genome.len()
                 {
@@ -253,7 +324,7 @@ pub fn execute_instruction(
             }
 
             Instruction::HCopy => {
-                let _copied = organism.copy_instruction(copy_mutation_rate);
+                let _copied = organism.copy_instruction(copy_mutation_rate, inst_set, rng);
                 // Logging handled in organism.copy_instruction()
             }
 
@@ -346,6 +417,140 @@ pub fn execute_instruction(
                 let position = organism.cpu.registers[2] as usize % organism.genome.len();
                 organism.cpu.flow_head = position;
             }
+
+            // Extended conditionals
+            Instruction::IfGrt | Instruction::IfEqu => {
+                let reg2_idx = organism.cpu.get_register_index(&organism.genome, 2); // CX default
+                let val1 = organism.cpu.registers[1];
+                let val2 = organism.cpu.registers[reg2_idx];
+                let holds = if inst == Instruction::IfGrt {
+                    val1 > val2
+                } else {
+                    val1 == val2
+                };
+                if !holds {
+                    organism.cpu.skip_next = true;
+                }
+            }
+
+            Instruction::IfNot0
+            | Instruction::IfEqu0
+            | Instruction::IfGrt0
+            | Instruction::IfLess0
+            | Instruction::IfBit1 => {
+                let reg_idx = organism.cpu.get_register_index(&organism.genome, 1); // Default BX
+                let value = organism.cpu.registers[reg_idx];
+                let holds = match inst {
+                    Instruction::IfNot0 => value != 0,
+                    Instruction::IfEqu0 => value == 0,
+                    Instruction::IfGrt0 => value > 0,
+                    Instruction::IfLess0 => value < 0,
+                    _ => value & 1 == 1,
+                };
+                if !holds {
+                    organism.cpu.skip_next = true;
+                }
+            }
+
+            // Conditional head movement
+            Instruction::MovHeadIfNEqu | Instruction::MovHeadIfLess => {
+                // Compare a register with the next one (BX with CX by default)
+                let reg1_idx = organism.cpu.get_register_index(&organism.genome, 1);
+                let reg2_idx = CPU::next_register(reg1_idx);
+                let val1 = organism.cpu.registers[reg1_idx];
+                let val2 = organism.cpu.registers[reg2_idx];
+                let holds = if inst == Instruction::MovHeadIfNEqu {
+                    val1 != val2
+                } else {
+                    val1 < val2
+                };
+                if holds {
+                    // Same as mov-head: the IP lands on the flow head after advancing
+                    organism.cpu.ip = (organism.cpu.flow_head + organism.genome.len() - 1)
+                        % organism.genome.len();
+                }
+            }
+
+            // Extended arithmetic
+            Instruction::Mult | Instruction::Div | Instruction::Mod => {
+                let dst_idx = organism.cpu.get_register_index(&organism.genome, 1); // Default BX
+                let bx = organism.cpu.registers[1];
+                let cx = organism.cpu.registers[2];
+                // Division by zero fails and leaves the registers unchanged
+                let result = match inst {
+                    Instruction::Mult => Some(bx.wrapping_mul(cx)),
+                    Instruction::Div => (cx != 0).then(|| bx.wrapping_div(cx)),
+                    _ => (cx != 0).then(|| bx.wrapping_rem(cx)),
+                };
+                if let Some(result) = result {
+                    organism.cpu.registers[dst_idx] = result;
+                }
+            }
+
+            Instruction::Sqrt => {
+                let reg_idx = organism.cpu.get_register_index(&organism.genome, 1); // Default BX
+                let value = organism.cpu.registers[reg_idx];
+                if value > 1 {
+                    organism.cpu.registers[reg_idx] = (value as f64).sqrt() as i32;
+                }
+            }
+
+            // Extended stack operations
+            Instruction::PushOther => {
+                let reg_idx = organism.cpu.get_register_index(&organism.genome, 1); // Default BX
+                let value = organism.cpu.registers[reg_idx];
+                organism.cpu.push_other(value);
+            }
+
+            Instruction::PopOther => {
+                let value = organism.cpu.pop_other();
+                let reg_idx = organism.cpu.get_register_index(&organism.genome, 1); // Default BX
+                organism.cpu.registers[reg_idx] = value;
+            }
+
+            Instruction::HeadPush => {
+                let head_type = organism.cpu.get_head_from_nop(&organism.genome);
+                let position = organism.cpu.get_head(head_type);
+                organism.cpu.push(position as i32);
+            }
+
+            Instruction::HeadPop => {
+                let head_type = organism.cpu.get_head_from_nop(&organism.genome);
+                let position = organism.cpu.pop().rem_euclid(organism.genome.len() as i32) as usize;
+                // Like mov-head, the IP lands on the position after advancing
+                let target = if head_type == HeadType::InstructionPointer {
+                    (position + organism.genome.len() - 1) % organism.genome.len()
+                } else {
+                    position
+                };
+                organism.cpu.set_head(head_type, target);
+            }
+
+            // Labels and replication
+            Instruction::Label => {
+                // The label's nops mark a position and are not executed
+                let template_start = organism
+                    .cpu
+                    .advance_head(organism.cpu.ip, organism.genome.len());
+                let template_len = organism
+                    .cpu
+                    .read_template(&organism.genome, template_start)
+                    .len();
+                for _ in 0..template_len {
+                    organism.cpu.ip = organism
+                        .cpu
+                        .advance_head(organism.cpu.ip, organism.genome.len());
+                }
+            }
+
+            Instruction::Repro => {
+                // Copy the whole genome (with copy mutations) and divide
+                organism.allocate_child();
+                for _ in 0..organism.genome.len() {
+                    organism.copy_instruction(copy_mutation_rate, inst_set, rng);
+                }
+                should_divide = true;
+            }
         }
     }
 
@@ -359,12 +564,26 @@ pub fn execute_instruction(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn test_rng() -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(42)
+    }
 
     #[test]
     fn test_nop_execution() {
+        let mut rng = test_rng();
         let mut org = Organism::new(vec![Instruction::NopA]);
         let mut detector = TaskDetector::new();
-        let (divide, _) = execute_instruction(&mut org, &mut detector, 0.0);
+        let (divide, _) = execute_instruction(
+            &mut org,
+            &mut detector,
+            &mut TaskEnvironment::default_logic9(),
+            0.0,
+            &InstSet::default(),
+            &mut rng,
+        );
         assert!(!divide);
         assert_eq!(org.cpu.ip, 0); // Wrapped around
         assert_eq!(org.instruction_count, 1); // Counter incremented
@@ -372,12 +591,20 @@ mod tests {
 
     #[test]
     fn test_add_instruction() {
+        let mut rng = test_rng();
         let mut org = Organism::new(vec![Instruction::Add]);
         org.cpu.registers[1] = 10; // BX
         org.cpu.registers[2] = 5; // CX
 
         let mut detector = TaskDetector::new();
-        execute_instruction(&mut org, &mut detector, 0.0);
+        execute_instruction(
+            &mut org,
+            &mut detector,
+            &mut TaskEnvironment::default_logic9(),
+            0.0,
+            &InstSet::default(),
+            &mut rng,
+        );
 
         assert_eq!(org.cpu.registers[1], 15); // BX = 10 + 5
         assert_eq!(org.instruction_count, 1);
@@ -385,11 +612,19 @@ mod tests {
 
     #[test]
     fn test_inc_instruction() {
+        let mut rng = test_rng();
         let mut org = Organism::new(vec![Instruction::Inc]);
         org.cpu.registers[1] = 42;
 
         let mut detector = TaskDetector::new();
-        execute_instruction(&mut org, &mut detector, 0.0);
+        execute_instruction(
+            &mut org,
+            &mut detector,
+            &mut TaskEnvironment::default_logic9(),
+            0.0,
+            &InstSet::default(),
+            &mut rng,
+        );
 
         assert_eq!(org.cpu.registers[1], 43);
         assert_eq!(org.instruction_count, 1);
@@ -397,6 +632,7 @@ mod tests {
 
     #[test]
     fn test_push_pop() {
+        let mut rng = test_rng();
         let mut org = Organism::new(vec![Instruction::Push, Instruction::Pop]);
         org.cpu.registers[1] = 100;
 
@@ -404,19 +640,34 @@ mod tests {
 
         // Push
         org.cpu.ip = 0;
-        execute_instruction(&mut org, &mut detector, 0.0);
+        execute_instruction(
+            &mut org,
+            &mut detector,
+            &mut TaskEnvironment::default_logic9(),
+            0.0,
+            &InstSet::default(),
+            &mut rng,
+        );
         assert_eq!(org.cpu.active_stack_ref().len(), 1);
         assert_eq!(org.instruction_count, 1);
 
         // Pop
         org.cpu.registers[1] = 0; // Clear BX
-        execute_instruction(&mut org, &mut detector, 0.0);
+        execute_instruction(
+            &mut org,
+            &mut detector,
+            &mut TaskEnvironment::default_logic9(),
+            0.0,
+            &InstSet::default(),
+            &mut rng,
+        );
         assert_eq!(org.cpu.registers[1], 100);
         assert_eq!(org.instruction_count, 2);
     }
 
     #[test]
     fn test_instruction_counter_increments() {
+        let mut rng = test_rng();
         let mut org = Organism::new(vec![
             Instruction::NopA,
             Instruction::NopB,
@@ -425,26 +676,56 @@ mod tests {
         let mut detector = TaskDetector::new();
 
         assert_eq!(org.instruction_count, 0);
-        execute_instruction(&mut org, &mut detector, 0.0);
+        execute_instruction(
+            &mut org,
+            &mut detector,
+            &mut TaskEnvironment::default_logic9(),
+            0.0,
+            &InstSet::default(),
+            &mut rng,
+        );
         assert_eq!(org.instruction_count, 1);
-        execute_instruction(&mut org, &mut detector, 0.0);
+        execute_instruction(
+            &mut org,
+            &mut detector,
+            &mut TaskEnvironment::default_logic9(),
+            0.0,
+            &InstSet::default(),
+            &mut rng,
+        );
         assert_eq!(org.instruction_count, 2);
-        execute_instruction(&mut org, &mut detector, 0.0);
+        execute_instruction(
+            &mut org,
+            &mut detector,
+            &mut TaskEnvironment::default_logic9(),
+            0.0,
+            &InstSet::default(),
+            &mut rng,
+        );
         assert_eq!(org.instruction_count, 3);
     }
 
     #[test]
     fn test_gestation_cycles_increment() {
+        let mut rng = test_rng();
         let mut org = Organism::new(vec![Instruction::NopA]);
         let mut detector = TaskDetector::new();
 
         assert_eq!(org.gestation_cycles, 0);
-        execute_instruction(&mut org, &mut detector, 0.0);
+        execute_instruction(
+            &mut org,
+            &mut detector,
+            &mut TaskEnvironment::default_logic9(),
+            0.0,
+            &InstSet::default(),
+            &mut rng,
+        );
         assert_eq!(org.gestation_cycles, 1);
     }
 
     #[test]
     fn test_minimal_rts_genome_does_not_signal_divide() {
+        let mut rng = test_rng();
         use crate::instruction::parse_genome;
 
         let genome = parse_genome("rts").expect("valid minimal genome");
@@ -452,7 +733,14 @@ mod tests {
         let mut detector = TaskDetector::new();
 
         for _ in 0..30 {
-            let (should_divide, _) = execute_instruction(&mut org, &mut detector, 0.0);
+            let (should_divide, _) = execute_instruction(
+                &mut org,
+                &mut detector,
+                &mut TaskEnvironment::default_logic9(),
+                0.0,
+                &InstSet::default(),
+                &mut rng,
+            );
             assert!(org.child_copy_progress < org.genome.len());
             assert!(
                 !should_divide,
@@ -463,6 +751,7 @@ mod tests {
 
     #[test]
     fn test_minimal_rtsy_genome_does_not_divide() {
+        let mut rng = test_rng();
         use crate::instruction::parse_genome;
 
         let genome = parse_genome("rtsy").expect("valid minimal genome");
@@ -471,15 +760,189 @@ mod tests {
 
         let mut divided = false;
         for _ in 0..500 {
-            let (should_divide, _) = execute_instruction(&mut org, &mut detector, 0.0);
-            if should_divide {
-                if org.divide(0.0, 0.0).is_some() {
-                    divided = true;
-                    break;
-                }
+            let (should_divide, _) = execute_instruction(
+                &mut org,
+                &mut detector,
+                &mut TaskEnvironment::default_logic9(),
+                0.0,
+                &InstSet::default(),
+                &mut rng,
+            );
+            if should_divide
+                && org
+                    .divide(0.0, 0.0, &InstSet::default(), &mut rng)
+                    .is_some()
+            {
+                divided = true;
+                break;
             }
         }
 
         assert!(!divided, "minimal rtsy genome should not be able to divide");
     }
+
+    /// Execute `steps` instructions; true if any of them signalled a divide
+    fn run(org: &mut Organism, steps: usize) -> bool {
+        let mut rng = test_rng();
+        let mut detector = TaskDetector::new();
+        let mut divide = false;
+        for _ in 0..steps {
+            let (should_divide, _) = execute_instruction(
+                org,
+                &mut detector,
+                &mut TaskEnvironment::default_logic9(),
+                0.0,
+                &InstSet::default(),
+                &mut rng,
+            );
+            divide |= should_divide;
+        }
+        divide
+    }
+
+    /// A genome written with the full set's letters, where extended instructions are upper-case
+    fn extended(sequence: &str) -> Vec<Instruction> {
+        InstSet::full().parse_sequence(sequence).unwrap()
+    }
+
+    #[test]
+    fn test_extended_conditionals() {
+        // if-grt: BX 5 > CX 3, so inc runs
+        let mut org = Organism::new(extended("Al"));
+        org.cpu.registers[1] = 5;
+        org.cpu.registers[2] = 3;
+        run(&mut org, 2);
+        assert_eq!(org.cpu.registers[1], 6);
+
+        // if-equ: BX 5 != CX 3, so inc is skipped
+        let mut org = Organism::new(extended("Bl"));
+        org.cpu.registers[1] = 5;
+        org.cpu.registers[2] = 3;
+        run(&mut org, 2);
+        assert_eq!(org.cpu.registers[1], 5);
+
+        // if-bit-1 on CX (nop-C modifier): CX 3 is odd, so the nop-C runs as a no-op
+        let mut org = Organism::new(extended("Gcl"));
+        org.cpu.registers[2] = 3;
+        run(&mut org, 3);
+        assert_eq!(org.cpu.registers[1], 1);
+
+        // if-less-0 on BX 0 skips the next instruction
+        let mut org = Organism::new(extended("Fll"));
+        run(&mut org, 3);
+        assert_eq!(org.cpu.registers[1], 1);
+    }
+
+    #[test]
+    fn test_extended_arithmetic() {
+        let mut org = Organism::new(extended("JKL"));
+        org.cpu.registers[1] = 7;
+        org.cpu.registers[2] = 3;
+        run(&mut org, 1);
+        assert_eq!(org.cpu.registers[1], 21); // mult
+        run(&mut org, 1);
+        assert_eq!(org.cpu.registers[1], 7); // div
+        run(&mut org, 1);
+        assert_eq!(org.cpu.registers[1], 1); // mod
+
+        // Division by zero leaves BX unchanged
+        let mut org = Organism::new(extended("K"));
+        org.cpu.registers[1] = 9;
+        run(&mut org, 1);
+        assert_eq!(org.cpu.registers[1], 9);
+
+        let mut org = Organism::new(extended("M"));
+        org.cpu.registers[1] = 17;
+        run(&mut org, 1);
+        assert_eq!(org.cpu.registers[1], 4);
+    }
+
+    #[test]
+    fn test_extended_stacks_and_heads() {
+        // push-other leaves the active stack empty; pop-other brings the value back
+        let mut org = Organism::new(extended("NfO"));
+        org.cpu.registers[1] = 42;
+        run(&mut org, 1);
+        assert!(org.cpu.active_stack_ref().is_empty());
+        org.cpu.registers[1] = 0;
+        run(&mut org, 2);
+        assert_eq!(org.cpu.registers[1], 42);
+
+        // h-push the IP (at 1), then h-pop it back at position 3: execution resumes at 1
+        let mut org = Organism::new(extended("lPlQll"));
+        run(&mut org, 4);
+        assert_eq!(org.cpu.ip, 1);
+
+        // mov-head-if-n-equ jumps to the flow head when BX != CX
+        let mut org = Organism::new(extended("Hllll"));
+        org.cpu.flow_head = 3;
+        org.cpu.registers[1] = 1;
+        run(&mut org, 1);
+        assert_eq!(org.cpu.ip, 3);
+
+        // label skips its nops
+        let mut org = Organism::new(extended("Rabcl"));
+        run(&mut org, 2);
+        assert_eq!(org.cpu.registers[1], 1);
+    }
+
+    #[test]
+    fn test_repro_divides_in_one_instruction() {
+        let genome = extended("Scccccccccccccccc");
+        let mut org = Organism::new(genome.clone());
+        assert!(run(&mut org, 1));
+        let mut rng = test_rng();
+        let offspring = org.divide(0.0, 0.0, &InstSet::default(), &mut rng).unwrap();
+        assert_eq!(offspring.genome, genome);
+    }
+
+    #[test]
+    fn test_parasites_are_rewarded_from_their_own_table() {
+        use crate::instruction::parse_genome;
+
+        let settings = ParasiteSettings {
+            task_rewards: ParasiteSettings::parse_task_rewards("not=3").unwrap(),
+            ..ParasiteSettings::default()
+        };
+        let perform_not = |parasite: bool| {
+            let mut org = Organism::new(parse_genome("q").unwrap());
+            let mut detector = TaskDetector::new();
+            detector.add_input(5);
+            detector.add_input(6);
+            org.cpu.registers[1] = !6;
+            let mut rng = test_rng();
+            let inst_set = InstSet::default();
+            let (_, task) = if parasite {
+                execute_parasite_instruction(
+                    &mut org,
+                    &mut detector,
+                    &settings,
+                    0.0,
+                    &inst_set,
+                    &mut rng,
+                )
+            } else {
+                execute_instruction(
+                    &mut org,
+                    &mut detector,
+                    &mut TaskEnvironment::default_logic9(),
+                    0.0,
+                    &inst_set,
+                    &mut rng,
+                )
+            };
+            assert_eq!(task, Some(Task::Not));
+            org.merit
+        };
+        assert_eq!(perform_not(true), 3.0);
+        assert_eq!(perform_not(false), Task::Not.merit_multiplier());
+    }
+
+    #[test]
+    fn test_maxalloc_allocates_twice_the_genome() {
+        let mut org = Organism::new(extended("Tcccccccccccccccc"));
+        run(&mut org, 1);
+        assert_eq!(org.child_genome.as_ref().map(Vec::len), Some(34));
+        assert_eq!(org.cpu.registers[0], 17);
+    }
 }
@@ -0,0 +1,504 @@
+//! Genotype tracking
+//! Organisms with identical genomes share a genotype. The registry assigns each new genome
+//! an id at birth, links it to its parent genotype and tracks abundance over time.
+//! Extinct genotypes are kept only while a living genotype descends from them.
+
+use crate::instruction::{genome_to_string, Instruction};
+use crate::organism::{Mutation, Organism};
+use crate::tasks::{Task, TaskSet};
+use crate::world::World;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+
+/// One genotype and its history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Genotype {
+    pub id: u64,
+    pub genome: Vec<Instruction>,
+    /// Genotype of the parent of the first organism (None for injected organisms)
+    pub parent_id: Option<u64>,
+    pub update_born: u64,
+    /// Generation of the first organism
+    pub generation_born: u32,
+    /// Number of genotype-changing ancestors (0 for injected genotypes)
+    pub depth: u32,
+    /// Currently living organisms
+    pub abundance: usize,
+    pub max_abundance: usize,
+    /// Organisms of this genotype that have ever lived
+    pub total_organisms: u64,
+    pub update_extinct: Option<u64>,
+    /// Tasks performed by organisms of this genotype that have died
+    #[serde(default)]
+    pub tasks: TaskSet,
+    /// Highest merit reached by an organism of this genotype that has died
+    #[serde(default)]
+    pub merit: f64,
+    /// Mutations from the parent genotype's genome to this one (empty without a parent)
+    #[serde(default)]
+    pub mutations: Vec<Mutation>,
+    /// Child genotypes still in the registry
+    children: usize,
+}
+
+impl Genotype {
+    pub fn is_extinct(&self) -> bool {
+        self.abundance == 0
+    }
+
+    pub fn genome_string(&self) -> String {
+        genome_to_string(&self.genome)
+    }
+}
+
+/// Registry of living genotypes and their extinct ancestors
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "SavedRegistry", into = "SavedRegistry")]
+pub struct GenotypeRegistry {
+    genotypes: BTreeMap<u64, Genotype>,
+    /// Living genotypes by genome
+    by_genome: HashMap<Vec<Instruction>, u64>,
+    next_id: u64,
+}
+
+/// Serialized form of the registry (the genome index is rebuilt on load)
+#[derive(Serialize, Deserialize)]
+struct SavedRegistry {
+    next_id: u64,
+    genotypes: Vec<Genotype>,
+}
+
+impl From<SavedRegistry> for GenotypeRegistry {
+    fn from(saved: SavedRegistry) -> Self {
+        let genotypes: BTreeMap<u64, Genotype> = saved
+            .genotypes
+            .into_iter()
+            .map(|genotype| (genotype.id, genotype))
+            .collect();
+        let by_genome = genotypes
+            .values()
+            .filter(|genotype| !genotype.is_extinct())
+            .map(|genotype| (genotype.genome.clone(), genotype.id))
+            .collect();
+        Self {
+            genotypes,
+            by_genome,
+            next_id: saved.next_id,
+        }
+    }
+}
+
+impl From<GenotypeRegistry> for SavedRegistry {
+    fn from(registry: GenotypeRegistry) -> Self {
+        Self {
+            next_id: registry.next_id,
+            genotypes: registry.genotypes.into_values().collect(),
+        }
+    }
+}
+
+impl Default for GenotypeRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GenotypeRegistry {
+    pub fn new() -> Self {
+        Self {
+            genotypes: BTreeMap::new(),
+            by_genome: HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Record a new organism and return its genotype id
+    /// A genome matching a living genotype joins it; otherwise a new genotype is created,
+    /// keeping the mutations that produced it from its parent
+    pub fn add_organism(
+        &mut self,
+        genome: &[Instruction],
+        parent_id: Option<u64>,
+        mutations: &[Mutation],
+        update: u64,
+        generation: u32,
+    ) -> u64 {
+        let id = match self.by_genome.get(genome) {
+            Some(&id) => id,
+            None => {
+                let id = self.next_id;
+                self.next_id += 1;
+
+                let parent = parent_id.and_then(|parent_id| self.genotypes.get_mut(&parent_id));
+                let depth = match parent {
+                    Some(parent) => {
+                        parent.children += 1;
+                        parent.depth + 1
+                    }
+                    None => 0,
+                };
+
+                self.genotypes.insert(
+                    id,
+                    Genotype {
+                        id,
+                        genome: genome.to_vec(),
+                        parent_id: parent_id.filter(|_| depth > 0),
+                        update_born: update,
+                        generation_born: generation,
+                        depth,
+                        abundance: 0,
+                        max_abundance: 0,
+                        total_organisms: 0,
+                        update_extinct: None,
+                        tasks: TaskSet::default(),
+                        merit: 0.0,
+                        mutations: if depth > 0 {
+                            mutations.to_vec()
+                        } else {
+                            Vec::new()
+                        },
+                        children: 0,
+                    },
+                );
+                self.by_genome.insert(genome.to_vec(), id);
+                id
+            }
+        };
+
+        let genotype = self
+            .genotypes
+            .get_mut(&id)
+            .expect("indexed genotype exists");
+        genotype.abundance += 1;
+        genotype.total_organisms += 1;
+        genotype.max_abundance = genotype.max_abundance.max(genotype.abundance);
+        id
+    }
+
+    /// Record the death of an organism of genotype `id` that performed `tasks` and reached `merit`
+    pub fn remove_organism(&mut self, id: u64, tasks: TaskSet, merit: f64, update: u64) {
+        let Some(genotype) = self.genotypes.get_mut(&id) else {
+            return;
+        };
+        if genotype.abundance == 0 {
+            return;
+        }
+        genotype.tasks |= tasks;
+        genotype.merit = genotype.merit.max(merit);
+        genotype.abundance -= 1;
+        if genotype.abundance == 0 {
+            genotype.update_extinct = Some(update);
+            let genome = genotype.genome.clone();
+            self.by_genome.remove(&genome);
+            self.prune(id);
+        }
+    }
+
+    /// Drop an extinct genotype without descendants, then its ancestors if they become prunable
+    fn prune(&mut self, mut id: u64) {
+        while let Some(genotype) = self.genotypes.get(&id) {
+            if !genotype.is_extinct() || genotype.children > 0 {
+                return;
+            }
+            let parent_id = genotype.parent_id;
+            self.genotypes.remove(&id);
+
+            match parent_id.and_then(|parent_id| self.genotypes.get_mut(&parent_id)) {
+                Some(parent) => {
+                    parent.children -= 1;
+                    id = parent.id;
+                }
+                None => return,
+            }
+        }
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Genotype> {
+        self.genotypes.get(&id)
+    }
+
+    /// All genotypes in the registry (living and extinct ancestors), by id
+    pub fn iter(&self) -> impl Iterator<Item = &Genotype> {
+        self.genotypes.values()
+    }
+
+    /// Living genotypes, by id
+    pub fn living(&self) -> impl Iterator<Item = &Genotype> {
+        self.iter().filter(|genotype| !genotype.is_extinct())
+    }
+
+    pub fn living_count(&self) -> usize {
+        self.by_genome.len()
+    }
+
+    /// Number of genotypes in the registry (living and retained extinct ones)
+    pub fn len(&self) -> usize {
+        self.genotypes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.genotypes.is_empty()
+    }
+
+    /// The most abundant living genotype (oldest id on ties)
+    pub fn dominant(&self) -> Option<&Genotype> {
+        self.living()
+            .max_by(|a, b| a.abundance.cmp(&b.abundance).then(b.id.cmp(&a.id)))
+    }
+}
+
+/// A genotype with statistics averaged over its living organisms
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenotypeReport {
+    pub genotype: Genotype,
+    pub merit: f64,
+    /// Average CPU cycles per gestation (0 if no organism has divided yet)
+    pub gestation_time: f64,
+    /// Merit divided by gestation time (0 if unknown)
+    pub fitness: f64,
+    /// Number of living organisms of the genotype that performed each task, by `Task::index`
+    pub task_counts: Vec<usize>,
+}
+
+impl GenotypeReport {
+    /// Tasks performed by at least one organism of the genotype
+    pub fn tasks(&self) -> Vec<Task> {
+        Task::all()
+            .into_iter()
+            .filter(|&task| self.task_counts[task.index()] > 0)
+            .collect()
+    }
+
+    /// Format the report as `key value` lines
+    pub fn format(&self) -> String {
+        let genotype = &self.genotype;
+        let mut out = String::new();
+        let _ = writeln!(out, "id {}", genotype.id);
+        let _ = writeln!(
+            out,
+            "parent_id {}",
+            genotype
+                .parent_id
+                .map_or("(none)".to_string(), |id| id.to_string())
+        );
+        let _ = writeln!(out, "abundance {}", genotype.abundance);
+        let _ = writeln!(out, "max_abundance {}", genotype.max_abundance);
+        let _ = writeln!(out, "update_born {}", genotype.update_born);
+        let _ = writeln!(out, "depth {}", genotype.depth);
+        let _ = writeln!(out, "length {}", genotype.genome.len());
+        let _ = writeln!(out, "merit {:.4}", self.merit);
+        let _ = writeln!(out, "gestation_time {:.2}", self.gestation_time);
+        let _ = writeln!(out, "fitness {:.6}", self.fitness);
+        let tasks: Vec<&str> = self.tasks().iter().map(|task| task.name()).collect();
+        let _ = writeln!(
+            out,
+            "tasks {}",
+            if tasks.is_empty() {
+                "(none)".to_string()
+            } else {
+                tasks.join(",")
+            }
+        );
+        let _ = writeln!(out, "sequence {}", genotype.genome_string());
+        out
+    }
+}
+
+/// Average the living organisms of a genotype into a report
+pub(crate) fn summarize<'a>(
+    genotype: &Genotype,
+    organisms: impl IntoIterator<Item = &'a Organism>,
+) -> GenotypeReport {
+    let mut count = 0usize;
+    let mut merit = 0.0;
+    let mut gestation_total = 0u64;
+    let mut gestation_count = 0usize;
+    let mut task_counts = vec![0usize; Task::COUNT];
+    for org in organisms {
+        count += 1;
+        merit += org.merit;
+        if org.gestation_time > 0 {
+            gestation_total += org.gestation_time;
+            gestation_count += 1;
+        }
+        for task in org.tasks_completed.iter() {
+            task_counts[task.index()] += 1;
+        }
+    }
+
+    let merit = if count > 0 { merit / count as f64 } else { 0.0 };
+    let gestation_time = if gestation_count > 0 {
+        gestation_total as f64 / gestation_count as f64
+    } else {
+        0.0
+    };
+    let fitness = if gestation_time > 0.0 {
+        merit / gestation_time
+    } else {
+        0.0
+    };
+
+    GenotypeReport {
+        genotype: genotype.clone(),
+        merit,
+        gestation_time,
+        fitness,
+        task_counts,
+    }
+}
+
+/// Statistics of a genotype's living organisms (None if it is not in the registry)
+pub fn report(world: &World, id: u64) -> Option<GenotypeReport> {
+    let genotype = world.genotypes().get(id)?;
+    let organisms = world
+        .grid
+        .iter()
+        .flatten()
+        .filter(|org| org.genotype_id == Some(id));
+    Some(summarize(genotype, organisms))
+}
+
+/// Report on the current dominant genotype
+pub fn dominant_report(world: &World) -> Option<GenotypeReport> {
+    let id = world.genotypes().dominant()?.id;
+    report(world, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::parse_genome;
+
+    fn genome(s: &str) -> Vec<Instruction> {
+        parse_genome(s).unwrap()
+    }
+
+    #[test]
+    fn test_same_genome_shares_genotype() {
+        let mut registry = GenotypeRegistry::new();
+        let a = registry.add_organism(&genome("abc"), None, &[], 0, 0);
+        let b = registry.add_organism(&genome("abc"), Some(a), &[], 5, 1);
+        let c = registry.add_organism(&genome("abd"), Some(a), &[], 7, 1);
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(registry.get(a).unwrap().abundance, 2);
+        assert_eq!(registry.get(a).unwrap().update_born, 0);
+        assert_eq!(registry.get(c).unwrap().parent_id, Some(a));
+        assert_eq!(registry.get(c).unwrap().depth, 1);
+        assert_eq!(registry.get(c).unwrap().update_born, 7);
+        assert_eq!(registry.living_count(), 2);
+    }
+
+    #[test]
+    fn test_abundance_and_extinction() {
+        let mut registry = GenotypeRegistry::new();
+        let a = registry.add_organism(&genome("abc"), None, &[], 0, 0);
+        registry.add_organism(&genome("abc"), Some(a), &[], 1, 1);
+        registry.add_organism(&genome("abc"), Some(a), &[], 2, 1);
+        registry.remove_organism(a, TaskSet::default(), 1.0, 3);
+        let genotype = registry.get(a).unwrap();
+        assert_eq!(genotype.abundance, 2);
+        assert_eq!(genotype.max_abundance, 3);
+        assert_eq!(genotype.total_organisms, 3);
+
+        let child = registry.add_organism(&genome("abd"), Some(a), &[], 4, 2);
+        registry.remove_organism(a, TaskSet::default(), 1.0, 5);
+        registry.remove_organism(a, TaskSet::default(), 1.0, 6);
+
+        // Extinct, but kept as the ancestor of a living genotype
+        let genotype = registry.get(a).unwrap();
+        assert!(genotype.is_extinct());
+        assert_eq!(genotype.update_extinct, Some(6));
+        assert_eq!(registry.living_count(), 1);
+
+        // The same genome appearing again is a new genotype
+        let again = registry.add_organism(&genome("abc"), None, &[], 7, 0);
+        assert_ne!(again, a);
+
+        // Once the last descendant dies, the whole extinct lineage is pruned
+        registry.remove_organism(child, TaskSet::default(), 1.0, 8);
+        assert!(registry.get(child).is_none());
+        assert!(registry.get(a).is_none());
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn test_dominant() {
+        let mut registry = GenotypeRegistry::new();
+        assert!(registry.dominant().is_none());
+        let a = registry.add_organism(&genome("abc"), None, &[], 0, 0);
+        let b = registry.add_organism(&genome("abd"), None, &[], 0, 0);
+        assert_eq!(registry.dominant().unwrap().id, a);
+        registry.add_organism(&genome("abd"), None, &[], 1, 0);
+        assert_eq!(registry.dominant().unwrap().id, b);
+    }
+
+    #[test]
+    fn test_serde_rebuilds_index() {
+        let mut registry = GenotypeRegistry::new();
+        let a = registry.add_organism(&genome("abc"), None, &[], 0, 0);
+        let json = serde_json::to_string(&registry).unwrap();
+        let mut restored: GenotypeRegistry = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.add_organism(&genome("abc"), None, &[], 1, 0), a);
+        assert_eq!(restored.get(a).unwrap().abundance, 2);
+        assert_eq!(
+            restored.add_organism(&genome("cba"), None, &[], 1, 0),
+            a + 1
+        );
+    }
+
+    #[test]
+    fn test_world_tracks_genotypes() {
+        let mut world = World::with_dimensions_and_seed(20, 20, 6);
+        world.copy_mutation_rate = 0.02;
+        world.inject_ancestor_with_tasks();
+        for _ in 0..300 {
+            world.update();
+        }
+
+        let living: usize = world.genotypes().living().map(|g| g.abundance).sum();
+        assert_eq!(living, world.population_size);
+        for org in world.grid.iter().flatten() {
+            let genotype = world.genotypes().get(org.genotype_id.unwrap()).unwrap();
+            assert_eq!(genotype.genome, org.genome);
+        }
+        assert!(world.genotypes().living_count() > 1);
+
+        // Every retained genotype traces back to the injected ancestor
+        for genotype in world.genotypes().iter() {
+            let mut current = genotype;
+            while let Some(parent) = current.parent_id {
+                current = world.genotypes().get(parent).unwrap();
+            }
+            assert_eq!(current.id, 1);
+        }
+    }
+
+    #[test]
+    fn test_dominant_report() {
+        let mut world = World::with_dimensions_and_seed(10, 10, 1);
+        assert!(dominant_report(&world).is_none());
+
+        let mut org = Organism::ancestor();
+        org.merit = 4.0;
+        org.gestation_time = 400;
+        org.complete_task(Task::Nand.index() as u8);
+        world.inject_organism(org, 0, 0);
+        let mut org = Organism::ancestor();
+        org.merit = 2.0;
+        world.inject_organism(org, 1, 0);
+        world.inject_organism(Organism::ancestor_with_tasks(), 2, 0);
+
+        let report = dominant_report(&world).unwrap();
+        assert_eq!(report.genotype.abundance, 2);
+        assert_eq!(report.genotype.genome, Organism::ancestor().genome);
+        assert_eq!(report.merit, 3.0);
+        assert_eq!(report.gestation_time, 400.0);
+        assert_eq!(report.fitness, 3.0 / 400.0);
+        assert_eq!(report.tasks(), vec![Task::Nand]);
+        assert!(report.format().contains("tasks NAND\n"));
+    }
+}
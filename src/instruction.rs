@@ -1,6 +1,13 @@
-/// Avida instruction set - 26 instructions (a-z)
-/// Based on the default instruction set from original Avida
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+use crate::instset::{InstSet, LETTERS};
+use serde::{Deserialize, Serialize};
+
+/// Avida instructions - the 26 default instructions (a-z) and extended ones
+/// Based on the default instruction set from original Avida; extended instructions
+/// have no letter of their own and are only used when an instruction set (`instset`)
+/// lists them, which also gives them their letters
+/// Serialized by Avida name (e.g. "h-alloc") so saved files stay readable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "&'static str", try_from = "String")]
 pub enum Instruction {
     // No-operation instructions (a-c)
     NopA, // a - no-op, modifies previous instruction or acts as label
@@ -45,6 +52,38 @@ pub enum Instruction {
     // Flow control (y-z)
     IfLabel, // y - test if complement label was most recently copied
     SetFlow, // z - move flow-head to position in CX register
+
+    // Extended conditionals
+    IfGrt,   // if BX > complement, execute next
+    IfEqu,   // if BX == complement, execute next
+    IfNot0,  // if BX != 0, execute next
+    IfEqu0,  // if BX == 0, execute next
+    IfGrt0,  // if BX > 0, execute next
+    IfLess0, // if BX < 0, execute next
+    IfBit1,  // if the lowest bit of BX is 1, execute next
+
+    // Conditional head movement
+    MovHeadIfNEqu, // jump IP to flow-head if BX != next register
+    MovHeadIfLess, // jump IP to flow-head if BX < next register
+
+    // Extended arithmetic
+    Mult, // BX = BX * CX
+    Div,  // BX = BX / CX (fails if CX is 0)
+    Mod,  // BX = BX % CX (fails if CX is 0)
+    Sqrt, // BX = square root of BX
+
+    // Extended stack operations
+    PushOther, // push BX onto the inactive stack
+    PopOther,  // pop the inactive stack into BX
+    HeadPush,  // push the IP position onto the active stack
+    HeadPop,   // pop the active stack into the IP
+
+    // Labels and replication
+    Label,     // mark the following nops as a label and skip over them
+    Repro,     // copy the whole genome and divide in one instruction
+    MaxAlloc,  // allocate an offspring of up to twice the genome size (h-alloc variant)
+    DivideSex, // like h-divide, but the offspring waits in the birth chamber for a mate
+    Inject,    // like h-divide, but the offspring infects a neighbor as a parasite
 }
 
 impl Instruction {
@@ -82,6 +121,8 @@ impl Instruction {
     }
 
     /// Convert an instruction to its character representation
+    /// Extended instructions are written with their letter in the full set
+    /// (`InstSet::full()`), as the default set has no letters for them
     pub fn to_char(self) -> char {
         match self {
             Instruction::NopA => 'a',
@@ -110,9 +151,85 @@ impl Instruction {
             Instruction::GetHead => 'x',
             Instruction::IfLabel => 'y',
             Instruction::SetFlow => 'z',
+            _ => {
+                let index = EXTENDED.iter().position(|&inst| inst == self).unwrap_or(0);
+                LETTERS.chars().nth(26 + index).unwrap_or('?')
+            }
         }
     }
 
+    /// Get the Avida instruction name (as used in .org and instset files)
+    pub fn name(self) -> &'static str {
+        match self {
+            Instruction::NopA => "nop-A",
+            Instruction::NopB => "nop-B",
+            Instruction::NopC => "nop-C",
+            Instruction::IfNEqu => "if-n-equ",
+            Instruction::IfLess => "if-less",
+            Instruction::Pop => "pop",
+            Instruction::Push => "push",
+            Instruction::SwapStk => "swap-stk",
+            Instruction::Swap => "swap",
+            Instruction::ShiftR => "shift-r",
+            Instruction::ShiftL => "shift-l",
+            Instruction::Inc => "inc",
+            Instruction::Dec => "dec",
+            Instruction::Add => "add",
+            Instruction::Sub => "sub",
+            Instruction::Nand => "nand",
+            Instruction::IO => "IO",
+            Instruction::HAlloc => "h-alloc",
+            Instruction::HDivide => "h-divide",
+            Instruction::HCopy => "h-copy",
+            Instruction::HSearch => "h-search",
+            Instruction::MovHead => "mov-head",
+            Instruction::JmpHead => "jmp-head",
+            Instruction::GetHead => "get-head",
+            Instruction::IfLabel => "if-label",
+            Instruction::SetFlow => "set-flow",
+            Instruction::IfGrt => "if-grt",
+            Instruction::IfEqu => "if-equ",
+            Instruction::IfNot0 => "if-not-0",
+            Instruction::IfEqu0 => "if-equ-0",
+            Instruction::IfGrt0 => "if-grt-0",
+            Instruction::IfLess0 => "if-less-0",
+            Instruction::IfBit1 => "if-bit-1",
+            Instruction::MovHeadIfNEqu => "mov-head-if-n-equ",
+            Instruction::MovHeadIfLess => "mov-head-if-less",
+            Instruction::Mult => "mult",
+            Instruction::Div => "div",
+            Instruction::Mod => "mod",
+            Instruction::Sqrt => "sqrt",
+            Instruction::PushOther => "push-other",
+            Instruction::PopOther => "pop-other",
+            Instruction::HeadPush => "h-push",
+            Instruction::HeadPop => "h-pop",
+            Instruction::Label => "label",
+            Instruction::Repro => "repro",
+            Instruction::MaxAlloc => "maxalloc",
+            Instruction::DivideSex => "divide-sex",
+            Instruction::Inject => "inject",
+        }
+    }
+
+    /// Every instruction, in letter order (the 26 default ones, then the extended ones)
+    pub fn all() -> Vec<Self> {
+        ('a'..='z')
+            .filter_map(Self::from_char)
+            .chain(EXTENDED)
+            .collect()
+    }
+
+    /// Whether this is an extended instruction (outside the default a-z set)
+    pub fn is_extended(self) -> bool {
+        EXTENDED.contains(&self)
+    }
+
+    /// Look up an instruction by its Avida name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all().into_iter().find(|inst| inst.name() == name)
+    }
+
     /// Get the complement nop instruction for template matching
     /// nop-A complements nop-B
     /// nop-B complements nop-C
@@ -146,6 +263,20 @@ impl Instruction {
     }
 }
 
+impl From<Instruction> for &'static str {
+    fn from(inst: Instruction) -> Self {
+        inst.name()
+    }
+}
+
+impl TryFrom<String> for Instruction {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        Instruction::from_name(&name).ok_or_else(|| format!("Unknown instruction name: '{}'", name))
+    }
+}
+
 /// Parse a genome string into a vector of instructions
 pub fn parse_genome(s: &str) -> Result<Vec<Instruction>, String> {
     s.chars()
@@ -156,6 +287,86 @@ pub fn parse_genome(s: &str) -> Result<Vec<Instruction>, String> {
         .collect()
 }
 
+/// Extended instructions, in the order they follow the default ones in the full set
+pub const EXTENDED: [Instruction; 22] = [
+    Instruction::IfGrt,
+    Instruction::IfEqu,
+    Instruction::IfNot0,
+    Instruction::IfEqu0,
+    Instruction::IfGrt0,
+    Instruction::IfLess0,
+    Instruction::IfBit1,
+    Instruction::MovHeadIfNEqu,
+    Instruction::MovHeadIfLess,
+    Instruction::Mult,
+    Instruction::Div,
+    Instruction::Mod,
+    Instruction::Sqrt,
+    Instruction::PushOther,
+    Instruction::PopOther,
+    Instruction::HeadPush,
+    Instruction::HeadPop,
+    Instruction::Label,
+    Instruction::Repro,
+    Instruction::MaxAlloc,
+    Instruction::DivideSex,
+    Instruction::Inject,
+];
+
+/// Instruction order of C++ Avida's `heads_default` instruction set (letters a-z)
+/// The names match avida-rs, but the letters differ (h-alloc is 'w' there, 'r' here)
+pub const HEADS_DEFAULT: [Instruction; 26] = [
+    Instruction::NopA,
+    Instruction::NopB,
+    Instruction::NopC,
+    Instruction::IfNEqu,
+    Instruction::IfLess,
+    Instruction::IfLabel,
+    Instruction::MovHead,
+    Instruction::JmpHead,
+    Instruction::GetHead,
+    Instruction::SetFlow,
+    Instruction::ShiftR,
+    Instruction::ShiftL,
+    Instruction::Inc,
+    Instruction::Dec,
+    Instruction::Push,
+    Instruction::Pop,
+    Instruction::SwapStk,
+    Instruction::Swap,
+    Instruction::Add,
+    Instruction::Sub,
+    Instruction::Nand,
+    Instruction::HCopy,
+    Instruction::HAlloc,
+    Instruction::HDivide,
+    Instruction::IO,
+    Instruction::HSearch,
+];
+
+/// Parse a sequence written with C++ Avida `heads_default` letters
+pub fn parse_heads_default_genome(s: &str) -> Result<Vec<Instruction>, String> {
+    s.chars()
+        .map(|c| match c.to_ascii_lowercase() {
+            letter @ 'a'..='z' => Ok(HEADS_DEFAULT[(letter as u8 - b'a') as usize]),
+            _ => Err(format!("Invalid instruction character: '{}'", c)),
+        })
+        .collect()
+}
+
+/// Convert a genome to a sequence using C++ Avida `heads_default` letters
+/// Instructions outside `heads_default` are written as `?`
+pub fn genome_to_heads_default(genome: &[Instruction]) -> String {
+    InstSet::heads_default().sequence(genome)
+}
+
+/// Parse the contents of an organism file
+/// Accepts either a single genome string ("rutyabsv...") or the Avida .org
+/// format with one instruction name per line. Blank lines and '#' comments are ignored.
+pub fn parse_organism_file(contents: &str) -> Result<Vec<Instruction>, String> {
+    InstSet::default().parse_organism(contents)
+}
+
 /// Convert a genome vector back to a string
 pub fn genome_to_string(genome: &[Instruction]) -> String {
     genome.iter().map(|i| i.to_char()).collect()
@@ -202,6 +413,30 @@ mod tests {
         assert_eq!(Instruction::from_char('Z'), Some(Instruction::SetFlow));
     }
 
+    #[test]
+    fn test_extended_instructions() {
+        let all = Instruction::all();
+        assert_eq!(all.len(), 48);
+        assert_eq!(all.iter().filter(|inst| inst.is_extended()).count(), 22);
+        let full = InstSet::full();
+        for inst in all {
+            assert_eq!(full.from_letter(inst.to_char()), Some(inst));
+            assert_eq!(Instruction::from_name(inst.name()), Some(inst));
+        }
+        // The default letters never name an extended instruction
+        assert_eq!(Instruction::IfGrt.to_char(), 'A');
+        assert_eq!(Instruction::from_char('A'), Some(Instruction::NopA));
+        assert_eq!(Instruction::from_name("if-grt"), Some(Instruction::IfGrt));
+        assert_eq!(
+            Instruction::from_name("h-push"),
+            Some(Instruction::HeadPush)
+        );
+        assert!(!Instruction::HAlloc.is_extended());
+
+        // Not part of C++ Avida's heads_default letters
+        assert_eq!(genome_to_heads_default(&[Instruction::Repro]), "?");
+    }
+
     #[test]
     fn test_from_char_invalid() {
         assert_eq!(Instruction::from_char('1'), None);
@@ -337,6 +572,40 @@ mod tests {
     }
 
     #[test]
+    fn test_name_roundtrip() {
+        for c in 'a'..='z' {
+            let inst = Instruction::from_char(c).unwrap();
+            assert_eq!(Instruction::from_name(inst.name()), Some(inst));
+        }
+        assert_eq!(Instruction::HAlloc.name(), "h-alloc");
+        assert_eq!(Instruction::from_name("IO"), Some(Instruction::IO));
+        assert_eq!(Instruction::from_name("h-bogus"), None);
+    }
+
+    #[test]
+    fn test_parse_organism_file_sequence() {
+        let genome =
+            parse_organism_file("# ancestor\nrutyabsvacccccccccccccccccccccccccccccccccccccccbc\n")
+                .unwrap();
+        assert_eq!(genome.len(), 50);
+        assert_eq!(genome[0], Instruction::HAlloc);
+    }
+
+    #[test]
+    fn test_parse_organism_file_names() {
+        let contents = "h-alloc\nh-search\nh-copy   # copy loop\n\nif-label\nnop-C\nnop-A\n";
+        let genome = parse_organism_file(contents).unwrap();
+        assert_eq!(genome_to_string(&genome), "rutyca");
+    }
+
+    #[test]
+    fn test_parse_organism_file_unknown_name() {
+        let result = parse_organism_file("h-alloc\nh-bogus\n");
+        assert!(result.unwrap_err().contains("h-bogus"));
+    }
+
+    #[test]
+    #[allow(clippy::clone_on_copy)]
     fn test_instruction_copy_clone() {
         let inst = Instruction::NopA;
         let copied = inst;
@@ -352,4 +621,28 @@ mod tests {
         let debug_str = format!("{:?}", inst);
         assert!(debug_str.contains("NopA"));
     }
+
+    #[test]
+    fn test_heads_default_letters() {
+        // Default ancestor from C++ Avida's heads_default instruction set
+        let genome = parse_heads_default_genome("wzcagcccccccccccczvfcaxgab").unwrap();
+        assert_eq!(genome[0], Instruction::HAlloc);
+        assert_eq!(genome[1], Instruction::HSearch);
+        assert_eq!(genome[4], Instruction::MovHead);
+        assert_eq!(genome[18], Instruction::HCopy);
+        assert_eq!(genome[19], Instruction::IfLabel);
+        assert_eq!(genome[22], Instruction::HDivide);
+        assert_eq!(
+            genome_to_heads_default(&genome),
+            "wzcagcccccccccccczvfcaxgab"
+        );
+        assert_eq!(genome_to_string(&genome[..2]), "ru");
+        assert!(parse_heads_default_genome("wz1").is_err());
+
+        // Every instruction appears exactly once
+        for c in 'a'..='z' {
+            let inst = Instruction::from_char(c).unwrap();
+            assert_eq!(HEADS_DEFAULT.iter().filter(|&&i| i == inst).count(), 1);
+        }
+    }
 }
@@ -0,0 +1,537 @@
+//! Instruction sets
+//! Avida `instset` files choose which instructions organisms can use, the letter each one is
+//! written with (letters are assigned in listed order), how often mutations produce it
+//! (redundancy) and how many CPU cycles it takes to execute (cost, plus an initial cost the
+//! first time an organism executes it in each gestation).
+
+use crate::instruction::{Instruction, HEADS_DEFAULT};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Letters of the instructions of a set, in order
+pub const LETTERS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// One instruction of a set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstEntry {
+    pub inst: Instruction,
+    /// Relative weight of the instruction when mutations draw a random instruction
+    pub redundancy: u32,
+    /// CPU cycles the instruction takes to execute
+    pub cost: u32,
+    /// Extra CPU cycles paid the first time the instruction executes in a gestation
+    #[serde(default)]
+    pub initial_cost: u32,
+}
+
+impl InstEntry {
+    pub fn new(inst: Instruction) -> Self {
+        Self {
+            inst,
+            redundancy: 1,
+            cost: 1,
+            initial_cost: 0,
+        }
+    }
+}
+
+/// An ordered set of instructions with mutation weights and execution costs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "SavedInstSet", into = "SavedInstSet")]
+pub struct InstSet {
+    name: String,
+    entries: Vec<InstEntry>,
+}
+
+/// Serialized form of an instruction set, validated when loaded
+#[derive(Serialize, Deserialize)]
+struct SavedInstSet {
+    name: String,
+    entries: Vec<InstEntry>,
+}
+
+impl TryFrom<SavedInstSet> for InstSet {
+    type Error = String;
+
+    fn try_from(saved: SavedInstSet) -> Result<Self, Self::Error> {
+        InstSet::new(saved.name, saved.entries)
+    }
+}
+
+impl From<InstSet> for SavedInstSet {
+    fn from(set: InstSet) -> Self {
+        Self {
+            name: set.name,
+            entries: set.entries,
+        }
+    }
+}
+
+/// The avida-rs instruction set: the 26 default instructions in avida-rs letter order
+impl Default for InstSet {
+    fn default() -> Self {
+        let core: Vec<Instruction> = Instruction::all()
+            .into_iter()
+            .filter(|inst| !inst.is_extended())
+            .collect();
+        Self::from_instructions("default", &core)
+    }
+}
+
+impl InstSet {
+    /// Build a set, checking that it is usable
+    pub fn new(name: impl Into<String>, entries: Vec<InstEntry>) -> Result<Self, String> {
+        if entries.is_empty() {
+            return Err("instruction set has no instructions".to_string());
+        }
+        if entries.len() > LETTERS.len() {
+            return Err(format!(
+                "instruction set has {} instructions, at most {} are supported",
+                entries.len(),
+                LETTERS.len()
+            ));
+        }
+        for (i, entry) in entries.iter().enumerate() {
+            if entries[..i].iter().any(|e| e.inst == entry.inst) {
+                return Err(format!("instruction {} is listed twice", entry.inst.name()));
+            }
+            if entry.cost == 0 {
+                return Err(format!("cost of {} must be at least 1", entry.inst.name()));
+            }
+        }
+        let total: u32 = entries.iter().map(|entry| entry.redundancy).sum();
+        if total == 0 {
+            return Err("at least one instruction needs a nonzero redundancy".to_string());
+        }
+        if total > u16::MAX as u32 {
+            return Err(format!(
+                "total redundancy {} is above the maximum of {}",
+                total,
+                u16::MAX
+            ));
+        }
+        Ok(Self {
+            name: name.into(),
+            entries,
+        })
+    }
+
+    /// A set of the given instructions with redundancy 1 and cost 1
+    fn from_instructions(name: &str, instructions: &[Instruction]) -> Self {
+        Self {
+            name: name.to_string(),
+            entries: instructions.iter().copied().map(InstEntry::new).collect(),
+        }
+    }
+
+    /// C++ Avida's `heads_default` set, with its letters
+    pub fn heads_default() -> Self {
+        Self::from_instructions("heads_default", &HEADS_DEFAULT)
+    }
+
+    /// Every instruction: the default set, then the extended instructions (letters A-T)
+    pub fn full() -> Self {
+        Self::from_instructions("full", &Instruction::all())
+    }
+
+    /// Parse an Avida instset file
+    /// Accepts the current format (`INSTSET name:hw_type=0` followed by
+    /// `INST h-alloc:redundancy=2:cost=3:initial_cost=10` lines) and the classic format with one
+    /// `name [redundancy [cost [initial_cost ...]]]` line per instruction, where further columns
+    /// are ignored.
+    /// `#` starts a comment.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut name = None;
+        let mut entries = Vec::new();
+
+        for (line_num, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let parse_line = |name: &mut Option<String>| -> Result<Option<InstEntry>, String> {
+                let mut parts = line.split_whitespace();
+                match parts.next().unwrap_or("") {
+                    "INSTSET" => {
+                        if name.is_some() {
+                            return Err("only one INSTSET per file is supported".to_string());
+                        }
+                        *name = Some(parse_instset_header(parts.next().unwrap_or(""))?);
+                        Ok(None)
+                    }
+                    "INST" => {
+                        let spec = parts
+                            .next()
+                            .ok_or_else(|| "missing instruction name after INST".to_string())?;
+                        parse_inst(spec).map(Some)
+                    }
+                    inst_name => {
+                        let mut entry = InstEntry::new(lookup(inst_name)?);
+                        if let Some(value) = parts.next() {
+                            entry.redundancy = parse_value("redundancy", value)?;
+                        }
+                        if let Some(value) = parts.next() {
+                            entry.cost = parse_value("cost", value)?;
+                        }
+                        if let Some(value) = parts.next() {
+                            entry.initial_cost = parse_value("initial_cost", value)?;
+                        }
+                        Ok(Some(entry))
+                    }
+                }
+            };
+            if let Some(entry) =
+                parse_line(&mut name).map_err(|e| format!("line {}: {}", line_num + 1, e))?
+            {
+                entries.push(entry);
+            }
+        }
+
+        Self::new(name.unwrap_or_else(|| "custom".to_string()), entries)
+    }
+
+    /// Read and parse an instset file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Write the set in the current instset format
+    pub fn format(&self) -> String {
+        let mut out = format!("INSTSET {}:hw_type=0\n\n", self.name);
+        for (entry, letter) in self.entries.iter().zip(LETTERS.chars()) {
+            let _ = writeln!(
+                out,
+                "INST {}:redundancy={}:cost={}:initial_cost={}   # {}",
+                entry.inst.name(),
+                entry.redundancy,
+                entry.cost,
+                entry.initial_cost,
+                letter
+            );
+        }
+        out
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Instructions of the set in letter order
+    pub fn entries(&self) -> &[InstEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn entry(&self, inst: Instruction) -> Option<&InstEntry> {
+        self.entries.iter().find(|entry| entry.inst == inst)
+    }
+
+    pub fn contains(&self, inst: Instruction) -> bool {
+        self.entry(inst).is_some()
+    }
+
+    /// Mutation weight of an instruction (0 if it is not in the set)
+    pub fn redundancy(&self, inst: Instruction) -> u32 {
+        self.entry(inst).map_or(0, |entry| entry.redundancy)
+    }
+
+    /// Execution cost of an instruction in CPU cycles (1 if it is not in the set)
+    pub fn cost(&self, inst: Instruction) -> u32 {
+        self.entry(inst).map_or(1, |entry| entry.cost)
+    }
+
+    /// Extra cycles of an instruction's first execution in a gestation (0 if it is not in the set)
+    pub fn initial_cost(&self, inst: Instruction) -> u32 {
+        self.entry(inst).map_or(0, |entry| entry.initial_cost)
+    }
+
+    /// Letter of an instruction in this set
+    pub fn letter(&self, inst: Instruction) -> Option<char> {
+        let index = self.entries.iter().position(|entry| entry.inst == inst)?;
+        LETTERS.chars().nth(index)
+    }
+
+    /// Instruction written with `letter` in this set
+    /// Sets of at most 26 instructions also accept upper-case letters
+    pub fn from_letter(&self, letter: char) -> Option<Instruction> {
+        let letter = if self.entries.len() <= 26 {
+            letter.to_ascii_lowercase()
+        } else {
+            letter
+        };
+        let index = LETTERS.chars().position(|c| c == letter)?;
+        self.entries.get(index).map(|entry| entry.inst)
+    }
+
+    /// Draw a random instruction, weighted by redundancy
+    /// Drawn as a u16 so the default set makes exactly the draws of the earlier
+    /// hard-coded 26-letter alphabet, and seeded runs are unchanged
+    pub fn random_instruction<R: Rng + ?Sized>(&self, rng: &mut R) -> Instruction {
+        let total: u32 = self.entries.iter().map(|entry| entry.redundancy).sum();
+        let mut pick = rng.gen_range(0..total as u16) as u32;
+        for entry in &self.entries {
+            if pick < entry.redundancy {
+                return entry.inst;
+            }
+            pick -= entry.redundancy;
+        }
+        unreachable!("pick is below the total redundancy")
+    }
+
+    /// Parse a sequence written with this set's letters
+    pub fn parse_sequence(&self, s: &str) -> Result<Vec<Instruction>, String> {
+        s.chars()
+            .map(|c| {
+                self.from_letter(c).ok_or_else(|| {
+                    format!(
+                        "Invalid instruction character for instruction set {}: '{}'",
+                        self.name, c
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Write a genome with this set's letters (`?` for instructions outside the set)
+    pub fn sequence(&self, genome: &[Instruction]) -> String {
+        genome
+            .iter()
+            .map(|&inst| self.letter(inst).unwrap_or('?'))
+            .collect()
+    }
+
+    /// Parse an organism file: a sequence in this set's letters or an Avida .org listing
+    /// with one instruction name per line. Every instruction must belong to the set.
+    pub fn parse_organism(&self, contents: &str) -> Result<Vec<Instruction>, String> {
+        let lines: Vec<&str> = contents
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        if lines.len() == 1 && Instruction::from_name(lines[0]).is_none() {
+            return self.parse_sequence(lines[0]);
+        }
+
+        lines
+            .iter()
+            .map(|name| {
+                let inst = Instruction::from_name(name)
+                    .ok_or_else(|| format!("Unknown instruction name: '{}'", name))?;
+                if !self.contains(inst) {
+                    return Err(format!(
+                        "Instruction {} is not in instruction set {}",
+                        name, self.name
+                    ));
+                }
+                Ok(inst)
+            })
+            .collect()
+    }
+}
+
+fn parse_value(key: &str, value: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' for {}", value, key))
+}
+
+fn lookup(name: &str) -> Result<Instruction, String> {
+    Instruction::from_name(name).ok_or_else(|| format!("unknown instruction '{}'", name))
+}
+
+/// Parse `name:hw_type=0` from an INSTSET line
+fn parse_instset_header(spec: &str) -> Result<String, String> {
+    let mut fields = spec.split(':');
+    let name = fields.next().unwrap_or("");
+    if name.is_empty() {
+        return Err("missing instruction set name after INSTSET".to_string());
+    }
+    for field in fields {
+        match field.split_once('=') {
+            Some(("hw_type", "0")) => {}
+            Some(("hw_type", value)) => {
+                return Err(format!(
+                    "hw_type {} is not supported (only 0, the heads CPU)",
+                    value
+                ))
+            }
+            _ => return Err(format!("unknown INSTSET setting '{}'", field)),
+        }
+    }
+    Ok(name.to_string())
+}
+
+/// Parse `name:key=value...` from an INST line
+fn parse_inst(spec: &str) -> Result<InstEntry, String> {
+    let mut fields = spec.split(':');
+    let mut entry = InstEntry::new(lookup(fields.next().unwrap_or(""))?);
+    for field in fields {
+        match field.split_once('=') {
+            Some(("redundancy", value)) => entry.redundancy = parse_value("redundancy", value)?,
+            Some(("cost", value)) => entry.cost = parse_value("cost", value)?,
+            Some(("initial_cost", value)) => {
+                entry.initial_cost = parse_value("initial_cost", value)?
+            }
+            _ => return Err(format!("unknown instruction setting '{}'", field)),
+        }
+    }
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{genome_to_heads_default, genome_to_string, parse_genome};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_default_matches_instruction_letters() {
+        let set = InstSet::default();
+        assert_eq!(set.len(), 26);
+        let ancestor = "rutyabsvacccccccccccccccccccccccccccccccccccccccbc";
+        let genome = set.parse_sequence(ancestor).unwrap();
+        assert_eq!(genome, parse_genome(ancestor).unwrap());
+        assert_eq!(set.sequence(&genome), genome_to_string(&genome));
+        assert_eq!(set.from_letter('R'), Some(Instruction::HAlloc));
+        assert_eq!(set.cost(Instruction::HAlloc), 1);
+
+        let heads = InstSet::heads_default();
+        assert_eq!(heads.sequence(&genome), genome_to_heads_default(&genome));
+        assert_eq!(heads.letter(Instruction::HAlloc), Some('w'));
+    }
+
+    #[test]
+    fn test_parse_current_format() {
+        let set = InstSet::parse(
+            "INSTSET small:hw_type=0\n\
+             # copy loop only\n\
+             INST nop-A\n\
+             INST nop-B\n\
+             INST nop-C:redundancy=5\n\
+             INST h-alloc:cost=10:initial_cost=50\n\
+             INST h-divide:redundancy=0:cost=2\n",
+        )
+        .unwrap();
+        assert_eq!(set.name(), "small");
+        assert_eq!(set.len(), 5);
+        assert_eq!(set.letter(Instruction::HAlloc), Some('d'));
+        assert_eq!(set.redundancy(Instruction::NopC), 5);
+        assert_eq!(set.redundancy(Instruction::IO), 0);
+        assert_eq!(set.cost(Instruction::HAlloc), 10);
+        assert_eq!(set.cost(Instruction::HDivide), 2);
+        assert_eq!(set.initial_cost(Instruction::HAlloc), 50);
+        assert_eq!(set.initial_cost(Instruction::HDivide), 0);
+        assert!(!set.contains(Instruction::IO));
+        assert_eq!(
+            set.parse_sequence("dabce").unwrap(),
+            vec![
+                Instruction::HAlloc,
+                Instruction::NopA,
+                Instruction::NopB,
+                Instruction::NopC,
+                Instruction::HDivide
+            ]
+        );
+        assert!(set.parse_sequence("abf").is_err());
+        assert!(set.parse_organism("h-alloc\nIO\n").is_err());
+
+        // Round trip through the file format
+        assert_eq!(InstSet::parse(&set.format()).unwrap(), set);
+    }
+
+    #[test]
+    fn test_parse_classic_format() {
+        let set = InstSet::parse("nop-A 1\nnop-B 1 0 0\nh-copy 3 2\nh-alloc\n");
+        assert!(set.unwrap_err().contains("cost of nop-B"));
+
+        let set = InstSet::parse("nop-A 1\nnop-B\nh-copy 3 2 7 0.0\n").unwrap();
+        assert_eq!(set.name(), "custom");
+        assert_eq!(set.redundancy(Instruction::HCopy), 3);
+        assert_eq!(set.cost(Instruction::HCopy), 2);
+        assert_eq!(set.initial_cost(Instruction::HCopy), 7);
+        assert_eq!(set.letter(Instruction::HCopy), Some('c'));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = InstSet::parse("INST nop-A\nINST h-bogus\n").unwrap_err();
+        assert!(err.starts_with("line 2:") && err.contains("h-bogus"));
+        assert!(InstSet::parse("INST nop-A\nINST nop-A\n")
+            .unwrap_err()
+            .contains("listed twice"));
+        assert!(InstSet::parse("INSTSET x:hw_type=2\nINST nop-A\n")
+            .unwrap_err()
+            .contains("hw_type 2"));
+        assert!(InstSet::parse("INST nop-A:weight=2\n")
+            .unwrap_err()
+            .contains("weight"));
+        assert!(InstSet::parse("INST nop-A:redundancy=0\n")
+            .unwrap_err()
+            .contains("nonzero redundancy"));
+        assert!(InstSet::parse("INST nop-A:redundancy=70000\n")
+            .unwrap_err()
+            .contains("total redundancy"));
+        assert!(InstSet::parse("# nothing\n").is_err());
+    }
+
+    #[test]
+    fn test_random_instruction_follows_redundancy() {
+        let set = InstSet::parse("INST nop-A:redundancy=3\nINST nop-B:redundancy=0\nINST nop-C\n")
+            .unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut counts = [0; 3];
+        for _ in 0..4000 {
+            counts[set
+                .random_instruction(&mut rng)
+                .nop_register_index()
+                .unwrap()] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!(counts[0] > 2 * counts[2] && counts[0] < 4 * counts[2]);
+    }
+
+    #[test]
+    fn test_extended_instructions_are_opt_in() {
+        assert!(InstSet::default()
+            .entries()
+            .iter()
+            .all(|entry| !entry.inst.is_extended()));
+
+        let contents = format!(
+            "{}INST if-grt\nINST repro:cost=40\n",
+            InstSet::default().format()
+        );
+        let set = InstSet::parse(&contents).unwrap();
+        assert_eq!(set.len(), 28);
+        assert_eq!(set.letter(Instruction::IfGrt), Some('A'));
+        assert_eq!(set.cost(Instruction::Repro), 40);
+        // Sets beyond 26 instructions are case-sensitive
+        assert_eq!(set.from_letter('B'), Some(Instruction::Repro));
+        assert_eq!(set.from_letter('b'), Some(Instruction::NopB));
+
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        assert!((0..1000).any(|_| set.random_instruction(&mut rng) == Instruction::IfGrt));
+    }
+
+    #[test]
+    fn test_serde_validates() {
+        let set = InstSet::heads_default();
+        let json = serde_json::to_string(&set).unwrap();
+        assert_eq!(serde_json::from_str::<InstSet>(&json).unwrap(), set);
+        assert!(serde_json::from_str::<InstSet>(r#"{"name":"x","entries":[]}"#).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+//! Knockout analysis
+//! Replaces each site of a genome in turn with nop-C, the neutral padding instruction, and
+//! runs the mutant alone in the test CPU to find which sites replication and each task
+//! depend on.
+
+use crate::instruction::Instruction;
+use crate::systematics::task_names;
+use crate::tasks::{Task, TaskSet};
+use crate::test_cpu::{Phenotype, TestCpu, Viability};
+use std::fmt::Write as _;
+
+/// Instruction a knocked-out site is replaced with
+pub const KNOCKOUT_INSTRUCTION: Instruction = Instruction::NopC;
+
+/// Effect of knocking out one site
+#[derive(Debug, Clone, PartialEq)]
+pub struct SiteKnockout {
+    pub site: usize,
+    pub original: Instruction,
+    pub viability: Viability,
+    pub fitness: f64,
+    /// Tasks the mutant still performs
+    pub tasks: TaskSet,
+    /// The unmutated genome replicates but the mutant does not
+    pub replication_essential: bool,
+    /// Tasks of the unmutated genome the mutant no longer performs
+    pub lost_tasks: TaskSet,
+}
+
+impl SiteKnockout {
+    /// The site is needed for `task`
+    pub fn essential_for(&self, task: Task) -> bool {
+        self.lost_tasks.contains(task)
+    }
+
+    /// Mutant fitness relative to the unmutated genome (1.0 if both are 0)
+    pub fn relative_fitness(&self, base: &Phenotype) -> f64 {
+        if base.fitness > 0.0 {
+            self.fitness / base.fitness
+        } else if self.fitness > 0.0 {
+            f64::INFINITY
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Knockouts of every site of a genome
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnockoutReport {
+    /// Phenotype of the unmutated genome
+    pub base: Phenotype,
+    pub sites: Vec<SiteKnockout>,
+}
+
+impl KnockoutReport {
+    /// Sites without which the genome no longer replicates itself
+    pub fn replication_sites(&self) -> Vec<usize> {
+        self.sites
+            .iter()
+            .filter(|site| site.replication_essential)
+            .map(|site| site.site)
+            .collect()
+    }
+
+    /// Sites without which the genome no longer performs `task`
+    pub fn task_sites(&self, task: Task) -> Vec<usize> {
+        self.sites
+            .iter()
+            .filter(|site| site.essential_for(task))
+            .map(|site| site.site)
+            .collect()
+    }
+
+    /// Per-site table: one row per site after a commented header
+    pub fn format(&self) -> String {
+        let mut out = String::from(
+            "# avida-rs knockout analysis\n\
+             # site inst viability fitness relative_fitness tasks replication_essential lost_tasks\n",
+        );
+        let or_dash = |s: String| if s.is_empty() { "-".to_string() } else { s };
+        for site in &self.sites {
+            let _ = writeln!(
+                out,
+                "{} {} {} {:.6} {:.4} {} {} {}",
+                site.site,
+                site.original.to_char(),
+                site.viability.name(),
+                site.fitness,
+                site.relative_fitness(&self.base),
+                or_dash(task_names(site.tasks)),
+                u8::from(site.replication_essential),
+                or_dash(task_names(site.lost_tasks))
+            );
+        }
+        out
+    }
+}
+
+/// Knock out every site of `genome` in turn
+/// Sites that already hold the knockout instruction are not re-evaluated
+pub fn knockout(genome: &[Instruction], test_cpu: &TestCpu) -> KnockoutReport {
+    let base = test_cpu.evaluate(genome);
+    let replicates = base.viability == Viability::SelfReplicating;
+
+    let mut mutant = genome.to_vec();
+    let sites = genome
+        .iter()
+        .enumerate()
+        .map(|(site, &original)| {
+            let (viability, fitness, tasks) = if original == KNOCKOUT_INSTRUCTION {
+                (base.viability, base.fitness, base.tasks)
+            } else {
+                mutant[site] = KNOCKOUT_INSTRUCTION;
+                let phenotype = test_cpu.evaluate(&mutant);
+                mutant[site] = original;
+                (phenotype.viability, phenotype.fitness, phenotype.tasks)
+            };
+            SiteKnockout {
+                site,
+                original,
+                viability,
+                fitness,
+                tasks,
+                replication_essential: replicates && viability != Viability::SelfReplicating,
+                lost_tasks: base.tasks - tasks,
+            }
+        })
+        .collect();
+
+    KnockoutReport { base, sites }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::parse_genome;
+    use crate::organism::Organism;
+
+    #[test]
+    fn test_ancestor_knockouts() {
+        let genome = Organism::ancestor().genome;
+        let report = knockout(&genome, &TestCpu::default());
+        assert_eq!(report.sites.len(), genome.len());
+
+        // h-alloc, h-copy and h-divide are essential; the nop-C padding is not
+        let essential = report.replication_sites();
+        for inst in [
+            Instruction::HAlloc,
+            Instruction::HCopy,
+            Instruction::HDivide,
+        ] {
+            let site = genome.iter().position(|&i| i == inst).unwrap();
+            assert!(essential.contains(&site), "{:?}", inst);
+        }
+        for site in &report.sites {
+            if site.original == KNOCKOUT_INSTRUCTION {
+                assert!(!site.replication_essential);
+                assert_eq!(site.relative_fitness(&report.base), 1.0);
+            }
+        }
+        assert!(Task::all()
+            .into_iter()
+            .all(|task| report.task_sites(task).is_empty()));
+    }
+
+    #[test]
+    fn test_task_sites() {
+        // Input twice, copy BX to CX through the stack, NAND it and output NOT
+        let genome = parse_genome("qqgifpqrutyabsvacccccccccccccbc").unwrap();
+        let report = knockout(&genome, &TestCpu::default());
+        assert!(report.base.has_task(Task::Not));
+
+        let not_sites = report.task_sites(Task::Not);
+        // The NAND and the final IO are needed for NOT
+        assert!(not_sites.contains(&5) && not_sites.contains(&6));
+        // Losing NOT does not stop replication
+        assert!(!report.sites[5].replication_essential);
+
+        let table = report.format();
+        assert_eq!(table.lines().count(), genome.len() + 2);
+        assert!(table
+            .lines()
+            .nth(7)
+            .unwrap()
+            .starts_with("5 p self-replicating "));
+        assert!(table.lines().nth(7).unwrap().ends_with(" 0 NOT"));
+    }
+}
@@ -0,0 +1,319 @@
+//! Mutational landscape
+//! Evaluates every single-point mutant of a genome in the test CPU (each site changed to each
+//! other instruction of the test CPU's instruction set) and classifies them by fitness relative to the unmutated genome.
+//! Double mutants can be sampled to estimate epistasis.
+
+use crate::instruction::Instruction;
+use crate::tasks::{Task, TaskSet};
+use crate::test_cpu::{Phenotype, TestCpu};
+use rand::Rng;
+use std::fmt::Write as _;
+
+/// Fitness effect of a mutation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MutantClass {
+    /// Fitness 0: the mutant never divides
+    Lethal,
+    Deleterious,
+    Neutral,
+    Beneficial,
+}
+
+impl MutantClass {
+    pub fn all() -> [MutantClass; 4] {
+        [
+            MutantClass::Lethal,
+            MutantClass::Deleterious,
+            MutantClass::Neutral,
+            MutantClass::Beneficial,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MutantClass::Lethal => "lethal",
+            MutantClass::Deleterious => "deleterious",
+            MutantClass::Neutral => "neutral",
+            MutantClass::Beneficial => "beneficial",
+        }
+    }
+}
+
+/// One single-point mutant
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointMutant {
+    pub site: usize,
+    pub inst: Instruction,
+    pub fitness: f64,
+    /// Tasks the mutant performs
+    pub tasks: TaskSet,
+}
+
+/// Every single-point mutant of a genome
+#[derive(Debug, Clone, PartialEq)]
+pub struct Landscape {
+    /// Phenotype of the unmutated genome
+    pub base: Phenotype,
+    /// Mutants in site order, then instruction set order
+    pub mutants: Vec<PointMutant>,
+    /// Tasks of the environment the genomes were evaluated in
+    pub tasks: TaskSet,
+}
+
+/// Statistics of sampled double mutants
+#[derive(Debug, Clone, PartialEq)]
+pub struct DoubleMutants {
+    pub samples: usize,
+    /// Number of double mutants in each class, in `MutantClass::all` order
+    pub counts: [usize; 4],
+    /// Mean of ln(w_ab) - ln(w_a) - ln(w_b) over samples where all three are viable
+    /// (relative fitness; 0 if there were none)
+    pub mean_epistasis: f64,
+    /// Number of samples the mean epistasis is taken over
+    pub epistasis_samples: usize,
+}
+
+impl DoubleMutants {
+    pub fn fraction(&self, class: MutantClass) -> f64 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            self.counts[class as usize] as f64 / self.samples as f64
+        }
+    }
+}
+
+/// Class of a mutant with `fitness`, relative to the unmutated `base_fitness`
+pub fn classify(fitness: f64, base_fitness: f64) -> MutantClass {
+    if fitness == 0.0 {
+        MutantClass::Lethal
+    } else if fitness < base_fitness {
+        MutantClass::Deleterious
+    } else if fitness == base_fitness {
+        MutantClass::Neutral
+    } else {
+        MutantClass::Beneficial
+    }
+}
+
+/// Evaluate every single-point mutant of `genome`
+pub fn landscape(genome: &[Instruction], test_cpu: &TestCpu) -> Landscape {
+    let base = test_cpu.evaluate(genome);
+    let alphabet: Vec<Instruction> = test_cpu
+        .inst_set
+        .entries()
+        .iter()
+        .map(|entry| entry.inst)
+        .collect();
+    let mut mutant = genome.to_vec();
+    let mut mutants = Vec::with_capacity(genome.len() * (alphabet.len() - 1));
+
+    for (site, &original) in genome.iter().enumerate() {
+        for &inst in alphabet.iter().filter(|&&inst| inst != original) {
+            mutant[site] = inst;
+            let phenotype = test_cpu.evaluate(&mutant);
+            mutants.push(PointMutant {
+                site,
+                inst,
+                fitness: phenotype.fitness,
+                tasks: phenotype.tasks,
+            });
+        }
+        mutant[site] = original;
+    }
+
+    Landscape {
+        base,
+        mutants,
+        tasks: test_cpu.environment.tasks(),
+    }
+}
+
+impl Landscape {
+    pub fn class(&self, mutant: &PointMutant) -> MutantClass {
+        classify(mutant.fitness, self.base.fitness)
+    }
+
+    pub fn count(&self, class: MutantClass) -> usize {
+        self.mutants
+            .iter()
+            .filter(|m| self.class(m) == class)
+            .count()
+    }
+
+    pub fn fraction(&self, class: MutantClass) -> f64 {
+        if self.mutants.is_empty() {
+            0.0
+        } else {
+            self.count(class) as f64 / self.mutants.len() as f64
+        }
+    }
+
+    /// Mutants that perform `task` when the unmutated genome does not
+    pub fn task_gains(&self, task: Task) -> usize {
+        if self.base.tasks.contains(task) {
+            return 0;
+        }
+        self.mutants
+            .iter()
+            .filter(|m| m.tasks.contains(task))
+            .count()
+    }
+
+    /// Mutants that no longer perform a `task` of the unmutated genome
+    pub fn task_losses(&self, task: Task) -> usize {
+        if !self.base.tasks.contains(task) {
+            return 0;
+        }
+        self.mutants
+            .iter()
+            .filter(|m| !m.tasks.contains(task))
+            .count()
+    }
+
+    /// Sample `count` random double mutants (two different sites, each changed to
+    /// another instruction) and compare them with the matching single mutants
+    pub fn sample_double_mutants<R: Rng + ?Sized>(
+        &self,
+        test_cpu: &TestCpu,
+        count: usize,
+        rng: &mut R,
+    ) -> DoubleMutants {
+        let genome = &self.base.genome;
+        let mut result = DoubleMutants {
+            samples: 0,
+            counts: [0; 4],
+            mean_epistasis: 0.0,
+            epistasis_samples: 0,
+        };
+        if genome.len() < 2 {
+            return result;
+        }
+
+        let mut epistasis_total = 0.0;
+        for _ in 0..count {
+            let a = &self.mutants[rng.gen_range(0..self.mutants.len())];
+            let b = loop {
+                let b = &self.mutants[rng.gen_range(0..self.mutants.len())];
+                if b.site != a.site {
+                    break b;
+                }
+            };
+
+            let mut mutant = genome.clone();
+            mutant[a.site] = a.inst;
+            mutant[b.site] = b.inst;
+            let fitness = test_cpu.evaluate(&mutant).fitness;
+
+            result.samples += 1;
+            result.counts[classify(fitness, self.base.fitness) as usize] += 1;
+            if fitness > 0.0 && a.fitness > 0.0 && b.fitness > 0.0 && self.base.fitness > 0.0 {
+                let relative = |w: f64| (w / self.base.fitness).ln();
+                epistasis_total += relative(fitness) - relative(a.fitness) - relative(b.fitness);
+                result.epistasis_samples += 1;
+            }
+        }
+
+        if result.epistasis_samples > 0 {
+            result.mean_epistasis = epistasis_total / result.epistasis_samples as f64;
+        }
+        result
+    }
+
+    /// Format the landscape as `key value` lines: class fractions, then task gains and losses
+    pub fn format(&self, doubles: Option<&DoubleMutants>) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "length {}", self.base.genome.len());
+        let _ = writeln!(out, "base_fitness {:.6}", self.base.fitness);
+        let _ = writeln!(out, "mutants {}", self.mutants.len());
+        for class in MutantClass::all() {
+            let _ = writeln!(out, "{} {:.6}", class.name(), self.fraction(class));
+        }
+        for task in self.tasks.iter() {
+            let _ = writeln!(out, "task_{}_gains {}", task.name(), self.task_gains(task));
+            let _ = writeln!(
+                out,
+                "task_{}_losses {}",
+                task.name(),
+                self.task_losses(task)
+            );
+        }
+        if let Some(doubles) = doubles {
+            let _ = writeln!(out, "double_samples {}", doubles.samples);
+            for class in MutantClass::all() {
+                let _ = writeln!(
+                    out,
+                    "double_{} {:.6}",
+                    class.name(),
+                    doubles.fraction(class)
+                );
+            }
+            let _ = writeln!(out, "mean_epistasis {:.6}", doubles.mean_epistasis);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::parse_genome;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(0.0, 0.5), MutantClass::Lethal);
+        assert_eq!(classify(0.25, 0.5), MutantClass::Deleterious);
+        assert_eq!(classify(0.5, 0.5), MutantClass::Neutral);
+        assert_eq!(classify(0.75, 0.5), MutantClass::Beneficial);
+    }
+
+    #[test]
+    fn test_point_mutant_landscape() {
+        let genome = parse_genome("qqgifpqrutyabsvacccccccccccccbc").unwrap();
+        let landscape = landscape(&genome, &TestCpu::default());
+        assert_eq!(landscape.mutants.len(), genome.len() * 25);
+        assert!(landscape.mutants.iter().all(|m| genome[m.site] != m.inst));
+
+        let total: f64 = MutantClass::all()
+            .into_iter()
+            .map(|class| landscape.fraction(class))
+            .sum();
+        assert!((total - 1.0).abs() < 1e-12);
+        assert!(landscape.count(MutantClass::Lethal) > 0);
+        assert!(landscape.count(MutantClass::Neutral) > 0);
+
+        // The genome performs NOT, so NOT can only be lost
+        assert!(landscape.task_losses(Task::Not) > 0);
+        assert_eq!(landscape.task_gains(Task::Not), 0);
+        assert_eq!(landscape.task_losses(Task::Equ), 0);
+
+        let text = landscape.format(None);
+        assert!(text.contains(&format!("mutants {}\n", genome.len() * 25)));
+        assert!(!text.contains("double_samples"));
+    }
+
+    #[test]
+    fn test_double_mutants() {
+        let genome = parse_genome("rutyabsvacccccccccccccbc").unwrap();
+        let cpu = TestCpu::default();
+        let landscape = landscape(&genome, &cpu);
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let doubles = landscape.sample_double_mutants(&cpu, 200, &mut rng);
+        assert_eq!(doubles.samples, 200);
+        assert_eq!(doubles.counts.iter().sum::<usize>(), 200);
+        assert!(doubles.epistasis_samples > 0);
+        assert!(doubles.mean_epistasis.is_finite());
+
+        // Same seed, same sample
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        assert_eq!(
+            landscape.sample_double_mutants(&cpu, 200, &mut rng),
+            doubles
+        );
+        assert!(landscape
+            .format(Some(&doubles))
+            .contains("double_samples 200\n"));
+    }
+}
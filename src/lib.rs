@@ -1,12 +1,30 @@
 // Library exports for avida-rs
 // This allows the debug_test binary and main binary to use the modules
 
+pub mod analyze;
+pub mod batch;
+pub mod birth_chamber;
+pub mod checkpoint;
+pub mod config;
 pub mod cpu;
+pub mod data;
 pub mod debug;
 pub mod diagnostics;
+pub mod environment;
+pub mod events;
 pub mod execute;
+pub mod genotype;
 pub mod instruction;
+pub mod instset;
+pub mod knockout;
+pub mod landscape;
+pub mod lineage;
 pub mod organism;
+pub mod parasite;
+pub mod resources;
+pub mod spop;
+pub mod systematics;
 pub mod tasks;
+pub mod test_cpu;
 pub mod ui;
 pub mod world;
@@ -0,0 +1,178 @@
+//! Line of descent
+//! Follows genotype parent links from a living genotype back to its injected ancestor and
+//! annotates every step with the mutations that produced it and the tasks it gained or lost.
+
+use crate::genotype::Genotype;
+use crate::systematics::task_names;
+use crate::tasks::TaskSet;
+use crate::world::World;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// One genotype on a line of descent
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineageStep {
+    pub genotype: Genotype,
+    /// Highest merit reached by any organism of the genotype, living or dead
+    pub merit: f64,
+    /// Tasks performed by any organism of the genotype
+    pub tasks: TaskSet,
+    /// Tasks the parent genotype did not perform
+    pub gained: TaskSet,
+    /// Tasks the parent genotype performed but this one did not
+    pub lost: TaskSet,
+}
+
+/// The line of descent of genotype `id`, starting at its injected ancestor
+/// None if the genotype is not in the registry
+pub fn line_of_descent(world: &World, id: u64) -> Option<Vec<LineageStep>> {
+    let mut living: HashMap<u64, (TaskSet, f64)> = HashMap::new();
+    for org in world.grid.iter().flatten() {
+        if let Some(id) = org.genotype_id {
+            let entry = living.entry(id).or_insert((TaskSet::default(), 0.0));
+            entry.0 |= org.tasks_completed;
+            entry.1 = entry.1.max(org.merit);
+        }
+    }
+
+    let mut genotypes = vec![world.genotypes().get(id)?];
+    while let Some(parent) = genotypes
+        .last()
+        .and_then(|genotype| genotype.parent_id)
+        .and_then(|id| world.genotypes().get(id))
+    {
+        genotypes.push(parent);
+    }
+
+    let mut steps: Vec<LineageStep> = Vec::with_capacity(genotypes.len());
+    for genotype in genotypes.into_iter().rev() {
+        let (living_tasks, living_merit) = living.get(&genotype.id).copied().unwrap_or_default();
+        let tasks = genotype.tasks | living_tasks;
+        let parent_tasks = steps
+            .last()
+            .map_or(TaskSet::default(), |parent| parent.tasks);
+        steps.push(LineageStep {
+            genotype: genotype.clone(),
+            merit: genotype.merit.max(living_merit),
+            tasks,
+            gained: tasks - parent_tasks,
+            lost: parent_tasks - tasks,
+        });
+    }
+    Some(steps)
+}
+
+/// The line of descent of the current dominant genotype (empty for an empty world)
+pub fn dominant_line_of_descent(world: &World) -> Vec<LineageStep> {
+    world
+        .genotypes()
+        .dominant()
+        .and_then(|genotype| line_of_descent(world, genotype.id))
+        .unwrap_or_default()
+}
+
+/// Format a line of descent with one line per genotype, ancestor first
+/// Mutations use `M<pos><from><to>`, `I<pos><inst>` and `D<pos><inst>`; empty lists are `-`
+pub fn format(steps: &[LineageStep]) -> String {
+    let mut out = String::from(
+        "# avida-rs line of descent\n\
+         # id parent_id update_born generation_born depth length merit tasks gained lost mutations sequence\n",
+    );
+    let or_dash = |s: String| if s.is_empty() { "-".to_string() } else { s };
+    for step in steps {
+        let genotype = &step.genotype;
+        let mutations: Vec<String> = genotype.mutations.iter().map(|m| m.to_string()).collect();
+        let _ = writeln!(
+            out,
+            "{} {} {} {} {} {} {:.4} {} {} {} {} {}",
+            genotype.id,
+            genotype
+                .parent_id
+                .map_or("(none)".to_string(), |id| id.to_string()),
+            genotype.update_born,
+            genotype.generation_born,
+            genotype.depth,
+            genotype.genome.len(),
+            step.merit,
+            or_dash(task_names(step.tasks)),
+            or_dash(task_names(step.gained)),
+            or_dash(task_names(step.lost)),
+            or_dash(mutations.join(",")),
+            genotype.genome_string()
+        );
+    }
+    out
+}
+
+/// Write the line of descent of the dominant genotype
+pub fn save_dominant(world: &World, path: impl AsRef<Path>) -> Result<(), String> {
+    let path = path.as_ref();
+    std::fs::write(path, format(&dominant_line_of_descent(world)))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::organism::Organism;
+    use crate::tasks::Task;
+
+    #[test]
+    fn test_mutations_rebuild_each_genome() {
+        let mut world = World::with_dimensions_and_seed(20, 20, 6);
+        world.copy_mutation_rate = 0.02;
+        world.inject_ancestor_with_tasks();
+        for _ in 0..300 {
+            world.update();
+        }
+
+        let steps = dominant_line_of_descent(&world);
+        assert!(steps.len() > 1);
+        assert_eq!(steps[0].genotype.id, 1);
+        assert!(steps[0].genotype.mutations.is_empty());
+        for pair in steps.windows(2) {
+            let (parent, child) = (&pair[0].genotype, &pair[1].genotype);
+            assert_eq!(child.parent_id, Some(parent.id));
+            assert!(!child.mutations.is_empty());
+
+            let mut genome = parent.genome.clone();
+            for mutation in &child.mutations {
+                mutation.apply(&mut genome);
+            }
+            assert_eq!(genome, child.genome, "genotype {}", child.id);
+
+            assert_eq!(pair[1].gained, pair[1].tasks - pair[0].tasks);
+            assert_eq!(pair[1].lost, pair[0].tasks - pair[1].tasks);
+        }
+
+        let text = format(&steps);
+        assert_eq!(text.lines().count(), steps.len() + 2);
+        assert!(text.lines().nth(2).unwrap().starts_with("1 (none) 0 0 0 "));
+    }
+
+    #[test]
+    fn test_tasks_and_merit_of_dead_organisms() {
+        let mut world = World::with_dimensions_and_seed(10, 10, 1);
+        assert!(dominant_line_of_descent(&world).is_empty());
+        assert!(line_of_descent(&world, 1).is_none());
+
+        let mut org = Organism::ancestor();
+        org.complete_task(Task::Equ.index() as u8);
+        org.merit = 16.0;
+        world.inject_organism(org, 0, 0);
+        let mut org = Organism::ancestor();
+        org.complete_task(Task::Not.index() as u8);
+        org.merit = 2.0;
+        world.inject_organism(org, 1, 0);
+        let id = world.grid[0].as_ref().unwrap().genotype_id.unwrap();
+
+        world.kill_organism(0);
+        let steps = line_of_descent(&world, id).unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].merit, 16.0);
+        assert_eq!(task_names(steps[0].tasks), "NOT;EQU");
+        assert_eq!(steps[0].gained, steps[0].tasks);
+        assert!(steps[0].lost.is_empty());
+    }
+}
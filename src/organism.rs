@@ -1,10 +1,90 @@
 use crate::cpu::CPU;
 use crate::instruction::Instruction;
+use crate::instset::InstSet;
+use crate::parasite::Parasite;
+use crate::tasks::{Task, TaskSet};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 pub const MINIMUM_GENOME_SIZE: usize = 15;
 
+/// A mutation applied while producing an offspring
+/// Positions index the offspring genome as it was when the mutation was applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mutation {
+    /// Copy mutation: `from` was read but `to` was written
+    Point {
+        pos: usize,
+        from: Instruction,
+        to: Instruction,
+    },
+    /// Insertion at divide (also used for nop-C padding of short genomes)
+    Insertion { pos: usize, inst: Instruction },
+    /// Deletion at divide
+    Deletion { pos: usize, inst: Instruction },
+}
+
+impl Mutation {
+    /// Apply the mutation to a genome
+    pub fn apply(&self, genome: &mut Vec<Instruction>) {
+        match *self {
+            Mutation::Point { pos, to, .. } => genome[pos] = to,
+            Mutation::Insertion { pos, inst } => genome.insert(pos, inst),
+            Mutation::Deletion { pos, .. } => {
+                genome.remove(pos);
+            }
+        }
+    }
+
+    /// Mutations turning `from` into `to`: point mutations where the genomes differ after
+    /// their common prefix and suffix, then insertions or deletions for the difference in
+    /// length
+    pub fn between(from: &[Instruction], to: &[Instruction]) -> Vec<Mutation> {
+        let prefix = from.iter().zip(to).take_while(|(a, b)| a == b).count();
+        let suffix = from[prefix..]
+            .iter()
+            .rev()
+            .zip(to[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let old = &from[prefix..from.len() - suffix];
+        let new = &to[prefix..to.len() - suffix];
+
+        let common = old.len().min(new.len());
+        let mut mutations: Vec<Mutation> = (0..common)
+            .filter(|&i| old[i] != new[i])
+            .map(|i| Mutation::Point {
+                pos: prefix + i,
+                from: old[i],
+                to: new[i],
+            })
+            .collect();
+        let pos = prefix + common;
+        for (i, &inst) in new[common..].iter().enumerate() {
+            mutations.push(Mutation::Insertion { pos: pos + i, inst });
+        }
+        for &inst in &old[common..] {
+            mutations.push(Mutation::Deletion { pos, inst });
+        }
+        mutations
+    }
+}
+
+impl std::fmt::Display for Mutation {
+    /// Avida-style notation: `M12ab` (a to b at 12), `I5c` (insert c at 5), `D7c` (delete c at 7)
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Mutation::Point { pos, from, to } => {
+                write!(f, "M{}{}{}", pos, from.to_char(), to.to_char())
+            }
+            Mutation::Insertion { pos, inst } => write!(f, "I{}{}", pos, inst.to_char()),
+            Mutation::Deletion { pos, inst } => write!(f, "D{}{}", pos, inst.to_char()),
+        }
+    }
+}
+
 /// Represents a digital organism in Avida
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Organism {
     /// The organism's genome (circular sequence of instructions)
     pub genome: Vec<Instruction>,
@@ -26,9 +106,12 @@ pub struct Organism {
     /// Number of offspring produced by this organism
     pub offspring_count: u32,
 
-    /// Tasks completed (bit flags for 9 tasks)
-    /// Each bit represents whether task 0-8 has been completed
-    pub tasks_completed: u16,
+    /// Tasks completed
+    pub tasks_completed: TaskSet,
+
+    /// Times each task has been performed, by `Task::index`
+    #[serde(default)]
+    pub task_counts: Vec<u32>,
 
     /// Child genome being constructed (allocated memory)
     pub child_genome: Option<Vec<Instruction>>,
@@ -36,12 +119,33 @@ pub struct Organism {
     /// Highest offset copied into the child genome during current gestation
     pub child_copy_progress: usize,
 
-    /// Number of instructions executed during current gestation period
-    /// (reset after each successful division)
+    /// Mutations that turned the parent's genome into this one, in the order applied
+    /// (empty for injected organisms)
+    #[serde(default)]
+    pub mutations: Vec<Mutation>,
+
+    /// CPU cycles used during current gestation period: one per instruction executed plus
+    /// cycles spent paying instruction costs (reset after each successful division)
     pub gestation_cycles: u64,
 
-    /// Position in the world grid
+    /// CPU cycles used during the last completed gestation (0 before the first divide)
+    #[serde(default)]
+    pub gestation_time: u64,
+
+    /// Position in the world grid (None outside a world, e.g. in the test CPU)
     pub position: Option<(usize, usize)>,
+
+    /// World update at which this organism was born or injected
+    #[serde(default)]
+    pub update_born: u64,
+
+    /// Genotype in the world's registry (None until placed in a world)
+    #[serde(default)]
+    pub genotype_id: Option<u64>,
+
+    /// Parasites running as extra threads of this organism's CPU
+    #[serde(default)]
+    pub parasites: Vec<Parasite>,
 }
 
 impl Organism {
@@ -55,11 +159,17 @@ impl Organism {
             instruction_count: 0,
             generation: 0,
             offspring_count: 0,
-            tasks_completed: 0,
+            tasks_completed: TaskSet::default(),
+            task_counts: vec![0; Task::COUNT],
             child_genome: None,
             child_copy_progress: 0,
+            mutations: Vec::new(),
             gestation_cycles: 0,
+            gestation_time: 0,
             position: None,
+            update_born: 0,
+            genotype_id: None,
+            parasites: Vec::new(),
         }
     }
 
@@ -83,6 +193,18 @@ impl Organism {
         Self::new(genome)
     }
 
+    /// Create the default parasite: a short ancestor with inject in place of h-divide, so
+    /// each offspring infects a neighboring host
+    pub fn parasite_ancestor() -> Self {
+        // Written with the full set's letters, where inject is 'V'
+        let genome_str = "rutyabVvacccccccccbc";
+
+        let genome = crate::instset::InstSet::full()
+            .parse_sequence(genome_str)
+            .expect("Parasite ancestor genome should be valid");
+        Self::new(genome)
+    }
+
     /// Create the Avida-ED style ancestor (for reference - doesn't self-replicate!)
     /// This is the educational version genome that we found in research
     #[allow(dead_code)]
@@ -107,6 +229,33 @@ impl Organism {
         self.genome.get(self.cpu.ip).copied()
     }
 
+    /// Spend one CPU cycle on the instruction at the IP
+    /// Returns true once its cost (plus its initial cost, the first time it runs in this
+    /// gestation) is paid and it should execute this cycle. Waiting cycles count towards
+    /// the gestation time. A skipped instruction is not executed and costs one cycle.
+    pub fn pay_instruction_cost(&mut self, inst_set: &InstSet) -> bool {
+        if self.cpu.cost_remaining == 0 {
+            self.cpu.cost_remaining = match self.current_instruction() {
+                Some(inst) if !self.cpu.skip_next => {
+                    let mut cost = inst_set.cost(inst);
+                    let initial_cost = inst_set.initial_cost(inst);
+                    if initial_cost > 0 && !self.cpu.initial_costs_paid.contains(&inst) {
+                        self.cpu.initial_costs_paid.push(inst);
+                        cost += initial_cost;
+                    }
+                    cost
+                }
+                _ => 1,
+            };
+        }
+        self.cpu.cost_remaining -= 1;
+        if self.cpu.cost_remaining > 0 {
+            self.gestation_cycles += 1;
+            return false;
+        }
+        true
+    }
+
     /// Advance the instruction pointer with circular wrapping
     pub fn advance_ip(&mut self) {
         self.cpu.ip = (self.cpu.ip + 1) % self.genome.len();
@@ -121,7 +270,12 @@ impl Organism {
 
     /// Allocate memory for offspring (h-alloc instruction)
     pub fn allocate_child(&mut self) {
-        let child_size = self.genome.len();
+        self.allocate_child_sized(self.genome.len());
+    }
+
+    /// Allocate `child_size` instructions for offspring (maxalloc allocates more than h-alloc)
+    /// Offspring keep only the part that was copied when they divide
+    pub fn allocate_child_sized(&mut self, child_size: usize) {
         if self.child_genome.is_some() {
             crate::debug::log_event(format!(
                 "[WARN] h-alloc reinitialized child (gen:{}, cycles:{}, ip:{})",
@@ -132,7 +286,7 @@ impl Organism {
         self.child_genome = Some(vec![Instruction::NopA; child_size]);
 
         // Set AX register to original size
-        self.cpu.registers[0] = child_size as i32;
+        self.cpu.registers[0] = self.genome.len() as i32;
 
         // Reset write head to start of child genome
         self.cpu.write_head = 0;
@@ -149,9 +303,13 @@ impl Organism {
 
     /// Copy an instruction from read-head to write-head (h-copy instruction)
     /// Returns the instruction that was copied (after potential mutation)
-    pub fn copy_instruction(&mut self, mutation_rate: f64) -> Option<Instruction> {
-        use rand::Rng;
-
+    /// Copy mutations write a random instruction from `inst_set`
+    pub fn copy_instruction<R: Rng + ?Sized>(
+        &mut self,
+        mutation_rate: f64,
+        inst_set: &InstSet,
+        rng: &mut R,
+    ) -> Option<Instruction> {
         if let Some(child) = &mut self.child_genome {
             // Check bounds before reading
             if self.cpu.read_head >= self.genome.len() {
@@ -168,11 +326,10 @@ impl Organism {
 
             // Apply copy mutation
             let mut mutated = false;
-            let inst_to_write = if rand::thread_rng().gen::<f64>() < mutation_rate {
-                // Random mutation
-                let random_char = (b'a' + rand::thread_rng().gen_range(0..26)) as char;
+            let inst_to_write = if rng.gen::<f64>() < mutation_rate {
+                // Random instruction from the set, weighted by redundancy
                 mutated = true;
-                Instruction::from_char(random_char).unwrap_or(inst)
+                inst_set.random_instruction(rng)
             } else {
                 inst
             };
@@ -184,7 +341,7 @@ impl Organism {
                 let copies =
                     crate::debug::COPIES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
                 // Log first few copies and every 50th copy
-                if copies < 10 || copies % 50 == 0 {
+                if copies < 10 || copies.is_multiple_of(50) {
                     crate::debug::log_event(format!(
                         "[COPY #{}] rh:{} -> wh:{} inst:{} progress:{}/{} {}",
                         copies,
@@ -229,9 +386,14 @@ impl Organism {
     /// Divide the organism and return the offspring
     /// The parent resets its gestation cycle counter
     /// The offspring starts with merit 1.0 (task bonuses not inherited)
-    pub fn divide(&mut self, insertion_rate: f64, deletion_rate: f64) -> Option<Organism> {
-        use rand::Rng;
-
+    /// Insertions draw a random instruction from `inst_set`
+    pub fn divide<R: Rng + ?Sized>(
+        &mut self,
+        insertion_rate: f64,
+        deletion_rate: f64,
+        inst_set: &InstSet,
+        rng: &mut R,
+    ) -> Option<Organism> {
         let child_genome_opt = self.child_genome.take();
         if child_genome_opt.is_none() {
             crate::debug::FAILED_DIVISIONS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
@@ -262,18 +424,21 @@ impl Organism {
         // Trim to write-head position
         child_genome.truncate(progress);
         let size_before_mutations = child_genome.len();
+        // Differences from the parent left by copying (copy mutations, or a copy loop that
+        // moved its heads) come first, then insertions and deletions
+        let mut mutations = Mutation::between(&self.genome, &child_genome);
 
         // Apply insertion and deletion mutations
         // insertion_rate/deletion_rate is the probability of ONE mutation per division
         let mut insertions = 0;
         let mut deletions = 0;
-        let mut rng = rand::thread_rng();
 
         // Single deletion check per division
         if !child_genome.is_empty() && rng.gen::<f64>() < deletion_rate {
             // Choose random position to delete
             let pos = rng.gen_range(0..child_genome.len());
-            child_genome.remove(pos);
+            let inst = child_genome.remove(pos);
+            mutations.push(Mutation::Deletion { pos, inst });
             deletions = 1;
         }
 
@@ -285,23 +450,32 @@ impl Organism {
             } else {
                 rng.gen_range(0..=child_genome.len())
             };
-            let random_char = (b'a' + rng.gen_range(0..26)) as char;
-            if let Some(inst) = Instruction::from_char(random_char) {
-                child_genome.insert(pos, inst);
-                insertions = 1;
-            }
+            let inst = inst_set.random_instruction(rng);
+            child_genome.insert(pos, inst);
+            mutations.push(Mutation::Insertion { pos, inst });
+            insertions = 1;
         }
 
         // Ensure minimum genome size
         if child_genome.is_empty() {
             child_genome.push(Instruction::NopC);
+            mutations.push(Mutation::Insertion {
+                pos: 0,
+                inst: Instruction::NopC,
+            });
             crate::debug::log_event("[WARN] Child genome was empty after mutations, added NopC");
         }
 
         let mut padded = false;
         if child_genome.len() < MINIMUM_GENOME_SIZE {
             let missing = MINIMUM_GENOME_SIZE - child_genome.len();
-            child_genome.extend(std::iter::repeat(Instruction::NopC).take(missing));
+            for _ in 0..missing {
+                mutations.push(Mutation::Insertion {
+                    pos: child_genome.len(),
+                    inst: Instruction::NopC,
+                });
+                child_genome.push(Instruction::NopC);
+            }
             padded = true;
             crate::debug::log_event(format!(
                 "[PAD] Child genome size {} below minimum {}, padded with {} nop-c",
@@ -315,10 +489,11 @@ impl Organism {
         let mut offspring = Organism::new(child_genome);
         offspring.generation = self.generation + 1;
         offspring.merit = 1.0; // Start with base merit (task bonuses not inherited)
+        offspring.mutations = mutations;
 
         let divisions = crate::debug::DIVISIONS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
         // Log first few divisions and every 10th
-        if divisions < 5 || divisions % 10 == 0 {
+        if divisions < 5 || divisions.is_multiple_of(10) {
             crate::debug::log_event(format!(
                 "[DIVIDE #{}] gen:{}->{} parent_size:{} copied:{} final:{} (ins:{} del:{}) parent_merit:{:.1} child_merit:{:.1} gestation:{}{}",
                 divisions,
@@ -340,29 +515,34 @@ impl Organism {
         self.offspring_count += 1;
 
         // Reset parent state for next replication cycle
+        self.gestation_time = self.gestation_cycles;
         self.gestation_cycles = 0;
         self.cpu.ip = 0; // Reset to start of genome
         self.cpu.read_head = 0;
         self.cpu.write_head = 0;
         self.cpu.last_copied_label.clear();
+        self.cpu.initial_costs_paid.clear();
         self.child_copy_progress = 0;
 
         Some(offspring)
     }
 
-    /// Check if a task bit is set
+    /// Check if the task at `task_index` in `Task::all` has been completed
     pub fn has_completed_task(&self, task_index: u8) -> bool {
-        if task_index >= 9 {
-            return false;
-        }
-        (self.tasks_completed & (1 << task_index)) != 0
+        Task::from_index(task_index as usize)
+            .is_some_and(|task| self.tasks_completed.contains(task))
     }
 
-    /// Set a task as completed
+    /// Set a task as completed and count it
     pub fn complete_task(&mut self, task_index: u8) {
-        if task_index < 9 {
-            self.tasks_completed |= 1 << task_index;
+        let Some(task) = Task::from_index(task_index as usize) else {
+            return;
+        };
+        self.tasks_completed.insert(task);
+        if self.task_counts.len() < Task::COUNT {
+            self.task_counts.resize(Task::COUNT, 0);
         }
+        self.task_counts[task.index()] += 1;
     }
 
     /// Get genome as a string
@@ -393,6 +573,12 @@ impl Organism {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn test_rng() -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(42)
+    }
 
     #[test]
     fn test_ancestor_creation() {
@@ -429,16 +615,20 @@ mod tests {
         org.complete_task(0);
         assert!(org.has_completed_task(0));
         assert!(!org.has_completed_task(1));
+        org.complete_task(0);
+        assert_eq!(org.task_counts[0], 2);
+        assert_eq!(org.task_counts[1], 0);
     }
 
     #[test]
     fn test_copy_instruction() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
         org.allocate_child();
         org.cpu.read_head = 0;
         org.cpu.write_head = 0;
 
-        let copied = org.copy_instruction(0.0); // No mutations
+        let copied = org.copy_instruction(0.0, &InstSet::default(), &mut rng); // No mutations
         assert!(copied.is_some());
         assert_eq!(org.cpu.read_head, 1);
         assert_eq!(org.cpu.write_head, 1);
@@ -447,13 +637,14 @@ mod tests {
 
     #[test]
     fn test_divide_retains_copied_genome() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
         let genome_len = org.genome_size();
 
         org.allocate_child();
 
         for _ in 0..genome_len {
-            let copied = org.copy_instruction(0.0);
+            let copied = org.copy_instruction(0.0, &InstSet::default(), &mut rng);
             assert!(
                 copied.is_some(),
                 "copy_instruction returned None before genome copied"
@@ -463,7 +654,9 @@ mod tests {
         assert!(org.child_genome.is_some());
         assert_eq!(org.cpu.write_head, genome_len);
 
-        let offspring = org.divide(0.0, 0.0).expect("offspring should be produced");
+        let offspring = org
+            .divide(0.0, 0.0, &InstSet::default(), &mut rng)
+            .expect("offspring should be produced");
         assert_eq!(offspring.genome_size(), genome_len);
         assert!(org.child_genome.is_none());
         assert_eq!(org.cpu.write_head, 0);
@@ -472,8 +665,9 @@ mod tests {
 
     #[test]
     fn test_instruction_driven_reproduction_preserves_genome() {
+        let mut rng = test_rng();
         use crate::execute::execute_instruction;
-        use crate::tasks::TaskDetector;
+        use crate::tasks::{TaskDetector, TaskEnvironment};
 
         let mut org = Organism::ancestor();
         let original_genome = org.genome.clone();
@@ -482,11 +676,18 @@ mod tests {
 
         let mut steps = 0;
         let offspring = loop {
-            let (should_divide, _) = execute_instruction(&mut org, &mut detector, 0.0);
+            let (should_divide, _) = execute_instruction(
+                &mut org,
+                &mut detector,
+                &mut TaskEnvironment::default_logic9(),
+                0.0,
+                &InstSet::default(),
+                &mut rng,
+            );
             steps += 1;
 
             if should_divide {
-                break org.divide(0.0, 0.0);
+                break org.divide(0.0, 0.0, &InstSet::default(), &mut rng);
             }
 
             assert!(
@@ -504,6 +705,7 @@ mod tests {
 
     #[test]
     fn test_divide_pads_small_genomes_to_minimum() {
+        let mut rng = test_rng();
         use crate::instruction::parse_genome;
 
         let genome = parse_genome("rtsd").expect("valid minimal genome");
@@ -512,10 +714,12 @@ mod tests {
         org.allocate_child();
         let genome_len = org.genome_size();
         for _ in 0..genome_len {
-            org.copy_instruction(0.0);
+            org.copy_instruction(0.0, &InstSet::default(), &mut rng);
         }
 
-        let offspring = org.divide(0.0, 0.0).expect("offspring should be produced");
+        let offspring = org
+            .divide(0.0, 0.0, &InstSet::default(), &mut rng)
+            .expect("offspring should be produced");
         assert!(
             offspring.genome_size() >= MINIMUM_GENOME_SIZE,
             "offspring genome too small: {}",
@@ -537,7 +741,7 @@ mod tests {
         assert_eq!(org.instruction_count, 0);
         assert_eq!(org.generation, 0);
         assert_eq!(org.offspring_count, 0);
-        assert_eq!(org.tasks_completed, 0);
+        assert!(org.tasks_completed.is_empty());
         assert!(org.child_genome.is_none());
         assert_eq!(org.child_copy_progress, 0);
         assert_eq!(org.gestation_cycles, 0);
@@ -567,19 +771,21 @@ mod tests {
 
     #[test]
     fn test_copy_instruction_without_allocation_fails() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
-        let result = org.copy_instruction(0.0);
+        let result = org.copy_instruction(0.0, &InstSet::default(), &mut rng);
         assert!(result.is_none());
     }
 
     #[test]
     fn test_copy_instruction_advances_heads() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
         org.allocate_child();
         let initial_rh = org.cpu.read_head;
         let initial_wh = org.cpu.write_head;
 
-        org.copy_instruction(0.0);
+        org.copy_instruction(0.0, &InstSet::default(), &mut rng);
 
         assert_eq!(org.cpu.read_head, initial_rh + 1);
         assert_eq!(org.cpu.write_head, initial_wh + 1);
@@ -587,24 +793,26 @@ mod tests {
 
     #[test]
     fn test_copy_instruction_updates_progress() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
         org.allocate_child();
 
         assert_eq!(org.child_copy_progress, 0);
-        org.copy_instruction(0.0);
+        org.copy_instruction(0.0, &InstSet::default(), &mut rng);
         assert_eq!(org.child_copy_progress, 1);
-        org.copy_instruction(0.0);
+        org.copy_instruction(0.0, &InstSet::default(), &mut rng);
         assert_eq!(org.child_copy_progress, 2);
     }
 
     #[test]
     fn test_reallocating_child_resets_progress() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
         org.allocate_child();
         assert!(org.child_genome.is_some());
 
         // Perform a copy to advance progress and modify child contents
-        org.copy_instruction(0.0);
+        org.copy_instruction(0.0, &InstSet::default(), &mut rng);
         assert_eq!(org.child_copy_progress, 1);
         assert_eq!(org.cpu.read_head, 1);
         assert_eq!(org.cpu.write_head, 1);
@@ -623,24 +831,81 @@ mod tests {
         assert!(child.iter().all(|inst| *inst == Instruction::NopA));
     }
 
+    #[test]
+    fn test_divide_records_mutations() {
+        let mut rng = test_rng();
+        let mut org = Organism::ancestor();
+        org.allocate_child();
+        for _ in 0..org.genome.len() {
+            org.copy_instruction(0.2, &InstSet::default(), &mut rng);
+        }
+
+        let offspring = org.divide(1.0, 1.0, &InstSet::default(), &mut rng).unwrap();
+        assert!(matches!(
+            offspring.mutations[offspring.mutations.len() - 2..],
+            [Mutation::Deletion { .. }, Mutation::Insertion { .. }]
+        ));
+
+        let mut genome = org.genome.clone();
+        for mutation in &offspring.mutations {
+            mutation.apply(&mut genome);
+        }
+        assert_eq!(genome, offspring.genome);
+        assert_eq!(
+            Mutation::Point {
+                pos: 12,
+                from: Instruction::NopA,
+                to: Instruction::NopB
+            }
+            .to_string(),
+            "M12ab"
+        );
+    }
+
+    #[test]
+    fn test_divide_records_mutations_of_nonlinear_copy() {
+        let mut rng = test_rng();
+        let mut org = Organism::ancestor();
+        org.allocate_child();
+        for _ in 0..org.genome.len() {
+            org.copy_instruction(0.0, &InstSet::default(), &mut rng);
+        }
+        // Copy part of the genome again, shifted by three instructions
+        org.cpu.read_head = 10;
+        org.cpu.write_head = 7;
+        for _ in 0..5 {
+            org.copy_instruction(0.0, &InstSet::default(), &mut rng);
+        }
+
+        let offspring = org.divide(0.0, 0.0, &InstSet::default(), &mut rng).unwrap();
+        assert!(!offspring.mutations.is_empty());
+        let mut genome = org.genome.clone();
+        for mutation in &offspring.mutations {
+            mutation.apply(&mut genome);
+        }
+        assert_eq!(genome, offspring.genome);
+    }
+
     #[test]
     fn test_divide_without_child_fails() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
-        let result = org.divide(0.0, 0.0);
+        let result = org.divide(0.0, 0.0, &InstSet::default(), &mut rng);
         assert!(result.is_none());
     }
 
     #[test]
     fn test_divide_with_insufficient_copying_fails() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
         org.allocate_child();
 
         // Copy less than half
         for _ in 0..20 {
-            org.copy_instruction(0.0);
+            org.copy_instruction(0.0, &InstSet::default(), &mut rng);
         }
 
-        let result = org.divide(0.0, 0.0);
+        let result = org.divide(0.0, 0.0, &InstSet::default(), &mut rng);
         assert!(result.is_none());
         // Child should be retained for retry
         assert!(org.child_genome.is_some());
@@ -648,15 +913,16 @@ mod tests {
 
     #[test]
     fn test_divide_resets_parent_state() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
         org.allocate_child();
 
         for _ in 0..50 {
-            org.copy_instruction(0.0);
+            org.copy_instruction(0.0, &InstSet::default(), &mut rng);
         }
 
         let offspring_count_before = org.offspring_count;
-        let result = org.divide(0.0, 0.0);
+        let result = org.divide(0.0, 0.0, &InstSet::default(), &mut rng);
         assert!(result.is_some());
 
         assert_eq!(org.offspring_count, offspring_count_before + 1);
@@ -670,28 +936,30 @@ mod tests {
 
     #[test]
     fn test_offspring_generation_increments() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
         org.allocate_child();
 
         for _ in 0..50 {
-            org.copy_instruction(0.0);
+            org.copy_instruction(0.0, &InstSet::default(), &mut rng);
         }
 
-        let offspring = org.divide(0.0, 0.0).unwrap();
+        let offspring = org.divide(0.0, 0.0, &InstSet::default(), &mut rng).unwrap();
         assert_eq!(offspring.generation, org.generation + 1);
     }
 
     #[test]
     fn test_offspring_starts_with_base_merit() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
         org.merit = 100.0; // High merit from tasks
         org.allocate_child();
 
         for _ in 0..50 {
-            org.copy_instruction(0.0);
+            org.copy_instruction(0.0, &InstSet::default(), &mut rng);
         }
 
-        let offspring = org.divide(0.0, 0.0).unwrap();
+        let offspring = org.divide(0.0, 0.0, &InstSet::default(), &mut rng).unwrap();
         assert_eq!(offspring.merit, 1.0); // Reset to base, not inherited
     }
 
@@ -714,8 +982,9 @@ mod tests {
     #[test]
     fn test_task_completion_out_of_range() {
         let mut org = Organism::ancestor();
-        org.complete_task(10); // Out of range
-        assert!(!org.has_completed_task(10));
+        org.complete_task(Task::COUNT as u8); // Out of range
+        assert!(!org.has_completed_task(Task::COUNT as u8));
+        assert!(org.tasks_completed.is_empty());
     }
 
     #[test]
@@ -748,16 +1017,17 @@ mod tests {
 
     #[test]
     fn test_copy_with_mutation() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
         org.allocate_child();
 
         let mut mutation_occurred = false;
         for _ in 0..100 {
             let original_inst = org.genome[org.cpu.read_head];
-            org.copy_instruction(1.0); // 100% mutation rate
+            org.copy_instruction(1.0, &InstSet::default(), &mut rng); // 100% mutation rate
 
-            if org.child_genome.is_some() {
-                let copied_inst = org.child_genome.as_ref().unwrap()[org.cpu.write_head - 1];
+            if let Some(child) = org.child_genome.as_ref() {
+                let copied_inst = child[org.cpu.write_head - 1];
                 if copied_inst != original_inst {
                     mutation_occurred = true;
                     break;
@@ -772,59 +1042,67 @@ mod tests {
 
     #[test]
     fn test_divide_with_insertions() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
         org.allocate_child();
 
         for _ in 0..50 {
-            org.copy_instruction(0.0);
+            org.copy_instruction(0.0, &InstSet::default(), &mut rng);
         }
 
-        let offspring = org.divide(1.0, 0.0).unwrap(); // 100% insertion rate
+        let offspring = org.divide(1.0, 0.0, &InstSet::default(), &mut rng).unwrap(); // 100% insertion rate
         assert_eq!(offspring.genome_size(), 51);
     }
 
     #[test]
     fn test_divide_with_deletions() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
         org.allocate_child();
 
         for _ in 0..50 {
-            org.copy_instruction(0.0);
+            org.copy_instruction(0.0, &InstSet::default(), &mut rng);
         }
 
-        let offspring = org.divide(0.0, 1.0).unwrap(); // 100% deletion rate
+        let offspring = org.divide(0.0, 1.0, &InstSet::default(), &mut rng).unwrap(); // 100% deletion rate
         assert_eq!(offspring.genome_size(), 49);
     }
 
     #[test]
     fn test_empty_genome_after_deletions_gets_nop() {
+        let mut rng = test_rng();
         let mut org = Organism::new(vec![Instruction::NopA; 1]);
         org.allocate_child();
 
         for _ in 0..1 {
-            org.copy_instruction(0.0);
+            org.copy_instruction(0.0, &InstSet::default(), &mut rng);
         }
 
-        let offspring = org.divide(0.0, 1.0).unwrap(); // 100% deletion rate
-        assert_eq!(offspring.genome_size(), 1);
-        assert_eq!(offspring.genome[0], Instruction::NopC);
+        let offspring = org.divide(0.0, 1.0, &InstSet::default(), &mut rng).unwrap(); // 100% deletion rate
+        assert_eq!(offspring.genome_size(), MINIMUM_GENOME_SIZE);
+        assert!(offspring
+            .genome
+            .iter()
+            .all(|inst| *inst == Instruction::NopC));
     }
 
     #[test]
     fn test_divide_with_both_indels() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
         org.allocate_child();
 
         for _ in 0..50 {
-            org.copy_instruction(0.0);
+            org.copy_instruction(0.0, &InstSet::default(), &mut rng);
         }
 
-        let offspring = org.divide(1.0, 1.0).unwrap();
+        let offspring = org.divide(1.0, 1.0, &InstSet::default(), &mut rng).unwrap();
         assert_eq!(offspring.genome_size(), 50);
     }
 
     #[test]
     fn test_divide_with_low_indel_rates() {
+        let mut rng = test_rng();
         let mut unchanged_count = 0;
         let trials = 100;
 
@@ -833,10 +1111,12 @@ mod tests {
             org.allocate_child();
 
             for _ in 0..50 {
-                org.copy_instruction(0.0);
+                org.copy_instruction(0.0, &InstSet::default(), &mut rng);
             }
 
-            let offspring = org.divide(0.05, 0.05).unwrap();
+            let offspring = org
+                .divide(0.05, 0.05, &InstSet::default(), &mut rng)
+                .unwrap();
             if offspring.genome_size() == 50 {
                 unchanged_count += 1;
             }
@@ -929,6 +1209,7 @@ mod tests {
 
     #[test]
     fn test_gestation_cycles_reset_after_divide() {
+        let mut rng = test_rng();
         let mut org = Organism::ancestor();
         org.allocate_child();
 
@@ -940,11 +1221,40 @@ mod tests {
 
         // Complete copying
         for _ in 0..50 {
-            org.copy_instruction(0.0);
+            org.copy_instruction(0.0, &InstSet::default(), &mut rng);
         }
 
-        // Divide should reset gestation_cycles
-        let _offspring = org.divide(0.0, 0.0).unwrap();
+        // Divide should reset gestation_cycles and record the gestation time
+        let offspring = org.divide(0.0, 0.0, &InstSet::default(), &mut rng).unwrap();
         assert_eq!(org.gestation_cycles, 0);
+        assert_eq!(org.gestation_time, 10);
+        assert_eq!(offspring.gestation_time, 0);
+    }
+
+    #[test]
+    fn test_pay_instruction_cost() {
+        let inst_set =
+            InstSet::parse("INST nop-A\nINST nop-B:cost=3:initial_cost=2\nINST nop-C\n").unwrap();
+        let mut org = Organism::new(vec![Instruction::NopB; 20]);
+
+        // First execution pays cost and initial cost: four waiting cycles, then it runs
+        for _ in 0..4 {
+            assert!(!org.pay_instruction_cost(&inst_set));
+        }
+        assert_eq!(org.cpu.cost_remaining, 1);
+        assert!(org.pay_instruction_cost(&inst_set));
+        assert_eq!(org.cpu.cost_remaining, 0);
+        assert_eq!(org.gestation_cycles, 4);
+        org.execute_instruction();
+        org.advance_ip();
+
+        // Later executions only pay the cost
+        assert!(!org.pay_instruction_cost(&inst_set));
+        assert!(!org.pay_instruction_cost(&inst_set));
+        assert!(org.pay_instruction_cost(&inst_set));
+
+        // Skipped instructions take one cycle
+        org.cpu.skip_next = true;
+        assert!(org.pay_instruction_cost(&inst_set));
     }
 }
@@ -0,0 +1,169 @@
+//! Parasites
+//! Hosts run a multi-threaded CPU in the spirit of Avida's transsmt hardware: besides the host's
+//! own thread, each parasite that infected it runs as an extra thread with its own genome, CPU
+//! state and task detector, on cycles taken from the host's time slice.
+
+use crate::organism::Organism;
+use crate::tasks::{Task, TaskDetector};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Parasite settings (Avida PARASITE_GROUP)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParasiteSettings {
+    /// MAX_CPU_THREADS: threads per host, counting the host's own (1 = no parasites)
+    pub max_threads: usize,
+    /// PARASITE_VIRULENCE: share of the host's cycles a parasite of merit 1 takes
+    pub virulence: f64,
+    /// PARASITE_TASK_REWARDS: merit multiplier a parasite gets for each task, by
+    /// `Task::index` (1.0 = no reward; hosts use their own task bonuses)
+    pub task_rewards: Vec<f64>,
+}
+
+impl Default for ParasiteSettings {
+    /// Single-threaded hosts; parasites need MAX_CPU_THREADS of at least 2
+    fn default() -> Self {
+        Self {
+            max_threads: 1,
+            virulence: 0.5,
+            task_rewards: vec![1.0; Task::COUNT],
+        }
+    }
+}
+
+impl ParasiteSettings {
+    /// Merit multiplier a parasite gets for `task`
+    pub fn task_reward(&self, task: Task) -> f64 {
+        self.task_rewards.get(task.index()).copied().unwrap_or(1.0)
+    }
+
+    /// Format the task rewards as `task=multiplier` pairs (`none` if no task is rewarded)
+    pub fn format_task_rewards(&self) -> String {
+        let rewards: Vec<String> = Task::all()
+            .into_iter()
+            .filter(|&task| self.task_reward(task) != 1.0)
+            .map(|task| format!("{}={}", task.name().to_lowercase(), self.task_reward(task)))
+            .collect();
+        if rewards.is_empty() {
+            "none".to_string()
+        } else {
+            rewards.join(",")
+        }
+    }
+
+    /// Parse task rewards written by `format_task_rewards`; unlisted tasks get no reward
+    pub fn parse_task_rewards(value: &str) -> Result<Vec<f64>, String> {
+        let mut rewards = vec![1.0; Task::COUNT];
+        if value == "none" {
+            return Ok(rewards);
+        }
+        for pair in value.split(',') {
+            let (name, reward) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected task=multiplier, got '{}'", pair))?;
+            let task = Task::from_name(name).ok_or_else(|| format!("unknown task '{}'", name))?;
+            let reward: f64 = reward
+                .parse()
+                .ok()
+                .filter(|reward: &f64| *reward > 0.0)
+                .ok_or_else(|| format!("invalid reward '{}' for {}", reward, name))?;
+            rewards[task.index()] = reward;
+        }
+        Ok(rewards)
+    }
+}
+
+/// A parasite running as a thread of its host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Parasite {
+    pub org: Organism,
+    pub detector: TaskDetector,
+}
+
+impl Parasite {
+    pub fn new(org: Organism) -> Self {
+        Self {
+            org,
+            detector: TaskDetector::new(),
+        }
+    }
+}
+
+/// Choose the thread that runs the next host cycle: None for the host's own thread,
+/// otherwise the index of a parasite
+/// The host has weight 1 - virulence and each parasite virulence × its merit, so parasites
+/// rewarded for tasks take a larger share of the host
+pub fn choose_thread<R: Rng + ?Sized>(
+    parasites: &[Parasite],
+    virulence: f64,
+    rng: &mut R,
+) -> Option<usize> {
+    if parasites.is_empty() {
+        return None;
+    }
+    let host_weight = 1.0 - virulence;
+    let total: f64 = host_weight
+        + parasites
+            .iter()
+            .map(|parasite| virulence * parasite.org.merit)
+            .sum::<f64>();
+    let mut pick = rng.gen::<f64>() * total - host_weight;
+    if pick < 0.0 {
+        return None;
+    }
+    for (i, parasite) in parasites.iter().enumerate() {
+        pick -= virulence * parasite.org.merit;
+        if pick < 0.0 {
+            return Some(i);
+        }
+    }
+    Some(parasites.len() - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_choose_thread_follows_virulence() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        assert_eq!(choose_thread(&[], 1.0, &mut rng), None);
+
+        let parasites = vec![Parasite::new(Organism::parasite_ancestor())];
+        let share = |parasites: &[Parasite], virulence, rng: &mut ChaCha8Rng| {
+            (0..10_000)
+                .filter(|_| choose_thread(parasites, virulence, rng).is_some())
+                .count() as f64
+                / 10_000.0
+        };
+        assert_eq!(share(&parasites, 0.0, &mut rng), 0.0);
+        assert_eq!(share(&parasites, 1.0, &mut rng), 1.0);
+        assert!((share(&parasites, 0.25, &mut rng) - 0.25).abs() < 0.02);
+
+        // A rewarded parasite takes more cycles: 0.5 × 3 / (0.5 + 0.5 × 3)
+        let mut rewarded = parasites;
+        rewarded[0].org.merit = 3.0;
+        assert!((share(&rewarded, 0.5, &mut rng) - 0.75).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_task_rewards_round_trip() {
+        let mut settings = ParasiteSettings::default();
+        assert_eq!(settings.format_task_rewards(), "none");
+        settings.task_rewards =
+            ParasiteSettings::parse_task_rewards("not=2,XOR=16,logic_3aa=4").unwrap();
+        assert_eq!(settings.task_reward(Task::Not), 2.0);
+        assert_eq!(settings.task_reward(Task::Xor), 16.0);
+        assert_eq!(settings.task_reward(Task::Nand), 1.0);
+        assert_eq!(settings.task_reward(Task::Logic3(0)), 4.0);
+        assert_eq!(
+            ParasiteSettings::parse_task_rewards(&settings.format_task_rewards()).unwrap(),
+            settings.task_rewards
+        );
+        assert!(ParasiteSettings::parse_task_rewards("xnor=2").is_err());
+        assert!(ParasiteSettings::parse_task_rewards("not=0").is_err());
+        assert!(ParasiteSettings::parse_task_rewards("not").is_err());
+    }
+}
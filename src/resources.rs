@@ -0,0 +1,403 @@
+//! Resources
+//! Global resource pools (Avida RESOURCE): each starts at an initial amount and every update
+//! loses a fraction of its amount to outflow and gains a constant inflow, so an unused pool
+//! settles at inflow / outflow. Reactions consume part of a pool when their task is performed.
+//! Spatial resources keep a concentration per grid cell instead: inflow and outflow apply to
+//! regions of the grid, concentrations diffuse and drift between neighboring cells, and
+//! organisms consume from their own cell.
+
+use serde::{Deserialize, Serialize};
+
+/// A global resource pool shared by the whole population
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Resource {
+    pub name: String,
+    /// Amount at the start of a run
+    pub initial: f64,
+    /// Amount added every update
+    pub inflow: f64,
+    /// Fraction of the amount removed every update
+    pub outflow: f64,
+    /// Current amount (the total over all cells for a spatial resource)
+    pub amount: f64,
+    /// Per-cell concentrations (None = a single global pool)
+    #[serde(default)]
+    pub spatial: Option<Spatial>,
+}
+
+impl Resource {
+    pub fn new(name: &str, initial: f64, inflow: f64, outflow: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            initial,
+            inflow,
+            outflow,
+            amount: initial,
+            spatial: None,
+        }
+    }
+
+    /// Make the resource spatial, spreading the initial amount evenly over the cells
+    pub fn with_spatial(mut self, spatial: Spatial) -> Self {
+        self.spatial = Some(spatial);
+        self.reset();
+        self
+    }
+
+    /// Apply one update of outflow and inflow (and diffusion for a spatial resource)
+    pub fn update(&mut self) {
+        match &mut self.spatial {
+            None => self.amount = self.amount * (1.0 - self.outflow) + self.inflow,
+            Some(spatial) => {
+                spatial.update(self.inflow, self.outflow);
+                self.amount = spatial.total();
+            }
+        }
+    }
+
+    /// Consume `frac` of the amount available at `position`, but at most `max`
+    /// A global pool ignores the position. A spatial resource consumes from the cell at
+    /// `position`, or evenly from all cells at their mean concentration without one (in the
+    /// test CPU).
+    /// Returns the amount consumed; nothing is consumed if it would be less than `min`
+    pub fn consume(
+        &mut self,
+        frac: f64,
+        max: f64,
+        min: f64,
+        position: Option<(usize, usize)>,
+    ) -> f64 {
+        let consumed = match &mut self.spatial {
+            None => consumption(self.amount, frac, max, min),
+            Some(spatial) => spatial.consume(frac, max, min, position),
+        };
+        self.amount -= consumed;
+        consumed
+    }
+
+    /// Set the amount back to the initial amount
+    pub fn reset(&mut self) {
+        self.amount = self.initial;
+        if let Some(spatial) = &mut self.spatial {
+            let per_cell = self.initial / spatial.cells.len().max(1) as f64;
+            spatial.cells.fill(per_cell);
+        }
+    }
+
+    /// Fit a spatial resource to a world of a new size and reset it
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if let Some(spatial) = &mut self.spatial {
+            spatial.width = width;
+            spatial.height = height;
+            spatial.cells = vec![0.0; width * height];
+        }
+        self.reset();
+    }
+}
+
+/// Amount consumed from `available`: `frac` of it, at most `max`, nothing below `min`
+fn consumption(available: f64, frac: f64, max: f64, min: f64) -> f64 {
+    let consumed = (available * frac).min(max);
+    if consumed <= 0.0 || consumed < min {
+        0.0
+    } else {
+        consumed
+    }
+}
+
+/// A rectangle of cells from (x1, y1) to (x2, y2), inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Region {
+    pub x1: usize,
+    pub y1: usize,
+    pub x2: usize,
+    pub y2: usize,
+}
+
+impl Region {
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x1..=self.x2).contains(&x) && (self.y1..=self.y2).contains(&y)
+    }
+}
+
+/// Per-cell concentrations of a spatial resource, on a grid matching the world's
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Spatial {
+    pub width: usize,
+    pub height: usize,
+    /// Whether flow wraps around the edges like the world grid (Avida `geometry=torus`);
+    /// otherwise the edges are closed (`geometry=grid`)
+    pub torus: bool,
+    /// Cells receiving the inflow, split evenly between them (None = every cell)
+    pub inflow_region: Option<Region>,
+    /// Cells losing the outflow fraction (None = every cell)
+    pub outflow_region: Option<Region>,
+    /// Diffusion rates along x and y, from 0 (none) to 1 (fastest)
+    pub xdiffuse: f64,
+    pub ydiffuse: f64,
+    /// Fraction of each cell's concentration drifting one cell towards +x and +y per update
+    /// (negative values drift towards -x and -y)
+    pub xgravity: f64,
+    pub ygravity: f64,
+    /// Concentration of each cell, row by row
+    pub cells: Vec<f64>,
+}
+
+impl Spatial {
+    /// A closed grid with inflow and outflow everywhere, full diffusion and no gravity
+    /// (Avida's defaults)
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            torus: false,
+            inflow_region: None,
+            outflow_region: None,
+            xdiffuse: 1.0,
+            ydiffuse: 1.0,
+            xgravity: 0.0,
+            ygravity: 0.0,
+            cells: vec![0.0; width * height],
+        }
+    }
+
+    /// Concentration of the cell at (`x`, `y`)
+    pub fn get(&self, x: usize, y: usize) -> f64 {
+        if x < self.width && y < self.height {
+            self.cells[y * self.width + x]
+        } else {
+            0.0
+        }
+    }
+
+    /// Total amount over all cells
+    pub fn total(&self) -> f64 {
+        self.cells.iter().sum()
+    }
+
+    /// Highest concentration of any cell
+    pub fn max(&self) -> f64 {
+        self.cells.iter().copied().fold(0.0, f64::max)
+    }
+
+    fn update(&mut self, inflow: f64, outflow: f64) {
+        let width = self.width;
+        let in_region = |region: &Option<Region>, i: usize| {
+            region.is_none_or(|region| region.contains(i % width, i / width))
+        };
+        let inflow_cells = (0..self.cells.len())
+            .filter(|&i| in_region(&self.inflow_region, i))
+            .count();
+        let mut cells = std::mem::take(&mut self.cells);
+        for (i, cell) in cells.iter_mut().enumerate() {
+            if in_region(&self.outflow_region, i) {
+                *cell *= 1.0 - outflow;
+            }
+            if in_region(&self.inflow_region, i) {
+                *cell += inflow / inflow_cells as f64;
+            }
+        }
+        self.cells = cells;
+        self.flow();
+    }
+
+    /// Diffusion and gravity between each cell and its +x and +y neighbors
+    /// A cell asked to give more than it holds gives all it holds, split in proportion,
+    /// so concentrations stay non-negative and flow neither creates nor destroys any amount
+    fn flow(&mut self) {
+        // Amounts moving between neighbors as (from, to, amount), with amount >= 0
+        let mut moves = Vec::with_capacity(2 * self.cells.len());
+        let mut outflow = vec![0.0; self.cells.len()];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let i = y * self.width + x;
+                let a = self.cells[i];
+                let pairs = [
+                    (self.neighbor(x + 1, y), self.xdiffuse, self.xgravity),
+                    (self.neighbor(x, y + 1), self.ydiffuse, self.ygravity),
+                ];
+                for (j, diffuse, gravity) in pairs {
+                    let Some(j) = j else { continue };
+                    let b = self.cells[j];
+                    // Positive amounts move from cell i to its neighbor j
+                    let drift = if gravity >= 0.0 {
+                        gravity * a
+                    } else {
+                        gravity * b
+                    };
+                    let moved = diffuse * (a - b) / 4.0 + drift;
+                    let (from, to, amount) = if moved >= 0.0 {
+                        (i, j, moved)
+                    } else {
+                        (j, i, -moved)
+                    };
+                    outflow[from] += amount;
+                    moves.push((from, to, amount));
+                }
+            }
+        }
+        let scale: Vec<f64> = self
+            .cells
+            .iter()
+            .zip(&outflow)
+            .map(|(&amount, &out)| if out > amount { amount / out } else { 1.0 })
+            .collect();
+        let mut next: Vec<f64> = self
+            .cells
+            .iter()
+            .zip(&outflow)
+            .zip(&scale)
+            .map(|((&amount, &out), &scale)| if scale < 1.0 { 0.0 } else { amount - out })
+            .collect();
+        for (from, to, amount) in moves {
+            next[to] += amount * scale[from];
+        }
+        self.cells = next;
+    }
+
+    /// Index of the cell at (`x`, `y`) one step past an edge: wrapped on a torus,
+    /// None on a closed grid
+    fn neighbor(&self, x: usize, y: usize) -> Option<usize> {
+        let wrap = |v: usize, size: usize| {
+            if v < size {
+                Some(v)
+            } else if self.torus {
+                Some(v % size)
+            } else {
+                None
+            }
+        };
+        Some(wrap(y, self.height)? * self.width + wrap(x, self.width)?)
+    }
+
+    fn consume(&mut self, frac: f64, max: f64, min: f64, position: Option<(usize, usize)>) -> f64 {
+        let cell = position
+            .filter(|&(x, y)| x < self.width && y < self.height)
+            .map(|(x, y)| y * self.width + x);
+        match cell {
+            Some(i) => {
+                let consumed = consumption(self.cells[i], frac, max, min);
+                self.cells[i] -= consumed;
+                consumed
+            }
+            None => {
+                let mean = self.total() / self.cells.len().max(1) as f64;
+                let consumed = consumption(mean, frac, max, min);
+                if consumed > 0.0 {
+                    let scale = 1.0 - consumed / mean / self.cells.len() as f64;
+                    for cell in &mut self.cells {
+                        *cell *= scale;
+                    }
+                }
+                consumed
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inflow_and_outflow_reach_equilibrium() {
+        let mut resource = Resource::new("glucose", 0.0, 10.0, 0.1);
+        resource.update();
+        assert_eq!(resource.amount, 10.0);
+        for _ in 0..1000 {
+            resource.update();
+        }
+        assert!((resource.amount - 100.0).abs() < 1e-9);
+
+        resource.reset();
+        assert_eq!(resource.amount, 0.0);
+    }
+
+    #[test]
+    fn test_consume_fraction_with_limits() {
+        let mut resource = Resource::new("glucose", 100.0, 0.0, 0.0);
+        assert_eq!(resource.consume(0.1, 1000.0, 0.0, None), 10.0);
+        assert_eq!(resource.amount, 90.0);
+        // Capped by max
+        assert_eq!(resource.consume(0.5, 5.0, 0.0, None), 5.0);
+        assert_eq!(resource.amount, 85.0);
+        // Below min nothing is consumed
+        assert_eq!(resource.consume(0.01, 1.0, 1.0, None), 0.0);
+        assert_eq!(resource.amount, 85.0);
+    }
+
+    #[test]
+    fn test_spatial_inflow_region_and_diffusion() {
+        let mut spatial = Spatial::new(10, 1);
+        spatial.inflow_region = Some(Region {
+            x1: 0,
+            y1: 0,
+            x2: 0,
+            y2: 0,
+        });
+        let mut resource = Resource::new("glucose", 0.0, 8.0, 0.0).with_spatial(spatial);
+        for _ in 0..50 {
+            resource.update();
+        }
+        let spatial = resource.spatial.as_ref().unwrap();
+        // Nothing is lost without outflow, and the resource spreads from the inflow cell
+        assert!((resource.amount - 400.0).abs() < 1e-9);
+        assert!((spatial.total() - 400.0).abs() < 1e-9);
+        assert!(spatial.get(9, 0) > 0.0);
+        for x in 1..10 {
+            assert!(spatial.get(x - 1, 0) > spatial.get(x, 0));
+        }
+
+        // Organisms consume from their own cell
+        let before = spatial.get(0, 0);
+        let consumed = resource.consume(0.5, 1000.0, 0.0, Some((0, 0)));
+        assert_eq!(consumed, before / 2.0);
+        assert_eq!(resource.spatial.as_ref().unwrap().get(0, 0), before / 2.0);
+        assert!((resource.amount - (400.0 - consumed)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_spatial_flow_conserves_mass() {
+        for torus in [false, true] {
+            let mut spatial = Spatial::new(4, 3);
+            spatial.torus = torus;
+            spatial.xgravity = 1.0;
+            spatial.ygravity = -0.8;
+            spatial.cells = (0..12).map(|i| ((i * 7) % 5) as f64).collect();
+            spatial.cells[5] = 100.0;
+            let total = spatial.total();
+            for _ in 0..50 {
+                // Without inflow or outflow, only diffusion and gravity move the resource
+                spatial.update(0.0, 0.0);
+                assert!(spatial.cells.iter().all(|&c| c >= 0.0));
+                assert!((spatial.total() - total).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_spatial_gravity_and_geometry() {
+        let mut spatial = Spatial::new(5, 5);
+        spatial.xdiffuse = 0.0;
+        spatial.ydiffuse = 0.0;
+        spatial.ygravity = 0.5;
+        let mut resource = Resource::new("glucose", 25.0, 0.0, 0.0).with_spatial(spatial.clone());
+        for _ in 0..100 {
+            resource.update();
+        }
+        // Drifts to the closed bottom edge
+        let grid = resource.spatial.as_ref().unwrap();
+        assert!((grid.total() - 25.0).abs() < 1e-9);
+        assert!((0..5).all(|x| (grid.get(x, 4) - 5.0).abs() < 1e-6));
+
+        // On a torus it keeps circulating evenly
+        spatial.torus = true;
+        let mut resource = Resource::new("glucose", 25.0, 0.0, 0.0).with_spatial(spatial);
+        resource.update();
+        let grid = resource.spatial.as_ref().unwrap();
+        assert!(grid.cells.iter().all(|&c| (c - 1.0).abs() < 1e-9));
+
+        resource.resize(2, 2);
+        assert_eq!(resource.spatial.as_ref().unwrap().cells, vec![6.25; 4]);
+    }
+}
@@ -0,0 +1,437 @@
+//! Avida structured population (.spop) files
+//! Exports the living population grouped by genotype and imports populations saved by
+//! C++ Avida or avida-rs. Sequences are written with C++ Avida's `heads_default` letters
+//! so files are interchangeable; other instruction sets are rejected on import.
+
+use crate::genotype::summarize;
+use crate::instruction::{genome_to_heads_default, parse_heads_default_genome, Instruction};
+use crate::organism::Organism;
+use crate::world::World;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Instruction set name written to and accepted from .spop files
+pub const INST_SET: &str = "heads_default";
+
+/// Columns written by `format`, in Avida's default SavePopulation order
+const COLUMNS: &[(&str, &str)] = &[
+    ("id", "ID"),
+    ("src", "Source"),
+    ("src_args", "Source Args"),
+    ("parent_id", "Parent ID(s)"),
+    ("num_units", "Number of currently living organisms"),
+    ("total_units", "Total number of organisms that ever existed"),
+    ("length", "Genome Length"),
+    ("merit", "Average Merit"),
+    ("gest_time", "Average Gestation Time"),
+    ("fitness", "Average Fitness"),
+    ("gen_born", "Generation Born"),
+    ("update_born", "Update Born"),
+    ("update_deactivated", "Update Deactivated"),
+    ("depth", "Phylogenetic Depth"),
+    ("hw_type", "Hardware Type ID"),
+    ("inst_set", "Instruction Set Name"),
+    ("sequence", "Genome Sequence"),
+    ("cells", "Occupied Cell IDs"),
+    ("gest_offset", "Gestation (CPU) Cycle Offsets"),
+    ("lineage", "Lineage Label"),
+];
+
+/// One genotype line of a .spop file
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpopGenotype {
+    pub id: u64,
+    /// Parent genotype ids (empty if unknown)
+    pub parent_ids: Vec<u64>,
+    pub num_units: usize,
+    pub genome: Vec<Instruction>,
+    /// Occupied cells, as Avida cell ids (y * world_x + x)
+    pub cells: Vec<usize>,
+    pub gen_born: u32,
+    /// Update the genotype first appeared (-1 if unknown)
+    pub update_born: i64,
+}
+
+/// Cells occupied by each living genotype, in grid order
+fn genotype_cells(world: &World) -> HashMap<u64, Vec<usize>> {
+    let mut cells: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (cell, org) in world.grid.iter().enumerate() {
+        if let Some(id) = org.as_ref().and_then(|org| org.genotype_id) {
+            cells.entry(id).or_default().push(cell);
+        }
+    }
+    cells
+}
+
+fn join<T: ToString>(values: impl IntoIterator<Item = T>) -> String {
+    values
+        .into_iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Format the living population as a .spop file (one line per genotype)
+/// Ids, parents and depth come from the world's genotype registry; gestation time is -1
+/// for genotypes none of whose organisms has divided yet
+pub fn format(world: &World) -> String {
+    let mut out = String::from("#filetype genotype_data\n#format");
+    for (name, _) in COLUMNS {
+        let _ = write!(out, " {}", name);
+    }
+    let _ = writeln!(
+        out,
+        "\n# Structured Population Save\n# avida-rs update {}\n#",
+        world.total_updates
+    );
+    for (i, (_, description)) in COLUMNS.iter().enumerate() {
+        let _ = writeln!(out, "# {:2}: {}", i + 1, description);
+    }
+    out.push('\n');
+
+    let mut cells = genotype_cells(world);
+    for genotype in world.genotypes().living() {
+        let cells = cells.remove(&genotype.id).unwrap_or_default();
+        let orgs: Vec<&Organism> = cells
+            .iter()
+            .filter_map(|&cell| world.grid[cell].as_ref())
+            .collect();
+        let report = summarize(genotype, orgs.iter().copied());
+
+        let fields = [
+            genotype.id.to_string(),
+            match genotype.parent_id {
+                Some(_) => "div:int",
+                None => "inject",
+            }
+            .to_string(),
+            "(none)".to_string(),
+            genotype
+                .parent_id
+                .map_or("(none)".to_string(), |id| id.to_string()),
+            genotype.abundance.to_string(),
+            genotype.total_organisms.to_string(),
+            genotype.genome.len().to_string(),
+            format!("{:.6}", report.merit),
+            if report.gestation_time > 0.0 {
+                format!("{:.6}", report.gestation_time)
+            } else {
+                "-1".to_string()
+            },
+            format!("{:.6}", report.fitness),
+            genotype.generation_born.to_string(),
+            genotype.update_born.to_string(),
+            "-1".to_string(),
+            genotype.depth.to_string(),
+            "0".to_string(),
+            INST_SET.to_string(),
+            genome_to_heads_default(&genotype.genome),
+            join(&cells),
+            join(orgs.iter().map(|o| o.gestation_cycles)),
+            "0".to_string(),
+        ];
+        out.push_str(&fields.join(" "));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Write the living population to a .spop file
+pub fn save(world: &World, path: impl AsRef<Path>) -> Result<(), String> {
+    let path = path.as_ref();
+    std::fs::write(path, format(world))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn parse_list<T: std::str::FromStr>(column: &str, value: &str) -> Result<Vec<T>, String> {
+    if value.is_empty() || value == "(none)" {
+        return Ok(Vec::new());
+    }
+    value
+        .split(',')
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            v.parse()
+                .map_err(|_| format!("invalid value '{}' in {}", v, column))
+        })
+        .collect()
+}
+
+fn parse_field<T: std::str::FromStr>(column: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' for {}", value, column))
+}
+
+/// Parse the genotypes of a .spop file
+/// Columns are taken from the `#format` header when present (so files from C++ Avida
+/// with extra or reordered columns load), otherwise the avida-rs default order is assumed.
+/// A missing `inst_set` column is read as `heads_default`.
+pub fn parse(contents: &str) -> Result<Vec<SpopGenotype>, String> {
+    let mut columns: Vec<String> = COLUMNS.iter().map(|(name, _)| name.to_string()).collect();
+    let mut genotypes = Vec::new();
+
+    for (line_num, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if let Some(format) = line.strip_prefix("#format") {
+            columns = format.split_whitespace().map(str::to_string).collect();
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: HashMap<&str, &str> = columns
+            .iter()
+            .map(String::as_str)
+            .zip(line.split_whitespace())
+            .collect();
+        let field = |column: &str| fields.get(column).copied().unwrap_or("");
+        let context = |e: String| format!("line {}: {}", line_num + 1, e);
+
+        let sequence = field("sequence");
+        if sequence.is_empty() {
+            return Err(context("missing sequence".to_string()));
+        }
+        let inst_set = field("inst_set");
+        if !inst_set.is_empty() && inst_set != INST_SET {
+            return Err(context(format!(
+                "unsupported instruction set '{}' (only {} is supported)",
+                inst_set, INST_SET
+            )));
+        }
+        let num_units: usize = parse_field("num_units", field("num_units")).map_err(context)?;
+        let cells: Vec<usize> = parse_list("cells", field("cells")).map_err(context)?;
+        if cells.len() != num_units {
+            return Err(context(format!(
+                "{} living organisms but {} cells listed",
+                num_units,
+                cells.len()
+            )));
+        }
+
+        genotypes.push(SpopGenotype {
+            id: parse_field("id", field("id")).map_err(context)?,
+            parent_ids: parse_list("parent_id", field("parent_id")).map_err(context)?,
+            num_units,
+            genome: parse_heads_default_genome(sequence).map_err(context)?,
+            cells,
+            gen_born: match field("gen_born") {
+                "" => 0,
+                value => parse_field("gen_born", value).map_err(context)?,
+            },
+            update_born: match field("update_born") {
+                "" => -1,
+                value => parse_field("update_born", value).map_err(context)?,
+            },
+        });
+    }
+
+    Ok(genotypes)
+}
+
+/// Inject the organisms of a .spop file into their cells
+/// Occupied cells are replaced; returns the number of organisms injected
+pub fn import(world: &mut World, contents: &str) -> Result<usize, String> {
+    let genotypes = parse(contents)?;
+    let (width, height) = world.dimensions();
+
+    // Validate everything before touching the world
+    let mut occupied = HashSet::new();
+    for genotype in &genotypes {
+        if genotype.genome.is_empty() {
+            return Err(format!("genotype {} has an empty sequence", genotype.id));
+        }
+        for &cell in &genotype.cells {
+            if cell >= width * height {
+                return Err(format!(
+                    "genotype {} occupies cell {} outside the {}x{} world",
+                    genotype.id, cell, width, height
+                ));
+            }
+            if !occupied.insert(cell) {
+                return Err(format!(
+                    "cell {} is listed more than once (genotype {})",
+                    cell, genotype.id
+                ));
+            }
+        }
+    }
+
+    let mut injected = 0;
+    for genotype in &genotypes {
+        for &cell in &genotype.cells {
+            let mut org = Organism::new(genotype.genome.clone());
+            org.generation = genotype.gen_born;
+            world.inject_organism(org, cell % width, cell / width);
+            if let (Some(org), Ok(update_born)) = (
+                world.grid[cell].as_mut(),
+                u64::try_from(genotype.update_born),
+            ) {
+                org.update_born = update_born;
+            }
+            injected += 1;
+        }
+    }
+
+    Ok(injected)
+}
+
+/// Read a .spop file and inject its organisms into the world
+pub fn load(world: &mut World, path: impl AsRef<Path>) -> Result<usize, String> {
+    let path = path.as_ref();
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    import(world, &contents).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_groups_genotypes() {
+        let mut world = World::with_dimensions_and_seed(10, 10, 1);
+        world.inject_organism(Organism::ancestor(), 1, 0);
+        world.inject_organism(Organism::ancestor(), 5, 5);
+        world.inject_organism(Organism::ancestor_with_tasks(), 2, 3);
+
+        let text = format(&world);
+        assert!(text.starts_with("#filetype genotype_data\n#format id src"));
+        let ancestor = genome_to_heads_default(&Organism::ancestor().genome);
+        assert!(text.contains(&format!(" heads_default {} ", ancestor)));
+
+        let genotypes = parse(&text).unwrap();
+        assert_eq!(genotypes.len(), 2);
+        assert_eq!(genotypes[0].id, 1);
+        assert_eq!(genotypes[0].num_units, 2);
+        assert_eq!(genotypes[0].cells, vec![1, 55]);
+        assert_eq!(genotypes[0].genome, Organism::ancestor().genome);
+        assert_eq!(genotypes[1].cells, vec![32]);
+        assert_eq!(genotypes[1].genome, Organism::ancestor_with_tasks().genome);
+        assert!(genotypes[1].parent_ids.is_empty());
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let mut world = World::with_dimensions_and_seed(15, 15, 2);
+        world.copy_mutation_rate = 0.02;
+        world.inject_ancestor_with_tasks();
+        for _ in 0..150 {
+            world.update();
+        }
+
+        // Ids and parents are the registry's
+        for genotype in parse(&format(&world)).unwrap() {
+            let registered = world.genotypes().get(genotype.id).unwrap();
+            assert_eq!(genotype.genome, registered.genome);
+            assert_eq!(
+                genotype.parent_ids,
+                registered.parent_id.into_iter().collect::<Vec<_>>()
+            );
+        }
+
+        let mut restored = World::with_dimensions_and_seed(15, 15, 3);
+        let injected = import(&mut restored, &format(&world)).unwrap();
+
+        assert_eq!(injected, world.population_size);
+        assert_eq!(restored.population_size, world.population_size);
+        for (original, loaded) in world.grid.iter().zip(&restored.grid) {
+            assert_eq!(
+                original.as_ref().map(|o| &o.genome),
+                loaded.as_ref().map(|o| &o.genome)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_avida_file() {
+        let contents = "\
+#filetype genotype_data
+#format id src src_args parent_id num_units total_units length merit gest_time fitness gen_born update_born update_deactivated depth hw_type inst_set sequence cells gest_offset lineage
+# Structured Population Save
+
+12 div:int (none) 3,7 2 5 8 24.5 380 0.06 14 1200 -1 14 0 heads_default wzcagvfx 4,9 0,0 0
+13 div:int (none) 12 0 1 8 1 390 0.002 15 1300 1400 15 0 heads_default wzcagvfb
+";
+        let genotypes = parse(contents).unwrap();
+        assert_eq!(genotypes.len(), 2);
+        assert_eq!(genotypes[0].id, 12);
+        assert_eq!(genotypes[0].parent_ids, vec![3, 7]);
+        assert_eq!(genotypes[0].cells, vec![4, 9]);
+        assert_eq!(genotypes[0].gen_born, 14);
+        assert_eq!(genotypes[0].update_born, 1200);
+        assert!(genotypes[1].cells.is_empty());
+
+        let mut world = World::with_dimensions_and_seed(5, 5, 1);
+        assert_eq!(import(&mut world, contents).unwrap(), 2);
+        let org = world.get_organism(4, 1).unwrap();
+        // heads_default letters: h-alloc h-search nop-C nop-A mov-head h-copy if-label h-divide
+        assert_eq!(org.genome_string(), "rucavtys");
+        assert_eq!(org.generation, 14);
+        assert_eq!(org.update_born, 1200);
+    }
+
+    #[test]
+    fn test_parse_custom_column_order() {
+        let contents = "#format sequence num_units cells id\nrutyabsv 1 3 9\n";
+        let genotypes = parse(contents).unwrap();
+        assert_eq!(genotypes[0].id, 9);
+        assert_eq!(genotypes[0].cells, vec![3]);
+        assert_eq!(genotypes[0].update_born, -1);
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let mut world = World::with_dimensions_and_seed(5, 5, 1);
+        let header = "#format id num_units sequence cells\n";
+
+        let err = import(&mut world, &format!("{}1 1 rut!bsv 0\n", header)).unwrap_err();
+        assert!(err.contains("line 2"), "{}", err);
+        assert!(err.contains("Invalid instruction character"), "{}", err);
+
+        let err = import(&mut world, &format!("{}1 1 rutyabsv 25\n", header)).unwrap_err();
+        assert!(err.contains("outside the 5x5 world"), "{}", err);
+        assert_eq!(world.population_size, 0);
+
+        let err = import(&mut world, &format!("{}1 2 rutyabsv 0\n", header)).unwrap_err();
+        assert!(err.contains("2 living organisms but 1 cells"), "{}", err);
+        let err = import(&mut world, "#format id num_units sequence\n1 1 rutyabsv\n").unwrap_err();
+        assert!(err.contains("1 living organisms but 0 cells"), "{}", err);
+
+        let duplicate = format!("{}1 1 rutyabsv 3\n2 2 rutyabsb 4,3\n", header);
+        let err = import(&mut world, &duplicate).unwrap_err();
+        assert!(err.contains("cell 3 is listed more than once"), "{}", err);
+        assert_eq!(world.population_size, 0);
+
+        let foreign = "#format id num_units inst_set sequence cells\n1 1 transsmt rutyabsv 0\n";
+        let err = import(&mut world, foreign).unwrap_err();
+        assert!(
+            err.contains("unsupported instruction set 'transsmt'"),
+            "{}",
+            err
+        );
+
+        assert!(import(&mut world, &format!("{}x 1 rutyabsv 0\n", header)).is_err());
+        assert!(load(&mut world, "/nonexistent/pop.spop").is_err());
+    }
+
+    #[test]
+    fn test_save_and_load_file() {
+        let dir = std::env::temp_dir().join(format!("avida-rs-spop-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("detail.spop");
+
+        let mut world = World::with_dimensions_and_seed(10, 10, 4);
+        world.inject_ancestor();
+        save(&world, &path).unwrap();
+
+        let mut loaded = World::with_dimensions_and_seed(10, 10, 5);
+        assert_eq!(load(&mut loaded, &path).unwrap(), 1);
+        assert!(loaded.get_organism(5, 5).is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
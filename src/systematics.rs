@@ -0,0 +1,210 @@
+//! Phylogeny export
+//! The genotype registry links each new genotype to its parent's genotype when the offspring
+//! of a division is placed, and drops extinct branches without living descendants. This module
+//! writes the resulting tree as Newick and as an ALife-standard phylogeny table.
+
+use crate::genotype::Genotype;
+use crate::tasks::TaskSet;
+use crate::world::World;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Tasks performed by each genotype: those of its dead organisms plus its living ones
+pub fn genotype_tasks(world: &World) -> HashMap<u64, TaskSet> {
+    let mut tasks: HashMap<u64, TaskSet> = world
+        .genotypes()
+        .iter()
+        .map(|genotype| (genotype.id, genotype.tasks))
+        .collect();
+    for org in world.grid.iter().flatten() {
+        if let Some(id) = org.genotype_id {
+            *tasks.entry(id).or_default() |= org.tasks_completed;
+        }
+    }
+    tasks
+}
+
+/// Names of the tasks in a task set, separated by `;` (empty if none)
+pub fn task_names(tasks: TaskSet) -> String {
+    let names: Vec<&str> = tasks.iter().map(|task| task.name()).collect();
+    names.join(";")
+}
+
+/// Root genotypes and the children of every genotype, in id order
+fn tree(world: &World) -> (Vec<&Genotype>, BTreeMap<u64, Vec<&Genotype>>) {
+    let mut roots = Vec::new();
+    let mut children: BTreeMap<u64, Vec<&Genotype>> = BTreeMap::new();
+    for genotype in world.genotypes().iter() {
+        match genotype.parent_id {
+            Some(parent) => children.entry(parent).or_default().push(genotype),
+            None => roots.push(genotype),
+        }
+    }
+    (roots, children)
+}
+
+/// Format the phylogeny as Newick, one tree per injected ancestor
+/// Nodes are labelled with genotype ids; branch lengths are updates between births
+pub fn newick(world: &World) -> String {
+    enum Step<'a> {
+        Enter(&'a Genotype),
+        Close(&'a Genotype),
+        Comma,
+    }
+
+    let (roots, children) = tree(world);
+    let mut out = String::new();
+    for root in roots {
+        // Iterative, since lineages can be far deeper than the call stack
+        let mut stack = vec![Step::Enter(root)];
+        while let Some(step) = stack.pop() {
+            let genotype = match step {
+                Step::Comma => {
+                    out.push(',');
+                    continue;
+                }
+                Step::Enter(genotype) => match children.get(&genotype.id) {
+                    Some(kids) => {
+                        out.push('(');
+                        stack.push(Step::Close(genotype));
+                        for (i, kid) in kids.iter().rev().enumerate() {
+                            if i > 0 {
+                                stack.push(Step::Comma);
+                            }
+                            stack.push(Step::Enter(kid));
+                        }
+                        continue;
+                    }
+                    None => genotype,
+                },
+                Step::Close(genotype) => {
+                    out.push(')');
+                    genotype
+                }
+            };
+
+            let _ = write!(out, "{}", genotype.id);
+            if let Some(parent) = genotype.parent_id.and_then(|id| world.genotypes().get(id)) {
+                let _ = write!(out, ":{}", genotype.update_born - parent.update_born);
+            }
+        }
+        out.push_str(";\n");
+    }
+    out
+}
+
+/// Format the phylogeny as an ALife-standard CSV table
+pub fn alife_csv(world: &World) -> String {
+    let tasks = genotype_tasks(world);
+    let mut out = String::from(
+        "id,ancestor_list,origin_time,destruction_time,num_orgs,total_orgs,max_abundance,depth,tasks,sequence\n",
+    );
+    for genotype in world.genotypes().iter() {
+        let _ = writeln!(
+            out,
+            "{},[{}],{},{},{},{},{},{},{},{}",
+            genotype.id,
+            genotype
+                .parent_id
+                .map_or("NONE".to_string(), |id| id.to_string()),
+            genotype.update_born,
+            genotype
+                .update_extinct
+                .map_or(String::new(), |update| update.to_string()),
+            genotype.abundance,
+            genotype.total_organisms,
+            genotype.max_abundance,
+            genotype.depth,
+            task_names(tasks[&genotype.id]),
+            genotype.genome_string()
+        );
+    }
+    out
+}
+
+/// Write the phylogeny as Newick
+pub fn save_newick(world: &World, path: impl AsRef<Path>) -> Result<(), String> {
+    let path = path.as_ref();
+    std::fs::write(path, newick(world))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Write the phylogeny as an ALife-standard CSV table
+pub fn save_csv(world: &World, path: impl AsRef<Path>) -> Result<(), String> {
+    let path = path.as_ref();
+    std::fs::write(path, alife_csv(world))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::parse_genome;
+    use crate::organism::Organism;
+    use crate::tasks::Task;
+
+    /// Inject an organism at `update` and return its genotype id
+    fn inject(world: &mut World, genome: &str, cell: usize, update: u64) -> u64 {
+        world.total_updates = update;
+        world.inject_organism(Organism::new(parse_genome(genome).unwrap()), cell, 0);
+        world.grid[cell].as_ref().unwrap().genotype_id.unwrap()
+    }
+
+    #[test]
+    fn test_newick_of_injected_roots() {
+        let mut world = World::with_dimensions_and_seed(10, 10, 1);
+        assert_eq!(newick(&world), "");
+        let a = inject(&mut world, "abc", 0, 0);
+        let b = inject(&mut world, "abd", 1, 5);
+        assert_eq!(newick(&world), format!("{};\n{};\n", a, b));
+    }
+
+    #[test]
+    fn test_phylogeny_of_evolving_population() {
+        let mut world = World::with_dimensions_and_seed(20, 20, 6);
+        world.copy_mutation_rate = 0.02;
+        world.inject_ancestor_with_tasks();
+        for _ in 0..300 {
+            world.update();
+        }
+
+        let tree = newick(&world);
+        assert_eq!(tree.lines().count(), 1);
+        assert!(tree.ends_with(")1;\n"));
+        assert_eq!(tree.matches('(').count(), tree.matches(')').count());
+        for genotype in world.genotypes().iter() {
+            assert!(tree.contains(&genotype.id.to_string()));
+        }
+
+        let csv = alife_csv(&world);
+        let rows: Vec<&str> = csv.lines().skip(1).collect();
+        assert_eq!(rows.len(), world.genotypes().len());
+        assert!(rows[0].starts_with("1,[NONE],0,"));
+        for (row, genotype) in rows.iter().zip(world.genotypes().iter()) {
+            let fields: Vec<&str> = row.split(',').collect();
+            assert_eq!(fields.len(), 10);
+            match genotype.parent_id {
+                Some(parent) => assert_eq!(fields[1], format!("[{}]", parent)),
+                None => assert_eq!(fields[1], "[NONE]"),
+            }
+            assert_eq!(fields[3].is_empty(), !genotype.is_extinct());
+        }
+    }
+
+    #[test]
+    fn test_genotype_tasks_include_dead_organisms() {
+        let mut world = World::with_dimensions_and_seed(10, 10, 1);
+        let mut org = Organism::ancestor();
+        org.complete_task(Task::Equ.index() as u8);
+        world.inject_organism(org, 0, 0);
+        let mut org = Organism::ancestor();
+        org.complete_task(Task::Not.index() as u8);
+        world.inject_organism(org, 1, 0);
+        let id = world.grid[0].as_ref().unwrap().genotype_id.unwrap();
+
+        world.kill_organism(0);
+        assert_eq!(task_names(genotype_tasks(&world)[&id]), "NOT;EQU");
+        assert_eq!(task_names(TaskSet::default()), "");
+    }
+}
@@ -1,34 +1,257 @@
 //! Task detection and reward system for Avida organisms
 //! Based on the default Logic-9 environment
 
-/// The 9 logic tasks organisms can perform
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use crate::resources::Resource;
+use serde::{Deserialize, Serialize};
+use std::num::Wrapping as W;
+use std::sync::OnceLock;
+
+/// Tasks organisms can perform: the Logic-9 tasks (functions of one or two inputs), the
+/// 68 three-input tasks that make up Logic-77 together with them, and the math tasks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Task {
-    Not = 0,  // NOT - bitwise not
-    Nand = 1, // NAND - bitwise nand
-    And = 2,  // AND - bitwise and
-    Orn = 3,  // ORN - or-not (A or not B)
-    Or = 4,   // OR - bitwise or
-    Andn = 5, // ANDN - and-not (A and not B)
-    Nor = 6,  // NOR - bitwise nor
-    Xor = 7,  // XOR - bitwise xor
-    Equ = 8,  // EQU - equivalence (not xor)
+    Not,  // NOT - bitwise not
+    Nand, // NAND - bitwise nand
+    And,  // AND - bitwise and
+    Orn,  // ORN - or-not (A or not B)
+    Or,   // OR - bitwise or
+    Andn, // ANDN - and-not (A and not B)
+    Nor,  // NOR - bitwise nor
+    Xor,  // XOR - bitwise xor
+    Equ,  // EQU - equivalence (not xor)
+    /// LOGIC_3AA..LOGIC_3CP: a three-input function, by index into `LOGIC_3`
+    Logic3(u8),
+    /// An arithmetic function of the inputs, by index into `MATH`
+    Math(u8),
+}
+
+/// The three-input tasks: every boolean function that depends on all three inputs, grouped
+/// into classes equal up to the order of the inputs
+/// Each class is named after its position and given by its smallest truth table, whose bit
+/// `k` is the output when input `i` (0 = most recent) has the value of bit `i` of `k`.
+const LOGIC_3: [(&str, u8); 68] = [
+    ("LOGIC_3AA", 0x01),
+    ("LOGIC_3AB", 0x02),
+    ("LOGIC_3AC", 0x06),
+    ("LOGIC_3AD", 0x07),
+    ("LOGIC_3AE", 0x08),
+    ("LOGIC_3AF", 0x09),
+    ("LOGIC_3AG", 0x0b),
+    ("LOGIC_3AH", 0x0e),
+    ("LOGIC_3AI", 0x16),
+    ("LOGIC_3AJ", 0x17),
+    ("LOGIC_3AK", 0x18),
+    ("LOGIC_3AL", 0x19),
+    ("LOGIC_3AM", 0x1a),
+    ("LOGIC_3AN", 0x1b),
+    ("LOGIC_3AO", 0x1e),
+    ("LOGIC_3AP", 0x1f),
+    ("LOGIC_3AQ", 0x28),
+    ("LOGIC_3AR", 0x29),
+    ("LOGIC_3AS", 0x2a),
+    ("LOGIC_3AT", 0x2b),
+    ("LOGIC_3AU", 0x2c),
+    ("LOGIC_3AV", 0x2d),
+    ("LOGIC_3AW", 0x2e),
+    ("LOGIC_3AX", 0x2f),
+    ("LOGIC_3AY", 0x3d),
+    ("LOGIC_3AZ", 0x3e),
+    ("LOGIC_3BA", 0x68),
+    ("LOGIC_3BB", 0x69),
+    ("LOGIC_3BC", 0x6a),
+    ("LOGIC_3BD", 0x6b),
+    ("LOGIC_3BE", 0x6e),
+    ("LOGIC_3BF", 0x6f),
+    ("LOGIC_3BG", 0x7e),
+    ("LOGIC_3BH", 0x7f),
+    ("LOGIC_3BI", 0x80),
+    ("LOGIC_3BJ", 0x81),
+    ("LOGIC_3BK", 0x82),
+    ("LOGIC_3BL", 0x83),
+    ("LOGIC_3BM", 0x86),
+    ("LOGIC_3BN", 0x87),
+    ("LOGIC_3BO", 0x89),
+    ("LOGIC_3BP", 0x8a),
+    ("LOGIC_3BQ", 0x8b),
+    ("LOGIC_3BR", 0x8e),
+    ("LOGIC_3BS", 0x8f),
+    ("LOGIC_3BT", 0x96),
+    ("LOGIC_3BU", 0x97),
+    ("LOGIC_3BV", 0x98),
+    ("LOGIC_3BW", 0x9a),
+    ("LOGIC_3BX", 0x9b),
+    ("LOGIC_3BY", 0x9e),
+    ("LOGIC_3BZ", 0x9f),
+    ("LOGIC_3CA", 0xa8),
+    ("LOGIC_3CB", 0xa9),
+    ("LOGIC_3CC", 0xab),
+    ("LOGIC_3CD", 0xac),
+    ("LOGIC_3CE", 0xad),
+    ("LOGIC_3CF", 0xae),
+    ("LOGIC_3CG", 0xbc),
+    ("LOGIC_3CH", 0xbd),
+    ("LOGIC_3CI", 0xbe),
+    ("LOGIC_3CJ", 0xbf),
+    ("LOGIC_3CK", 0xe8),
+    ("LOGIC_3CL", 0xe9),
+    ("LOGIC_3CM", 0xea),
+    ("LOGIC_3CN", 0xeb),
+    ("LOGIC_3CO", 0xef),
+    ("LOGIC_3CP", 0xfe),
+];
+
+/// Truth tables of the three most recent inputs (0 = most recent)
+const INPUT_TABLES: [u8; 3] = [0xaa, 0xcc, 0xf0];
+
+/// Arithmetic function of up to three inputs `x`, `y` and `z` (None where undefined)
+type MathFn = fn(i32, i32, i32) -> Option<i32>;
+
+/// The math tasks: name, number of inputs and function
+/// The functions wrap around like the CPU's arithmetic; divisions, square roots and
+/// logarithms round toward zero. The `MATH_*` families follow Avida's definitions.
+const MATH: [(&str, usize, MathFn); 61] = [
+    ("ECHO", 1, |x, _, _| Some(x)),
+    ("ADD", 2, |x, y, _| Some((W(x) + W(y)).0)),
+    ("ADD3", 3, |x, y, z| Some((W(x) + W(y) + W(z)).0)),
+    ("SUB", 2, |x, y, _| Some((W(x) - W(y)).0)),
+    ("MUL2", 1, |x, _, _| Some((W(2) * W(x)).0)),
+    ("MUL3", 1, |x, _, _| Some((W(3) * W(x)).0)),
+    ("SQUARE", 1, |x, _, _| Some((W(x) * W(x)).0)),
+    ("CUBE", 1, |x, _, _| Some((W(x) * W(x) * W(x)).0)),
+    ("MAX", 2, |x, y, _| Some(x.max(y))),
+    ("MIN", 2, |x, y, _| Some(x.min(y))),
+    // One input
+    ("MATH_1AA", 1, |x, _, _| Some((W(2) * W(x)).0)),
+    ("MATH_1AB", 1, |x, _, _| div((W(2) * W(x)).0, 3)),
+    ("MATH_1AC", 1, |x, _, _| div((W(5) * W(x)).0, 4)),
+    ("MATH_1AD", 1, |x, _, _| Some((W(x) * W(x)).0)),
+    ("MATH_1AE", 1, |x, _, _| Some((W(x) * W(x) * W(x)).0)),
+    ("MATH_1AF", 1, |x, _, _| sqrt(x)),
+    ("MATH_1AG", 1, |x, _, _| log(x as f64)),
+    ("MATH_1AH", 1, |x, _, _| {
+        Some((W(x) * W(x) + W(x) * W(x) * W(x)).0)
+    }),
+    ("MATH_1AI", 1, |x, _, _| Some((W(x) * W(x) + W(sqrt(x)?)).0)),
+    ("MATH_1AJ", 1, |x, _, _| Some(x.wrapping_abs())),
+    ("MATH_1AK", 1, |x, _, _| Some((W(x) - W(5)).0)),
+    ("MATH_1AL", 1, |x, _, _| Some((-W(x)).0)),
+    ("MATH_1AM", 1, |x, _, _| Some((W(5) * W(x)).0)),
+    ("MATH_1AN", 1, |x, _, _| div(x, 4)),
+    ("MATH_1AO", 1, |x, _, _| Some((W(x) - W(6)).0)),
+    ("MATH_1AP", 1, |x, _, _| Some((W(x) - W(7)).0)),
+    // Two inputs
+    ("MATH_2AA", 2, |x, y, _| sqrt((W(x) + W(y)).0)),
+    ("MATH_2AB", 2, |x, y, _| {
+        Some(((W(x) + W(y)) * (W(x) + W(y))).0)
+    }),
+    ("MATH_2AC", 2, |x, y, _| x.checked_rem(y)),
+    ("MATH_2AD", 2, |x, y, _| {
+        Some((W(div((W(3) * W(x)).0, 2)?) + W(div((W(5) * W(y)).0, 4)?)).0)
+    }),
+    ("MATH_2AE", 2, |x, y, _| {
+        Some((W((W(x) - W(5)).0.wrapping_abs()) + W((W(y) - W(6)).0.wrapping_abs())).0)
+    }),
+    ("MATH_2AF", 2, |x, y, _| {
+        Some((W(x) * W(y) - W(div(x, y)?)).0)
+    }),
+    ("MATH_2AG", 2, |x, y, _| {
+        Some(((W(x) - W(y)) * (W(x) - W(y))).0)
+    }),
+    ("MATH_2AH", 2, |x, y, _| Some((W(x) * W(x) + W(y) * W(y)).0)),
+    ("MATH_2AI", 2, |x, y, _| {
+        Some((W(x) * W(x) + W(y) * W(y) * W(y)).0)
+    }),
+    ("MATH_2AJ", 2, |x, y, _| {
+        div((W(sqrt(x)?) + W(y)).0, (W(x) - W(7)).0)
+    }),
+    ("MATH_2AK", 2, |x, y, _| log(x as f64 / y as f64)),
+    ("MATH_2AL", 2, |x, y, _| div(log(x as f64)?, y)),
+    ("MATH_2AM", 2, |x, y, _| div(x, log(y as f64)?)),
+    ("MATH_2AN", 2, |x, y, _| Some((W(x) + W(y)).0)),
+    ("MATH_2AO", 2, |x, y, _| Some((W(x) - W(y)).0)),
+    ("MATH_2AP", 2, |x, y, _| div(x, y)),
+    ("MATH_2AQ", 2, |x, y, _| Some((W(x) * W(y)).0)),
+    ("MATH_2AR", 2, |x, y, _| Some((W(sqrt(x)?) + W(sqrt(y)?)).0)),
+    ("MATH_2AS", 2, |x, y, _| Some((W(x) + W(2) * W(y)).0)),
+    ("MATH_2AT", 2, |x, y, _| Some((W(x) + W(3) * W(y)).0)),
+    ("MATH_2AU", 2, |x, y, _| Some((W(2) * W(x) + W(3) * W(y)).0)),
+    ("MATH_2AV", 2, |x, y, _| Some((W(x) * W(y) * W(y)).0)),
+    // Three inputs
+    ("MATH_3AA", 3, |x, y, z| {
+        Some((W(x) * W(x) + W(y) * W(y) + W(z) * W(z)).0)
+    }),
+    ("MATH_3AB", 3, |x, y, z| {
+        Some((W(sqrt(x)?) + W(sqrt(y)?) + W(sqrt(z)?)).0)
+    }),
+    ("MATH_3AC", 3, |x, y, z| {
+        Some((W(x) + W(2) * W(y) + W(3) * W(z)).0)
+    }),
+    ("MATH_3AD", 3, |x, y, z| {
+        Some((W(x) * W(y) * W(y) + W(z) * W(z) * W(z)).0)
+    }),
+    ("MATH_3AE", 3, |x, y, z| {
+        Some((W(x.checked_rem(y)?) * W(z)).0)
+    }),
+    ("MATH_3AF", 3, |x, y, z| {
+        Some(((W(x) + W(y)) * (W(x) + W(y)) + W(sqrt((W(y) + W(z)).0)?)).0)
+    }),
+    ("MATH_3AG", 3, |x, y, z| {
+        (W(x) * W(y)).0.checked_rem((W(y) * W(z)).0)
+    }),
+    ("MATH_3AH", 3, |x, y, z| Some((W(x) + W(y) + W(z)).0)),
+    ("MATH_3AI", 3, |x, y, z| Some((-W(x) - W(y) - W(z)).0)),
+    ("MATH_3AJ", 3, |x, y, z| {
+        let (a, b, c) = (W(x) - W(y), W(y) - W(z), W(z) - W(x));
+        Some((a * a + b * b + c * c).0)
+    }),
+    ("MATH_3AK", 3, |x, y, z| {
+        let (a, b, c) = (W(x) + W(y), W(y) + W(z), W(z) + W(x));
+        Some((a * a + b * b + c * c).0)
+    }),
+    ("MATH_3AL", 3, |x, y, z| {
+        let (a, b) = (W(x) - W(y), W(x) - W(z));
+        Some((a * a + b * b).0)
+    }),
+    ("MATH_3AM", 3, |x, y, z| {
+        let (a, b) = (W(x) + W(y), W(y) + W(z));
+        Some((a * a + b * b).0)
+    }),
+];
+
+/// Quotient rounded toward zero (None when dividing by zero)
+fn div(x: i32, y: i32) -> Option<i32> {
+    x.checked_div(y)
+}
+
+/// Square root rounded down (None for negative numbers)
+fn sqrt(x: i32) -> Option<i32> {
+    (x >= 0).then(|| (x as f64).sqrt() as i32)
+}
+
+/// Natural logarithm of the magnitude, rounded toward zero (None for zero)
+fn log(x: f64) -> Option<i32> {
+    (x != 0.0).then(|| x.abs().ln() as i32)
 }
 
 impl Task {
+    /// Number of tasks (the length of `Task::all`)
+    pub const COUNT: usize = 9 + LOGIC_3.len() + MATH.len();
+
     /// Get the merit bonus value for this task
     /// Merit multiplier = 2^value
     pub fn bonus_value(&self) -> f64 {
         match self {
-            Task::Not => 1.0,  // 2^1 = 2x merit
-            Task::Nand => 1.0, // 2^1 = 2x merit
-            Task::And => 2.0,  // 2^2 = 4x merit
-            Task::Orn => 2.0,  // 2^2 = 4x merit
-            Task::Or => 3.0,   // 2^3 = 8x merit
-            Task::Andn => 3.0, // 2^3 = 8x merit
-            Task::Nor => 4.0,  // 2^4 = 16x merit
-            Task::Xor => 4.0,  // 2^4 = 16x merit
-            Task::Equ => 4.0,  // 2^4 = 16x merit
+            Task::Not => 1.0,       // 2^1 = 2x merit
+            Task::Nand => 1.0,      // 2^1 = 2x merit
+            Task::And => 2.0,       // 2^2 = 4x merit
+            Task::Orn => 2.0,       // 2^2 = 4x merit
+            Task::Or => 3.0,        // 2^3 = 8x merit
+            Task::Andn => 3.0,      // 2^3 = 8x merit
+            Task::Nor => 4.0,       // 2^4 = 16x merit
+            Task::Xor => 4.0,       // 2^4 = 16x merit
+            Task::Equ => 4.0,       // 2^4 = 16x merit
+            Task::Logic3(_) => 1.0, // 2^1 = 2x merit
+            Task::Math(_) => 1.0,   // 2^1 = 2x merit
         }
     }
 
@@ -49,11 +272,58 @@ impl Task {
             Task::Nor => "NOR",
             Task::Xor => "XOR",
             Task::Equ => "EQU",
+            Task::Logic3(i) => LOGIC_3[*i as usize].0,
+            Task::Math(i) => MATH[*i as usize].0,
+        }
+    }
+
+    /// Position of the task in `Task::all`
+    pub fn index(&self) -> usize {
+        match self {
+            Task::Logic3(i) => 9 + *i as usize,
+            Task::Math(i) => 9 + LOGIC_3.len() + *i as usize,
+            task => Task::logic9().iter().position(|t| t == task).unwrap_or(0),
+        }
+    }
+
+    /// The task at `index` in `Task::all`
+    pub fn from_index(index: usize) -> Option<Task> {
+        match index {
+            0..9 => Some(Task::logic9()[index]),
+            _ if index < 9 + LOGIC_3.len() => Some(Task::Logic3((index - 9) as u8)),
+            _ if index < Task::COUNT => Some(Task::Math((index - 9 - LOGIC_3.len()) as u8)),
+            _ => None,
+        }
+    }
+
+    /// All tasks in order: Logic-9, then the three-input tasks, then the math tasks
+    pub fn all() -> Vec<Task> {
+        (0..Task::COUNT).filter_map(Task::from_index).collect()
+    }
+
+    /// The Logic-77 tasks: Logic-9, then the three-input tasks
+    pub fn logic77() -> Vec<Task> {
+        (0..9 + LOGIC_3.len())
+            .filter_map(Task::from_index)
+            .collect()
+    }
+
+    /// The math tasks in order
+    pub fn math() -> Vec<Task> {
+        (0..MATH.len() as u8).map(Task::Math).collect()
+    }
+
+    /// Output of a math task on inputs `x`, `y` and `z` (None for other tasks and where the
+    /// function is undefined)
+    pub fn math_output(&self, x: i32, y: i32, z: i32) -> Option<i32> {
+        match self {
+            Task::Math(i) => (MATH[*i as usize].2)(x, y, z),
+            _ => None,
         }
     }
 
-    /// All tasks in order
-    pub fn all() -> [Task; 9] {
+    /// The Logic-9 tasks in order
+    pub fn logic9() -> [Task; 9] {
         [
             Task::Not,
             Task::Nand,
@@ -66,10 +336,193 @@ impl Task {
             Task::Equ,
         ]
     }
+
+    /// Look up a task by name (case-insensitive, e.g. "NOT", "equ" or "logic_3AA")
+    pub fn from_name(name: &str) -> Option<Task> {
+        Task::all()
+            .into_iter()
+            .find(|task| task.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The task computing the function with truth table `table` over the three most recent
+    /// inputs (see `LOGIC_3`), if any
+    /// Logic-9 tasks may use any of the inputs, in either order. The tasks of all 256 tables
+    /// are found once, on first use.
+    pub fn from_truth_table(table: u8) -> Option<Task> {
+        static TASKS: OnceLock<[Option<Task>; 256]> = OnceLock::new();
+        TASKS.get_or_init(|| std::array::from_fn(|table| find_task(table as u8)))[table as usize]
+    }
+}
+
+/// Search the tasks for the one computing truth table `table` (see `Task::from_truth_table`)
+fn find_task(table: u8) -> Option<Task> {
+    if (0..3).all(|input| depends_on(table, input)) {
+        let smallest = permutations(table).min()?;
+        let i = LOGIC_3.iter().position(|&(_, t)| t == smallest)?;
+        return Some(Task::Logic3(i as u8));
+    }
+    Task::logic9().into_iter().find(|task| {
+        INPUT_TABLES.iter().any(|&a| {
+            INPUT_TABLES
+                .iter()
+                .any(|&b| a != b && logic9_output(*task, a, b) == Some(table))
+        })
+    })
+}
+
+/// Output of a Logic-9 task on inputs `a` and `b` (None for other tasks)
+fn logic9_output(task: Task, a: u8, b: u8) -> Option<u8> {
+    Some(match task {
+        Task::Not => !b,
+        Task::Nand => !(a & b),
+        Task::And => a & b,
+        Task::Orn => a | !b,
+        Task::Or => a | b,
+        Task::Andn => a & !b,
+        Task::Nor => !(a | b),
+        Task::Xor => a ^ b,
+        Task::Equ => !(a ^ b),
+        Task::Logic3(_) | Task::Math(_) => return None,
+    })
+}
+
+/// Whether the function with truth table `table` depends on `input`
+fn depends_on(table: u8, input: usize) -> bool {
+    (0..8).any(|k| (table >> k) & 1 != (table >> (k ^ (1 << input))) & 1)
+}
+
+/// Every order of the three inputs
+const INPUT_ORDERS: [[usize; 3]; 6] = [
+    [0, 1, 2],
+    [0, 2, 1],
+    [1, 0, 2],
+    [1, 2, 0],
+    [2, 0, 1],
+    [2, 1, 0],
+];
+
+/// Truth tables of the function with `table` under every order of the three inputs
+fn permutations(table: u8) -> impl Iterator<Item = u8> {
+    INPUT_ORDERS.into_iter().map(move |order| {
+        (0..8).fold(0, |permuted, k| {
+            let from = (0..3).fold(0, |from, i| from | ((k >> i) & 1) << order[i]);
+            permuted | ((table >> from) & 1) << k
+        })
+    })
+}
+
+/// Number of 64-bit words in a `TaskSet`
+const TASK_SET_WORDS: usize = Task::COUNT.div_ceil(64);
+
+/// A set of tasks, stored as bits indexed by `Task::index`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "TaskSetWords", into = "TaskSetWords")]
+pub struct TaskSet([u64; TASK_SET_WORDS]);
+
+/// Serialized form of a `TaskSet`: the bit words, or a single Logic-9 bit mask as written
+/// by earlier versions
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum TaskSetWords {
+    Mask(u64),
+    Words(Vec<u64>),
+}
+
+impl From<TaskSetWords> for TaskSet {
+    fn from(words: TaskSetWords) -> Self {
+        let mut set = TaskSet::default();
+        let words = match words {
+            TaskSetWords::Mask(mask) => vec![mask],
+            TaskSetWords::Words(words) => words,
+        };
+        for (word, bits) in set.0.iter_mut().zip(words) {
+            *word = bits;
+        }
+        set
+    }
+}
+
+impl From<TaskSet> for TaskSetWords {
+    fn from(set: TaskSet) -> Self {
+        TaskSetWords::Words(set.0.to_vec())
+    }
+}
+
+impl TaskSet {
+    /// Every task
+    pub fn all() -> Self {
+        Task::all().into_iter().collect()
+    }
+
+    pub fn contains(&self, task: Task) -> bool {
+        let i = task.index();
+        self.0[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub fn insert(&mut self, task: Task) {
+        let i = task.index();
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    /// Number of tasks in the set
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// The tasks in the set, in `Task::all` order
+    pub fn iter(&self) -> impl Iterator<Item = Task> + '_ {
+        (0..Task::COUNT)
+            .filter_map(Task::from_index)
+            .filter(|&task| self.contains(task))
+    }
+}
+
+impl FromIterator<Task> for TaskSet {
+    fn from_iter<I: IntoIterator<Item = Task>>(tasks: I) -> Self {
+        let mut set = TaskSet::default();
+        for task in tasks {
+            set.insert(task);
+        }
+        set
+    }
+}
+
+/// Union
+impl std::ops::BitOr for TaskSet {
+    type Output = TaskSet;
+
+    fn bitor(mut self, other: TaskSet) -> TaskSet {
+        self |= other;
+        self
+    }
+}
+
+impl std::ops::BitOrAssign for TaskSet {
+    fn bitor_assign(&mut self, other: TaskSet) {
+        for (word, bits) in self.0.iter_mut().zip(other.0) {
+            *word |= bits;
+        }
+    }
+}
+
+/// Difference: the tasks in `self` but not in `other`
+impl std::ops::Sub for TaskSet {
+    type Output = TaskSet;
+
+    fn sub(mut self, other: TaskSet) -> TaskSet {
+        for (word, bits) in self.0.iter_mut().zip(other.0) {
+            *word &= !bits;
+        }
+        self
+    }
 }
 
 /// Task detector - checks if an output matches a task given inputs
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskDetector {
     /// Previous inputs for task checking
     inputs: Vec<i32>,
@@ -89,37 +542,76 @@ impl TaskDetector {
         }
     }
 
-    /// Check if an output value corresponds to any task
-    /// Returns the task if detected, along with the inputs used
-    pub fn check_output(&self, output: i32) -> Option<(Task, Vec<i32>)> {
-        // Need at least 2 inputs for most tasks
-        if self.inputs.len() < 2 {
+    /// Check if an output value corresponds to one of `tasks`, computed bitwise or
+    /// arithmetically from any combination of the last three inputs
+    /// Returns the task if detected (the first in `Task::all` order if the output matches
+    /// several), along with the inputs used
+    pub fn check_output(&self, output: i32, tasks: &TaskSet) -> Option<(Task, Vec<i32>)> {
+        if self.inputs.is_empty() {
             return None;
         }
 
-        let a = self.inputs[self.inputs.len() - 2];
-        let b = self.inputs[self.inputs.len() - 1];
-
-        // Check each task
-        for task in Task::all() {
-            let expected = match task {
-                Task::Not => !b,
-                Task::Nand => !(a & b),
-                Task::And => a & b,
-                Task::Orn => a | !b,
-                Task::Or => a | b,
-                Task::Andn => a & !b,
-                Task::Nor => !(a | b),
-                Task::Xor => a ^ b,
-                Task::Equ => !(a ^ b),
-            };
+        [
+            self.logic_task(output, tasks),
+            self.math_task(output, tasks),
+        ]
+        .into_iter()
+        .flatten()
+        .min_by_key(Task::index)
+        .map(|task| (task, self.inputs.clone()))
+    }
 
-            if expected == output {
-                return Some((task, vec![a, b]));
+    /// The first of `tasks` whose truth table agrees with every bit of `output`
+    fn logic_task(&self, output: i32, tasks: &TaskSet) -> Option<Task> {
+        // Bits of the output where the inputs (most recent first, missing ones 0) have
+        // each combination of values give the rows of the output's truth table
+        let recent: Vec<i32> = self.inputs.iter().rev().copied().collect();
+        let mut known = 0u8;
+        let mut observed = 0u8;
+        for row in 0..8 {
+            let bits = (0..3).fold(-1, |bits, i| {
+                let input = recent.get(i).copied().unwrap_or(0);
+                bits & if row & (1 << i) != 0 { input } else { !input }
+            });
+            if bits == 0 {
+                continue;
+            }
+            known |= 1 << row;
+            if output & bits == bits {
+                observed |= 1 << row;
+            } else if output & bits != 0 {
+                return None;
             }
         }
 
-        None
+        // Every function of the available inputs agreeing with the observed rows
+        (0..=255u8)
+            .filter(|&table| table & known == observed)
+            .filter(|&table| (recent.len()..3).all(|input| !depends_on(table, input)))
+            .filter_map(Task::from_truth_table)
+            .filter(|&task| tasks.contains(task))
+            .min_by_key(Task::index)
+    }
+
+    /// The first math task in `tasks` that gives `output` for some order of some of the
+    /// last three inputs
+    /// An output of 0 never counts, since many of the functions round to it.
+    fn math_task(&self, output: i32, tasks: &TaskSet) -> Option<Task> {
+        if output == 0 {
+            return None;
+        }
+        let recent: Vec<i32> = self.inputs.iter().rev().copied().collect();
+        let input = |i: usize| recent.get(i).copied().unwrap_or(0);
+        tasks.iter().find(|task| {
+            let Task::Math(i) = task else { return false };
+            let arity = MATH[*i as usize].1;
+            // The first `arity` inputs of every order give every choice of inputs
+            INPUT_ORDERS.iter().any(|order| {
+                order[..arity].iter().all(|&i| i < recent.len())
+                    && task.math_output(input(order[0]), input(order[1]), input(order[2]))
+                        == Some(output)
+            })
+        })
     }
 
     /// Clear input history
@@ -134,29 +626,320 @@ impl Default for TaskDetector {
     }
 }
 
+/// How a reaction's bonus changes merit (Avida process `type`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RewardType {
+    /// merit + bonus
+    Add,
+    /// merit × bonus
+    Mult,
+    /// merit × 2^bonus
+    Pow,
+}
+
+/// Condition on an organism's earlier tasks for a reaction to be rewarded (Avida requisite)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Requisite {
+    /// Reactions whose tasks the organism must already have performed
+    pub reactions: Vec<String>,
+    /// Reactions whose tasks the organism must not have performed
+    pub noreactions: Vec<String>,
+    /// Times the organism must already have performed the reaction's task
+    pub min_count: u32,
+    /// Times the organism may already have performed the reaction's task
+    /// (1 = rewarded only the first time)
+    pub max_count: u32,
+}
+
+impl Default for Requisite {
+    /// No condition: always satisfied
+    fn default() -> Self {
+        Self {
+            reactions: Vec::new(),
+            noreactions: Vec::new(),
+            min_count: 0,
+            max_count: u32::MAX,
+        }
+    }
+}
+
+/// Reward for performing a task (Avida REACTION with a single process)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reaction {
+    /// Name other reactions' requisites refer to
+    #[serde(default)]
+    pub name: String,
+    pub task: Task,
+    /// Name of the resource consumed (None = unlimited: `max` is consumed every time)
+    pub resource: Option<String>,
+    /// Bonus per unit of resource consumed
+    pub value: f64,
+    pub reward_type: RewardType,
+    /// Fraction of the available resource consumed
+    pub frac: f64,
+    /// Most resource consumed at once
+    pub max: f64,
+    /// Least resource that must be consumed for any reward
+    pub min: f64,
+    /// The reaction is rewarded if any requisite is satisfied (always if there are none)
+    #[serde(default)]
+    pub requisites: Vec<Requisite>,
+}
+
+impl Reaction {
+    /// A reaction named after its task with Avida's process defaults: unlimited resource,
+    /// additive bonus, frac 1, max 1, min 0 and no requisites
+    pub fn new(task: Task, value: f64) -> Self {
+        Self {
+            name: task.name().to_string(),
+            task,
+            resource: None,
+            value,
+            reward_type: RewardType::Add,
+            frac: 1.0,
+            max: 1.0,
+            min: 0.0,
+            requisites: Vec::new(),
+        }
+    }
+}
+
 /// Task environment configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SavedEnvironment", into = "SavedEnvironment")]
 pub struct TaskEnvironment {
-    /// Whether each task is enabled
-    pub task_enabled: [bool; 9],
+    /// Whether each task is enabled, by `Task::index` (missing entries are enabled)
+    pub task_enabled: Vec<bool>,
+
+    /// Maximum times each task can be rewarded (0 = unlimited, 1 = once per organism),
+    /// on top of the reactions' requisites, by `Task::index` (missing entries are unlimited)
+    pub max_task_count: Vec<u32>,
+
+    /// Rewards for tasks; a task without a reaction earns nothing
+    reactions: Vec<Reaction>,
 
-    /// Maximum times each task can be rewarded (0 = unlimited, 1 = once per organism)
-    pub max_task_count: [u32; 9],
+    /// Tasks rewarded by at least one reaction, kept in step with `reactions`
+    tasks: TaskSet,
+
+    /// Global resources consumed by reactions
+    pub resources: Vec<Resource>,
+}
+
+/// Serialized form of a task environment (the rewarded tasks are rebuilt on load)
+#[derive(Serialize, Deserialize)]
+struct SavedEnvironment {
+    task_enabled: Vec<bool>,
+    max_task_count: Vec<u32>,
+    #[serde(default = "logic9_reactions")]
+    reactions: Vec<Reaction>,
+    #[serde(default)]
+    resources: Vec<Resource>,
+}
+
+impl From<SavedEnvironment> for TaskEnvironment {
+    fn from(saved: SavedEnvironment) -> Self {
+        let mut env = TaskEnvironment::with_reactions(saved.reactions);
+        env.task_enabled = saved.task_enabled;
+        env.max_task_count = saved.max_task_count;
+        env.resources = saved.resources;
+        env
+    }
+}
+
+impl From<TaskEnvironment> for SavedEnvironment {
+    fn from(env: TaskEnvironment) -> Self {
+        Self {
+            task_enabled: env.task_enabled,
+            max_task_count: env.max_task_count,
+            reactions: env.reactions,
+            resources: env.resources,
+        }
+    }
+}
+
+/// Unlimited Logic-9 reactions: each task multiplies merit by `Task::merit_multiplier`,
+/// the first time an organism performs it
+fn logic9_reactions() -> Vec<Reaction> {
+    unlimited_reactions(Task::logic9())
+}
+
+/// Unlimited reactions multiplying merit by each task's `Task::merit_multiplier`, the first
+/// time an organism performs it
+fn unlimited_reactions(tasks: impl IntoIterator<Item = Task>) -> Vec<Reaction> {
+    tasks
+        .into_iter()
+        .map(|task| Reaction {
+            reward_type: RewardType::Pow,
+            requisites: vec![Requisite {
+                max_count: 1,
+                ..Requisite::default()
+            }],
+            ..Reaction::new(task, task.bonus_value())
+        })
+        .collect()
 }
 
 impl TaskEnvironment {
+    /// An environment rewarding tasks only through `reactions`: every task enabled, no
+    /// caps beyond the reactions' requisites and no resources
+    pub fn with_reactions(reactions: Vec<Reaction>) -> Self {
+        let mut env = Self {
+            task_enabled: vec![true; Task::COUNT],
+            max_task_count: vec![0; Task::COUNT],
+            reactions: Vec::new(),
+            tasks: TaskSet::default(),
+            resources: Vec::new(),
+        };
+        env.set_reactions(reactions);
+        env
+    }
+
     /// Create the default Logic-9 environment
+    /// Each reaction's requisite rewards its task once.
     pub fn default_logic9() -> Self {
-        Self {
-            task_enabled: [true; 9], // All tasks enabled
-            max_task_count: [1; 9],  // Each task can only be rewarded once
+        Self::with_reactions(logic9_reactions())
+    }
+
+    /// Create the Logic-77 environment: the Logic-9 tasks and every three-input task, each
+    /// rewarded the first time it is performed
+    pub fn default_logic77() -> Self {
+        Self::with_reactions(unlimited_reactions(Task::logic77()))
+    }
+
+    /// Create the math environment: every math task, each rewarded the first time it is
+    /// performed
+    pub fn default_math() -> Self {
+        Self::with_reactions(unlimited_reactions(Task::math()))
+    }
+
+    /// Rewards for tasks; a task without a reaction earns nothing
+    pub fn reactions(&self) -> &[Reaction] {
+        &self.reactions
+    }
+
+    /// Replace the reactions
+    pub fn set_reactions(&mut self, reactions: Vec<Reaction>) {
+        self.update_reactions(|current| *current = reactions);
+    }
+
+    /// Change the reactions in place, keeping the rewarded tasks up to date
+    pub fn update_reactions(&mut self, update: impl FnOnce(&mut Vec<Reaction>)) {
+        update(&mut self.reactions);
+        self.tasks = self
+            .reactions
+            .iter()
+            .map(|reaction| reaction.task)
+            .collect();
+    }
+
+    /// The tasks rewarded by at least one reaction
+    pub fn tasks(&self) -> TaskSet {
+        self.tasks
+    }
+
+    /// Perform the reactions of `task` for an organism that has performed each task
+    /// `task_counts` times before, consuming their resources (spatial resources from the
+    /// cell at `position`)
+    /// Returns the new merit (unchanged if the task is disabled or cannot be rewarded again)
+    pub fn reward(
+        &mut self,
+        task: Task,
+        mut merit: f64,
+        position: Option<(usize, usize)>,
+        task_counts: &[u32],
+    ) -> f64 {
+        let count_of = |task: Task| task_counts.get(task.index()).copied().unwrap_or(0);
+        if !self.can_reward_task(task, count_of(task)) {
+            return merit;
+        }
+        let performed = |name: &String| {
+            self.reactions
+                .iter()
+                .find(|reaction| &reaction.name == name)
+                .is_some_and(|reaction| count_of(reaction.task) > 0)
+        };
+        let satisfied = |requisite: &Requisite| {
+            let count = count_of(task);
+            requisite.reactions.iter().all(performed)
+                && !requisite.noreactions.iter().any(performed)
+                && count >= requisite.min_count
+                && count < requisite.max_count
+        };
+        let rewarded: Vec<usize> = (0..self.reactions.len())
+            .filter(|&i| {
+                let reaction = &self.reactions[i];
+                reaction.task == task
+                    && (reaction.requisites.is_empty() || reaction.requisites.iter().any(satisfied))
+            })
+            .collect();
+
+        for i in rewarded {
+            let reaction = &self.reactions[i];
+            let consumed = match &reaction.resource {
+                None if reaction.max >= reaction.min => reaction.max,
+                None => 0.0,
+                Some(name) => self
+                    .resources
+                    .iter_mut()
+                    .find(|resource| &resource.name == name)
+                    .map_or(0.0, |resource| {
+                        resource.consume(reaction.frac, reaction.max, reaction.min, position)
+                    }),
+            };
+            if consumed <= 0.0 {
+                continue;
+            }
+            let bonus = reaction.value * consumed;
+            merit = match reaction.reward_type {
+                RewardType::Add => merit + bonus,
+                RewardType::Mult => merit * bonus,
+                RewardType::Pow => merit * 2.0_f64.powf(bonus),
+            };
+        }
+        merit
+    }
+
+    /// Look up a resource by name
+    pub fn resource(&self, name: &str) -> Option<&Resource> {
+        self.resources.iter().find(|resource| resource.name == name)
+    }
+
+    /// Apply one update of inflow and outflow to every resource
+    pub fn update_resources(&mut self) {
+        for resource in &mut self.resources {
+            resource.update();
+        }
+    }
+
+    /// Set every resource back to its initial amount
+    pub fn reset_resources(&mut self) {
+        for resource in &mut self.resources {
+            resource.reset();
+        }
+    }
+
+    /// Fit spatial resources to a world of `width` × `height` and reset every resource
+    pub fn resize_resources(&mut self, width: usize, height: usize) {
+        for resource in &mut self.resources {
+            resource.resize(width, height);
         }
     }
 
     /// Check if a task is enabled and can still be rewarded
     pub fn can_reward_task(&self, task: Task, current_count: u32) -> bool {
-        let idx = task as usize;
-        self.task_enabled[idx]
-            && (self.max_task_count[idx] == 0 || current_count < self.max_task_count[idx])
+        let idx = task.index();
+        let max_count = self.max_task_count.get(idx).copied().unwrap_or(0);
+        self.task_enabled.get(idx).copied().unwrap_or(true)
+            && (max_count == 0 || current_count < max_count)
+    }
+
+    /// Enable or disable rewards for a task
+    pub fn set_task_enabled(&mut self, task: Task, enabled: bool) {
+        let idx = task.index();
+        if self.task_enabled.len() <= idx {
+            self.task_enabled.resize(Task::COUNT, true);
+        }
+        self.task_enabled[idx] = enabled;
     }
 }
 
@@ -164,6 +947,147 @@ impl TaskEnvironment {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_task_from_name() {
+        for task in Task::all() {
+            assert_eq!(Task::from_name(task.name()), Some(task));
+        }
+        assert_eq!(Task::from_name("equ"), Some(Task::Equ));
+        assert_eq!(Task::from_name("XNOR"), None);
+    }
+
+    #[test]
+    fn test_logic77_tasks() {
+        let logic77 = Task::logic77();
+        assert_eq!(logic77.len(), 77);
+        assert_eq!(logic77[..9], Task::logic9());
+        assert_eq!(Task::all()[..77], logic77);
+        for (i, task) in Task::all().iter().enumerate() {
+            assert_eq!(task.index(), i);
+            assert_eq!(Task::from_index(i), Some(*task));
+        }
+        assert_eq!(Task::from_index(Task::COUNT), None);
+        assert_eq!(Task::from_name("logic_3AA"), Some(Task::Logic3(0)));
+        assert_eq!(Task::Logic3(67).name(), "LOGIC_3CP");
+
+        // Every truth table over three inputs is one task, except the constants and the
+        // inputs themselves
+        let mut tasks = TaskSet::default();
+        for table in 0..=255u8 {
+            match Task::from_truth_table(table) {
+                Some(task) => tasks.insert(task),
+                None => assert!([0x00, 0xff, 0xaa, 0xcc, 0xf0].contains(&table)),
+            }
+        }
+        assert_eq!(tasks, logic77.into_iter().collect());
+        assert_eq!(Task::from_truth_table(0x80).unwrap().name(), "LOGIC_3BI");
+        assert_eq!(Task::from_truth_table(0x55), Some(Task::Not));
+        assert_eq!(Task::from_truth_table(0xf0 & !0xaa), Some(Task::Andn));
+    }
+
+    #[test]
+    fn test_three_input_tasks() {
+        let (a, b, c) = (0x0f13_149f, 0x3308_e53e, 0x5562_41eb);
+        let mut detector = TaskDetector::new();
+        detector.add_input(a);
+        detector.add_input(b);
+        detector.add_input(c);
+        let detect = |output, tasks: &TaskSet| detector.check_output(output, tasks).map(|r| r.0);
+        let all = TaskSet::all();
+
+        // The order of the inputs does not matter
+        let and3 = Task::from_truth_table(0x80);
+        assert_eq!(detect(a & b & c, &all), and3);
+        assert_eq!(detect(c & a & b, &all), and3);
+        let majority = detect((a & b) | (b & c) | (a & c), &all).unwrap();
+        assert!(matches!(majority, Task::Logic3(_)));
+        assert_ne!(detect(a & b & !c, &all), Some(majority));
+        assert_eq!(detect(!a & b & c, &all), detect(a & !b & c, &all));
+
+        // Logic-9 tasks use any pair of the three inputs
+        assert_eq!(detect(!(a & c), &all), Some(Task::Nand));
+        assert_eq!(detect(!a, &all), Some(Task::Not));
+        assert_eq!(detect(c | !a, &all), Some(Task::Orn));
+        assert_eq!(detect(a ^ b, &all), Some(Task::Xor));
+
+        // Only the given tasks are detected
+        let logic9 = TaskEnvironment::default_logic9().tasks();
+        assert_eq!(detect(a & b & c, &logic9), None);
+        assert_eq!(detect(a & c, &logic9), Some(Task::And));
+        let logic77 = TaskEnvironment::default_logic77().tasks();
+        assert_eq!(detect(a, &logic77), None);
+    }
+
+    #[test]
+    fn test_math_tasks() {
+        let (x, y, z) = (9, 4, 100); // z is the most recent input
+        let mut detector = TaskDetector::new();
+        detector.add_input(x);
+        detector.add_input(y);
+        detector.add_input(z);
+        let math = TaskEnvironment::default_math().tasks();
+        let detect = |output| detector.check_output(output, &math).map(|r| r.0.name());
+
+        assert_eq!(detect(100), Some("ECHO"));
+        assert_eq!(detect(13), Some("ADD"));
+        assert_eq!(detect(113), Some("ADD3"));
+        // Either order of the inputs
+        assert_eq!(detect(-96), Some("SUB"));
+        assert_eq!(detect(96), Some("SUB"));
+        assert_eq!(detect(300), Some("MUL3"));
+        assert_eq!(detect(729), Some("CUBE"));
+        assert_eq!(detect(11), Some("MATH_1AC"));
+        assert_eq!(detect(3), Some("MATH_1AF"));
+        assert_eq!(detect(4 + 2 * 9 + 3 * 100), Some("MATH_3AC"));
+        assert_eq!(detect(77), None);
+        assert_eq!(detect(0), None);
+
+        // Only the given tasks are detected, along with the logic tasks
+        let sub: TaskSet = [Task::Xor, Task::from_name("math_2ao").unwrap()]
+            .into_iter()
+            .collect();
+        let detect = |output| detector.check_output(output, &sub).map(|r| r.0.name());
+        assert_eq!(detect(-96), Some("MATH_2AO"));
+        assert_eq!(detect(13), Some("XOR"));
+        assert_eq!(detect(113), None);
+
+        // Undefined results and wrapping arithmetic
+        let task = |name| Task::from_name(name).unwrap();
+        assert_eq!(task("math_2ap").math_output(1, 0, 0), None);
+        assert_eq!(task("math_1af").math_output(-4, 0, 0), None);
+        assert_eq!(task("add").math_output(i32::MAX, 1, 0), Some(i32::MIN));
+        assert_eq!(Task::Xor.math_output(1, 2, 3), None);
+    }
+
+    #[test]
+    fn test_task_set() {
+        let mut set = TaskSet::default();
+        assert!(set.is_empty());
+        set.insert(Task::Nand);
+        set.insert(Task::Logic3(60));
+        assert!(set.contains(Task::Logic3(60)) && !set.contains(Task::Not));
+        assert_eq!(set.len(), 2);
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![Task::Nand, Task::Logic3(60)]
+        );
+        let other: TaskSet = [Task::Nand, Task::Equ].into_iter().collect();
+        assert_eq!(
+            (set - other).iter().collect::<Vec<_>>(),
+            vec![Task::Logic3(60)]
+        );
+        assert_eq!((set | other).len(), 3);
+
+        let json = serde_json::to_string(&set).unwrap();
+        assert_eq!(serde_json::from_str::<TaskSet>(&json).unwrap(), set);
+        // Earlier versions stored a Logic-9 bit mask
+        let legacy: TaskSet = serde_json::from_str("5").unwrap();
+        assert_eq!(
+            legacy.iter().collect::<Vec<_>>(),
+            vec![Task::Not, Task::And]
+        );
+    }
+
     #[test]
     fn test_all_task_merit_multipliers() {
         assert_eq!(Task::Not.merit_multiplier(), 2.0);
@@ -184,7 +1108,7 @@ mod tests {
         detector.add_input(10);
 
         let output = !10;
-        let result = detector.check_output(output);
+        let result = detector.check_output(output, &TaskSet::all());
         assert!(result.is_some());
         let (task, inputs) = result.unwrap();
         assert_eq!(task, Task::Not);
@@ -198,7 +1122,7 @@ mod tests {
         detector.add_input(0b1010);
 
         let output = !(0b1100 & 0b1010);
-        let result = detector.check_output(output);
+        let result = detector.check_output(output, &TaskSet::all());
         assert!(result.is_some());
         let (task, _) = result.unwrap();
         assert_eq!(task, Task::Nand);
@@ -211,7 +1135,7 @@ mod tests {
         detector.add_input(0b1010);
 
         let output = 0b1100 & 0b1010; // 0b1000
-        let result = detector.check_output(output);
+        let result = detector.check_output(output, &TaskSet::all());
         assert!(result.is_some());
         let (task, _) = result.unwrap();
         assert_eq!(task, Task::And);
@@ -224,7 +1148,7 @@ mod tests {
         detector.add_input(0b1010);
 
         let output = 0b1100 | !0b1010;
-        let result = detector.check_output(output);
+        let result = detector.check_output(output, &TaskSet::all());
         assert!(result.is_some());
         let (task, _) = result.unwrap();
         assert_eq!(task, Task::Orn);
@@ -237,7 +1161,7 @@ mod tests {
         detector.add_input(0b1010);
 
         let output = 0b1100 | 0b1010;
-        let result = detector.check_output(output);
+        let result = detector.check_output(output, &TaskSet::all());
         assert!(result.is_some());
         let (task, _) = result.unwrap();
         assert_eq!(task, Task::Or);
@@ -250,7 +1174,7 @@ mod tests {
         detector.add_input(0b1010);
 
         let output = 0b1100 & !0b1010;
-        let result = detector.check_output(output);
+        let result = detector.check_output(output, &TaskSet::all());
         assert!(result.is_some());
         let (task, _) = result.unwrap();
         assert_eq!(task, Task::Andn);
@@ -263,7 +1187,7 @@ mod tests {
         detector.add_input(0b1010);
 
         let output = !(0b1100 | 0b1010);
-        let result = detector.check_output(output);
+        let result = detector.check_output(output, &TaskSet::all());
         assert!(result.is_some());
         let (task, _) = result.unwrap();
         assert_eq!(task, Task::Nor);
@@ -276,7 +1200,7 @@ mod tests {
         detector.add_input(0b1010);
 
         let output = 0b1100 ^ 0b1010;
-        let result = detector.check_output(output);
+        let result = detector.check_output(output, &TaskSet::all());
         assert!(result.is_some());
         let (task, _) = result.unwrap();
         assert_eq!(task, Task::Xor);
@@ -289,7 +1213,7 @@ mod tests {
         detector.add_input(0b1010);
 
         let output = !(0b1100 ^ 0b1010);
-        let result = detector.check_output(output);
+        let result = detector.check_output(output, &TaskSet::all());
         assert!(result.is_some());
         let (task, _) = result.unwrap();
         assert_eq!(task, Task::Equ);
@@ -298,12 +1222,12 @@ mod tests {
     #[test]
     fn test_insufficient_inputs() {
         let detector = TaskDetector::new();
-        let result = detector.check_output(42);
+        let result = detector.check_output(42, &TaskSet::all());
         assert!(result.is_none());
 
         let mut detector2 = TaskDetector::new();
         detector2.add_input(5);
-        let result2 = detector2.check_output(42);
+        let result2 = detector2.check_output(42, &TaskSet::all());
         assert!(result2.is_none());
     }
 
@@ -340,10 +1264,23 @@ mod tests {
 
         // Output doesn't match any task
         let output = 42;
-        let result = detector.check_output(output);
+        let logic9 = TaskEnvironment::default_logic9().tasks();
+        let result = detector.check_output(output, &logic9);
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_math_output_detected_when_enabled() {
+        let mut detector = TaskDetector::new();
+        detector.add_input(0b1100);
+        detector.add_input(0b1010);
+
+        // 42 = 12 + 3 * 10
+        let math = TaskEnvironment::default_math().tasks();
+        let result = detector.check_output(42, &math);
+        assert_eq!(result.unwrap().0, Task::from_name("MATH_2AT").unwrap());
+    }
+
     #[test]
     fn test_task_environment_default() {
         let env = TaskEnvironment::default_logic9();
@@ -371,6 +1308,126 @@ mod tests {
         assert!(env.task_enabled[1]); // Other tasks still enabled
     }
 
+    #[test]
+    fn test_default_rewards_match_merit_multipliers() {
+        let mut env = TaskEnvironment::default_logic9();
+        for task in Task::logic9() {
+            assert_eq!(
+                env.reward(task, 1.0, None, &[0; 9]),
+                task.merit_multiplier()
+            );
+        }
+        env.task_enabled[Task::Xor.index()] = false;
+        assert_eq!(env.reward(Task::Xor, 3.0, None, &[0; 9]), 3.0);
+    }
+
+    #[test]
+    fn test_logic77_environment() {
+        let mut env = TaskEnvironment::default_logic77();
+        assert_eq!(env.tasks(), Task::logic77().into_iter().collect());
+        let task = Task::Logic3(5);
+        assert_eq!(env.reward(task, 1.0, None, &[0; 9]), 2.0);
+        let mut counts = vec![0; Task::COUNT];
+        counts[task.index()] = 1;
+        assert_eq!(env.reward(task, 1.0, None, &counts), 1.0);
+        assert_eq!(env.reward(Task::Equ, 1.0, None, &counts), 16.0);
+
+        env.set_task_enabled(task, false);
+        assert_eq!(env.reward(task, 1.0, None, &[0; 9]), 1.0);
+
+        // The rewarded tasks follow the reactions, and are rebuilt when loaded
+        env.update_reactions(|reactions| reactions.retain(|r| r.task != Task::Equ));
+        assert_eq!(env.tasks().len(), Task::logic77().len() - 1);
+        assert!(!env.tasks().contains(Task::Equ));
+        let json = serde_json::to_string(&env).unwrap();
+        assert_eq!(serde_json::from_str::<TaskEnvironment>(&json).unwrap(), env);
+    }
+
+    #[test]
+    fn test_requisites() {
+        let mut env = TaskEnvironment::default_logic9();
+        let mut counts = [0; 9];
+        counts[Task::Not.index()] = 1;
+        // The default reactions reward each task only the first time
+        assert_eq!(env.reward(Task::Not, 1.0, None, &counts), 1.0);
+
+        // AND needs NOT first and is blocked by XOR
+        env.set_reactions(vec![
+            Reaction::new(Task::Not, 1.0),
+            Reaction::new(Task::Xor, 1.0),
+            Reaction {
+                requisites: vec![Requisite {
+                    reactions: vec!["NOT".to_string()],
+                    noreactions: vec!["XOR".to_string()],
+                    ..Requisite::default()
+                }],
+                ..Reaction::new(Task::And, 1.0)
+            },
+        ]);
+        assert_eq!(env.reward(Task::And, 1.0, None, &[0; 9]), 1.0);
+        assert_eq!(env.reward(Task::And, 1.0, None, &counts), 2.0);
+        // Without a max_count the task is rewarded every time
+        assert_eq!(env.reward(Task::Not, 1.0, None, &counts), 2.0);
+        counts[Task::Xor.index()] = 1;
+        assert_eq!(env.reward(Task::And, 1.0, None, &counts), 1.0);
+
+        // Alternative requisites: either the second performance or after XOR
+        env.update_reactions(|reactions| {
+            reactions[2].requisites = vec![
+                Requisite {
+                    min_count: 1,
+                    max_count: 2,
+                    ..Requisite::default()
+                },
+                Requisite {
+                    reactions: vec!["XOR".to_string()],
+                    ..Requisite::default()
+                },
+            ]
+        });
+        let mut counts = [0; 9];
+        assert_eq!(env.reward(Task::And, 1.0, None, &counts), 1.0);
+        counts[Task::And.index()] = 1;
+        assert_eq!(env.reward(Task::And, 1.0, None, &counts), 2.0);
+        counts[Task::And.index()] = 2;
+        assert_eq!(env.reward(Task::And, 1.0, None, &counts), 1.0);
+        counts[Task::Xor.index()] = 1;
+        assert_eq!(env.reward(Task::And, 1.0, None, &counts), 2.0);
+    }
+
+    #[test]
+    fn test_reward_scales_with_resource_consumed() {
+        let mut env = TaskEnvironment::default_logic9();
+        env.resources
+            .push(Resource::new("glucose", 100.0, 0.0, 0.0));
+        env.set_reactions(vec![Reaction {
+            resource: Some("glucose".to_string()),
+            frac: 0.1,
+            max: 100.0,
+            ..Reaction::new(Task::Not, 0.5)
+        }]);
+
+        // 10 consumed: merit + 0.5 × 10
+        assert_eq!(env.reward(Task::Not, 1.0, None, &[0; 9]), 6.0);
+        assert_eq!(env.resource("glucose").unwrap().amount, 90.0);
+        // The depleted pool gives less: 9 consumed
+        assert_eq!(env.reward(Task::Not, 1.0, None, &[0; 9]), 5.5);
+        // Tasks without a reaction earn nothing
+        assert_eq!(env.reward(Task::Nand, 1.0, None, &[0; 9]), 1.0);
+
+        env.update_reactions(|reactions| {
+            reactions[0].reward_type = RewardType::Pow;
+            reactions[0].value = 0.25;
+        });
+        env.resources[0].amount = 40.0;
+        assert_eq!(env.reward(Task::Not, 1.0, None, &[0; 9]), 2.0);
+
+        env.reset_resources();
+        assert_eq!(env.resource("glucose").unwrap().amount, 100.0);
+        env.update_resources();
+        assert_eq!(env.resource("glucose").unwrap().amount, 100.0);
+    }
+
     #[test]
     fn test_multiple_tasks_same_inputs() {
         let mut detector = TaskDetector::new();
@@ -384,13 +1441,13 @@ mod tests {
         let or_result = a | b;
         let xor_result = a ^ b;
 
-        let task1 = detector.check_output(and_result);
+        let task1 = detector.check_output(and_result, &TaskSet::all());
         assert_eq!(task1.unwrap().0, Task::And);
 
-        let task2 = detector.check_output(or_result);
+        let task2 = detector.check_output(or_result, &TaskSet::all());
         assert_eq!(task2.unwrap().0, Task::Or);
 
-        let task3 = detector.check_output(xor_result);
+        let task3 = detector.check_output(xor_result, &TaskSet::all());
         assert_eq!(task3.unwrap().0, Task::Xor);
     }
 
@@ -400,8 +1457,8 @@ mod tests {
         detector.add_input(0);
         detector.add_input(0);
 
-        let output = 0 & 0;
-        let result = detector.check_output(output);
+        let output = 0;
+        let result = detector.check_output(output, &TaskSet::all());
         assert!(result.is_some());
         let (task, _) = result.unwrap();
         assert_eq!(task, Task::And);
@@ -414,7 +1471,7 @@ mod tests {
         detector.add_input(10);
 
         let output = !10;
-        let result = detector.check_output(output);
+        let result = detector.check_output(output, &TaskSet::all());
         assert!(result.is_some());
     }
 }
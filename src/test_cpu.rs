@@ -0,0 +1,251 @@
+//! Test CPU
+//! Runs a single genome alone, outside any world, until its first successful divide and
+//! reports its phenotype: gestation time, copied and executed size, merit, tasks and whether
+//! it breeds true. Mutations are disabled and task inputs come from a fixed seed, so the
+//! same genome always gets the same report.
+
+use crate::execute::execute_instruction;
+use crate::instruction::{genome_to_string, Instruction};
+use crate::instset::InstSet;
+use crate::organism::Organism;
+use crate::tasks::{Task, TaskDetector, TaskEnvironment, TaskSet};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::fmt::Write as _;
+
+/// Outcome of running a genome to its first divide
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Viability {
+    /// Divided and produced an exact copy of its own genome
+    SelfReplicating,
+    /// Divided, but the offspring genome differs from the parent's
+    NonReplicating,
+    /// Did not divide within the step limit
+    Sterile,
+}
+
+impl Viability {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Viability::SelfReplicating => "self-replicating",
+            Viability::NonReplicating => "non-replicating",
+            Viability::Sterile => "sterile",
+        }
+    }
+}
+
+/// What a genome does when run alone
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phenotype {
+    pub genome: Vec<Instruction>,
+    pub viability: Viability,
+    /// CPU cycles used up to the divide (or until the step limit if sterile),
+    /// counting each instruction at its cost and initial cost in the instruction set
+    pub gestation_time: u64,
+    /// Instructions copied into the offspring
+    pub copied_size: usize,
+    /// Distinct genome positions that were executed
+    pub executed_size: usize,
+    /// Merit at divide: base merit 1.0 with the reward of every task performed
+    pub merit: f64,
+    /// Merit divided by gestation time (0 if sterile)
+    pub fitness: f64,
+    /// Tasks performed before the divide
+    pub tasks: TaskSet,
+    /// Genome of the offspring (None if sterile)
+    pub offspring: Option<Vec<Instruction>>,
+}
+
+impl Phenotype {
+    pub fn is_viable(&self) -> bool {
+        self.viability != Viability::Sterile
+    }
+
+    pub fn has_task(&self, task: Task) -> bool {
+        self.tasks.contains(task)
+    }
+
+    /// Format the phenotype as `key value` lines
+    pub fn format(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "viability {}", self.viability.name());
+        let _ = writeln!(out, "length {}", self.genome.len());
+        let _ = writeln!(out, "gestation_time {}", self.gestation_time);
+        let _ = writeln!(out, "copied_size {}", self.copied_size);
+        let _ = writeln!(out, "executed_size {}", self.executed_size);
+        let _ = writeln!(out, "merit {:.4}", self.merit);
+        let _ = writeln!(out, "fitness {:.6}", self.fitness);
+        let tasks: Vec<&str> = self.tasks.iter().map(|task| task.name()).collect();
+        let _ = writeln!(
+            out,
+            "tasks {}",
+            if tasks.is_empty() {
+                "(none)".to_string()
+            } else {
+                tasks.join(",")
+            }
+        );
+        let _ = writeln!(out, "sequence {}", genome_to_string(&self.genome));
+        if let Some(offspring) = &self.offspring {
+            let _ = writeln!(out, "offspring {}", genome_to_string(offspring));
+        }
+        out
+    }
+}
+
+/// Sandbox for evaluating genomes in isolation
+#[derive(Debug, Clone)]
+pub struct TestCpu {
+    /// Step limit as a multiple of genome length (Avida's TEST_CPU_TIME_MOD)
+    pub time_mod: u64,
+    /// Seed for the task inputs
+    pub seed: u64,
+    /// Instruction set providing execution costs
+    pub inst_set: InstSet,
+    /// Environment rewarding tasks; every evaluation starts from its resource amounts
+    pub environment: TaskEnvironment,
+}
+
+impl Default for TestCpu {
+    fn default() -> Self {
+        Self {
+            time_mod: 20,
+            seed: 0,
+            inst_set: InstSet::default(),
+            environment: TaskEnvironment::default_logic9(),
+        }
+    }
+}
+
+impl TestCpu {
+    /// Maximum CPU cycles used by a genome of `length`
+    pub fn step_limit(&self, length: usize) -> u64 {
+        self.time_mod * length as u64
+    }
+
+    /// Run a genome until its first successful divide or the step limit
+    pub fn evaluate(&self, genome: &[Instruction]) -> Phenotype {
+        let mut org = Organism::new(genome.to_vec());
+        let mut detector = TaskDetector::new();
+        let mut environment = self.environment.clone();
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut executed = vec![false; genome.len()];
+
+        let mut offspring = None;
+        let mut steps = 0;
+        while steps < self.step_limit(genome.len()) {
+            if let Some(seen) = executed.get_mut(org.cpu.ip) {
+                *seen = true;
+            }
+            let copied_size = org.child_copy_progress;
+            steps += 1;
+            if !org.pay_instruction_cost(&self.inst_set) {
+                continue;
+            }
+            let (should_divide, _) = execute_instruction(
+                &mut org,
+                &mut detector,
+                &mut environment,
+                0.0,
+                &self.inst_set,
+                &mut rng,
+            );
+            if should_divide {
+                if let Some(child) = org.divide(0.0, 0.0, &self.inst_set, &mut rng) {
+                    offspring = Some((child, copied_size));
+                    break;
+                }
+            }
+        }
+
+        let (viability, offspring, copied_size) = match offspring {
+            Some((child, copied_size)) => (
+                if child.genome == genome {
+                    Viability::SelfReplicating
+                } else {
+                    Viability::NonReplicating
+                },
+                Some(child.genome),
+                copied_size,
+            ),
+            None => (Viability::Sterile, None, org.child_copy_progress),
+        };
+
+        Phenotype {
+            genome: genome.to_vec(),
+            viability,
+            gestation_time: steps,
+            copied_size,
+            executed_size: executed.iter().filter(|&&seen| seen).count(),
+            merit: org.merit,
+            fitness: if offspring.is_some() {
+                org.merit / steps as f64
+            } else {
+                0.0
+            },
+            tasks: org.tasks_completed,
+            offspring,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::parse_genome;
+
+    #[test]
+    fn test_ancestor_self_replicates() {
+        let ancestor = Organism::ancestor().genome;
+        let phenotype = TestCpu::default().evaluate(&ancestor);
+        assert_eq!(phenotype.viability, Viability::SelfReplicating);
+        assert_eq!(phenotype.offspring.as_ref(), Some(&ancestor));
+        assert_eq!(phenotype.copied_size, ancestor.len());
+        assert!(phenotype.gestation_time > ancestor.len() as u64);
+        assert!(phenotype.executed_size > 0 && phenotype.executed_size <= ancestor.len());
+        assert!(phenotype.tasks.is_empty());
+        assert_eq!(phenotype.merit, 1.0);
+        assert_eq!(phenotype.fitness, 1.0 / phenotype.gestation_time as f64);
+
+        // Deterministic
+        assert_eq!(TestCpu::default().evaluate(&ancestor), phenotype);
+    }
+
+    #[test]
+    fn test_task_profile() {
+        // Input twice, copy BX to CX through the stack, NAND it with itself and output NOT
+        let genome = parse_genome("qqgifpqrutyabsvacccccccccccccbc").unwrap();
+        let phenotype = TestCpu::default().evaluate(&genome);
+        assert_eq!(phenotype.viability, Viability::SelfReplicating);
+        assert!(phenotype.has_task(Task::Not));
+        assert_eq!(phenotype.merit, Task::Not.merit_multiplier());
+        assert!(phenotype.format().contains("\ntasks NOT\n"));
+    }
+
+    #[test]
+    fn test_sterile_genome() {
+        let genome = parse_genome("cccccccccccccccccccc").unwrap();
+        let cpu = TestCpu::default();
+        let phenotype = cpu.evaluate(&genome);
+        assert_eq!(phenotype.viability, Viability::Sterile);
+        assert!(!phenotype.is_viable());
+        assert_eq!(phenotype.gestation_time, cpu.step_limit(genome.len()));
+        assert_eq!(phenotype.executed_size, genome.len());
+        assert_eq!(phenotype.fitness, 0.0);
+        assert!(phenotype.offspring.is_none());
+        assert!(phenotype.format().contains("viability sterile\n"));
+    }
+
+    #[test]
+    fn test_non_replicating_genome() {
+        // Copies itself, but the offspring is padded with nop-C up to the minimum genome size
+        let genome = parse_genome("rutyabsvacbc").unwrap();
+        let phenotype = TestCpu::default().evaluate(&genome);
+        assert_eq!(phenotype.viability, Viability::NonReplicating);
+        assert_eq!(phenotype.copied_size, genome.len());
+        assert_eq!(
+            phenotype.offspring.map(|offspring| offspring.len()),
+            Some(crate::organism::MINIMUM_GENOME_SIZE)
+        );
+    }
+}
@@ -1,4 +1,6 @@
-use crate::tasks::Task;
+use crate::knockout::{self, KnockoutReport};
+use crate::tasks::TaskEnvironment;
+use crate::test_cpu::TestCpu;
 use crate::world::World;
 use egui::{Color32, Rect, Vec2};
 
@@ -11,6 +13,12 @@ pub struct AvidaApp {
     pub show_inspector: bool,
     pub color_mode: ColorMode,
     pub active_tab: AppTab,
+    /// World size applied on the next reset
+    pub world_size: (usize, usize),
+    /// Knockout analysis of the last inspected genome
+    pub knockout: Option<KnockoutReport>,
+    /// Spatial resource drawn as a heatmap over the grid (index into the resources)
+    pub resource_overlay: Option<usize>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -33,15 +41,44 @@ impl AvidaApp {
     pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
         let mut world = World::new();
         world.inject_ancestor();
+        let world_size = world.dimensions();
 
         Self {
             world,
+            world_size,
             paused: false,
             updates_per_frame: 1,
             selected_cell: None,
             show_inspector: true,
             color_mode: ColorMode::Tasks,
             active_tab: AppTab::Grid,
+            knockout: None,
+            resource_overlay: None,
+        }
+    }
+
+    /// Clear the world, rebuilding it first if the requested size changed
+    fn reset_world(&mut self) {
+        if self.world.dimensions() != self.world_size {
+            let (width, height) = self.world_size;
+            let mut world = World::with_dimensions(width, height);
+            world.inst_set = self.world.inst_set.clone();
+            world.copy_mutation_rate = self.world.copy_mutation_rate;
+            world.insertion_rate = self.world.insertion_rate;
+            world.deletion_rate = self.world.deletion_rate;
+            world.death_method = self.world.death_method;
+            world.age_limit = self.world.age_limit;
+            world.prefer_empty = self.world.prefer_empty;
+            world.recombination = self.world.recombination;
+            world.parasites = self.world.parasites.clone();
+            world.task_env = self.world.task_env.clone();
+            for resource in &mut world.task_env.resources {
+                resource.resize(width, height);
+            }
+            self.world = world;
+            self.selected_cell = None;
+        } else {
+            self.world.clear();
         }
     }
 
@@ -53,6 +90,26 @@ impl AvidaApp {
         ui.label(format!("Total Births: {}", self.world.total_births));
         ui.label(format!("Total Deaths: {}", self.world.total_deaths));
         ui.label(format!("Updates: {}", self.world.total_updates));
+        ui.label(format!("Seed: {}", self.world.seed()));
+        if let Some(waiting) = self.world.birth_chamber().waiting() {
+            ui.label(format!(
+                "Birth chamber: offspring of length {} waiting for a mate",
+                waiting.offspring.genome_size()
+            ));
+        }
+        if self.world.parasites.max_threads > 1 {
+            ui.label(format!(
+                "Parasites: {} ({} infections)",
+                self.world.parasite_count(),
+                self.world.total_infections
+            ));
+        }
+        for resource in &self.world.task_env.resources {
+            ui.label(format!(
+                "Resource {}: {:.2}",
+                resource.name, resource.amount
+            ));
+        }
 
         ui.add_space(10.0);
         ui.label(format!(
@@ -62,6 +119,20 @@ impl AvidaApp {
         ui.label(format!("Avg Merit: {:.2}", self.world.average_merit()));
         ui.label(format!("Avg Fitness: {:.4}", self.world.average_fitness()));
 
+        ui.add_space(10.0);
+        ui.label(format!(
+            "Genotypes: {}",
+            self.world.genotypes().living_count()
+        ));
+        if let Some(dominant) = self.world.genotypes().dominant() {
+            ui.label(format!(
+                "Dominant: #{} ({} alive, length {})",
+                dominant.id,
+                dominant.abundance,
+                dominant.genome.len()
+            ));
+        }
+
         ui.add_space(10.0);
         ui.separator();
         ui.heading("Mutation Rates");
@@ -97,9 +168,24 @@ impl AvidaApp {
         ui.separator();
         ui.heading("Tasks Completed");
 
+        ui.horizontal(|ui| {
+            ui.label("Environment:");
+            let presets = [
+                ("Logic-9", TaskEnvironment::default_logic9()),
+                ("Logic-77", TaskEnvironment::default_logic77()),
+                ("Math", TaskEnvironment::default_math()),
+            ];
+            for (name, environment) in presets {
+                let selected = self.world.task_env.tasks() == environment.tasks();
+                if ui.selectable_label(selected, name).clicked() && !selected {
+                    self.world.task_env = environment;
+                }
+            }
+        });
+
         let task_stats = self.world.task_statistics();
-        for task in Task::all() {
-            let count = task_stats[task as usize];
+        for task in self.world.task_env.tasks().iter() {
+            let count = task_stats[task.index()];
             ui.label(format!(
                 "{}: {} ({}x merit)",
                 task.name(),
@@ -118,6 +204,23 @@ impl AvidaApp {
         ui.radio_value(&mut self.color_mode, ColorMode::GenomeSize, "Genome Size");
         ui.radio_value(&mut self.color_mode, ColorMode::Tasks, "Tasks Completed");
 
+        if self
+            .world
+            .task_env
+            .resources
+            .iter()
+            .any(|resource| resource.spatial.is_some())
+        {
+            ui.add_space(10.0);
+            ui.label("Resource Overlay:");
+            ui.radio_value(&mut self.resource_overlay, None, "None");
+            for (i, resource) in self.world.task_env.resources.iter().enumerate() {
+                if resource.spatial.is_some() {
+                    ui.radio_value(&mut self.resource_overlay, Some(i), &resource.name);
+                }
+            }
+        }
+
         ui.add_space(10.0);
         ui.checkbox(&mut self.show_inspector, "Show Inspector");
     }
@@ -129,6 +232,11 @@ impl AvidaApp {
         if let Some((x, y)) = self.selected_cell {
             if let Some(org) = self.world.get_organism(x, y) {
                 ui.label(format!("Position: ({}, {})", x, y));
+                for resource in &self.world.task_env.resources {
+                    if let Some(spatial) = &resource.spatial {
+                        ui.label(format!("{} here: {:.2}", resource.name, spatial.get(x, y)));
+                    }
+                }
                 ui.label(format!("Generation: {}", org.generation));
                 ui.label(format!("Age: {}", org.age()));
                 ui.label(format!("Merit: {:.2}", org.merit));
@@ -154,6 +262,20 @@ impl AvidaApp {
                     ui.label(format!("{:3}: {}", i * 50, chunk_str));
                 }
 
+                ui.add_space(10.0);
+                ui.label("Knockouts:");
+                ui.separator();
+                let genome = org.genome.clone();
+                if ui.button("Run knockout analysis").clicked() {
+                    self.knockout = Some(knockout::knockout(&genome, &TestCpu::default()));
+                }
+                match &self.knockout {
+                    Some(report) if report.base.genome == genome => knockout_ui(ui, report),
+                    _ => {
+                        ui.label("Knocks out each site in the test CPU");
+                    }
+                }
+
                 ui.add_space(10.0);
                 ui.label("CPU State:");
                 ui.separator();
@@ -164,14 +286,34 @@ impl AvidaApp {
                 ui.label(format!("Read-Head: {}", org.cpu.read_head));
                 ui.label(format!("Write-Head: {}", org.cpu.write_head));
                 ui.label(format!("Flow-Head: {}", org.cpu.flow_head));
+                if org.cpu.cost_remaining > 0 {
+                    let inst = org.current_instruction().map_or("?", |inst| inst.name());
+                    ui.label(format!(
+                        "Paying: {} ({} cycles left)",
+                        inst, org.cpu.cost_remaining
+                    ));
+                }
+
+                if !org.parasites.is_empty() {
+                    ui.add_space(10.0);
+                    ui.label("Parasite Threads:");
+                    ui.separator();
+                    for (i, parasite) in org.parasites.iter().enumerate() {
+                        ui.label(format!(
+                            "#{}: length {}, merit {:.2}, IP {}",
+                            i + 1,
+                            parasite.org.genome_size(),
+                            parasite.org.merit,
+                            parasite.org.cpu.ip
+                        ));
+                    }
+                }
 
                 ui.add_space(10.0);
                 ui.label("Tasks:");
                 ui.separator();
-                for task in Task::all() {
-                    if org.has_completed_task(task as u8) {
-                        ui.label(format!("✓ {}", task.name()));
-                    }
+                for task in org.tasks_completed.iter() {
+                    ui.label(format!("✓ {}", task.name()));
                 }
             } else {
                 ui.label("Empty cell");
@@ -193,6 +335,13 @@ impl AvidaApp {
 
         let (response, painter) = ui.allocate_painter(desired_size, egui::Sense::click());
 
+        let overlay = self
+            .resource_overlay
+            .and_then(|i| self.world.task_env.resources.get(i))
+            .and_then(|resource| resource.spatial.as_ref());
+        let overlay_max = overlay.map_or(0.0, |spatial| spatial.max());
+        let env_task_count = self.world.task_env.tasks().len().max(1) as f32;
+
         if response.clicked() {
             if let Some(pos) = response.interact_pointer_pos() {
                 let click_pos = pos - response.rect.min;
@@ -242,8 +391,8 @@ impl AvidaApp {
                             }
                         }
                         ColorMode::Tasks => {
-                            let task_count = (0..9).filter(|&i| org.has_completed_task(i)).count();
-                            let intensity = (task_count as f32 / 9.0 * 255.0) as u8;
+                            let task_count = org.tasks_completed.len();
+                            let intensity = (task_count as f32 / env_task_count * 255.0) as u8;
                             Color32::from_rgb(0, intensity, 0)
                         }
                     }
@@ -253,6 +402,16 @@ impl AvidaApp {
 
                 painter.rect_filled(rect, 0.0, color);
 
+                // Heatmap of the overlay resource, relative to its densest cell
+                if let Some(spatial) = overlay.filter(|_| overlay_max > 0.0) {
+                    let alpha = (spatial.get(x, y) / overlay_max * 200.0) as u8;
+                    painter.rect_filled(
+                        rect,
+                        0.0,
+                        Color32::from_rgba_unmultiplied(0, 160, 255, alpha),
+                    );
+                }
+
                 if Some((x, y)) == self.selected_cell {
                     painter.rect_stroke(rect, 0.0, (2.0, Color32::WHITE));
                 }
@@ -303,13 +462,18 @@ impl eframe::App for AvidaApp {
 
                 ui.separator();
 
+                ui.label("Size:");
+                ui.add(egui::DragValue::new(&mut self.world_size.0).range(1..=500));
+                ui.label("×");
+                ui.add(egui::DragValue::new(&mut self.world_size.1).range(1..=500));
+
                 if ui.button("Reset").clicked() {
-                    self.world.clear();
+                    self.reset_world();
                     self.world.inject_ancestor();
                 }
 
                 if ui.button("Reset (Tasks)").clicked() {
-                    self.world.clear();
+                    self.reset_world();
                     self.world.inject_ancestor_with_tasks();
                 }
 
@@ -379,3 +543,66 @@ impl eframe::App for AvidaApp {
         }
     }
 }
+
+/// Knockout colors: replication-essential, task-essential, deleterious, neutral, beneficial
+const KNOCKOUT_COLORS: [(Color32, &str); 5] = [
+    (Color32::from_rgb(220, 50, 50), "replication"),
+    (Color32::from_rgb(255, 160, 0), "task"),
+    (Color32::from_rgb(230, 220, 80), "deleterious"),
+    (Color32::GRAY, "neutral"),
+    (Color32::from_rgb(80, 200, 80), "beneficial"),
+];
+
+/// Genome colored by the effect of knocking out each site, with details on hover
+fn knockout_ui(ui: &mut egui::Ui, report: &KnockoutReport) {
+    ui.label(format!(
+        "Unmutated: {} fitness {:.4}",
+        report.base.viability.name(),
+        report.base.fitness
+    ));
+
+    for row in report.sites.chunks(50) {
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            ui.monospace(format!("{:3}: ", row[0].site));
+            for site in row {
+                let relative = site.relative_fitness(&report.base);
+                let (color, _) = if site.replication_essential {
+                    KNOCKOUT_COLORS[0]
+                } else if !site.lost_tasks.is_empty() {
+                    KNOCKOUT_COLORS[1]
+                } else if relative < 1.0 {
+                    KNOCKOUT_COLORS[2]
+                } else if relative == 1.0 {
+                    KNOCKOUT_COLORS[3]
+                } else {
+                    KNOCKOUT_COLORS[4]
+                };
+                let lost: Vec<&str> = site.lost_tasks.iter().map(|task| task.name()).collect();
+                ui.label(
+                    egui::RichText::new(site.original.to_char().to_string())
+                        .monospace()
+                        .color(color),
+                )
+                .on_hover_text(format!(
+                    "site {} ({}): {}, relative fitness {:.3}{}",
+                    site.site,
+                    site.original.name(),
+                    site.viability.name(),
+                    relative,
+                    if lost.is_empty() {
+                        String::new()
+                    } else {
+                        format!(", loses {}", lost.join(", "))
+                    }
+                ));
+            }
+        });
+    }
+
+    ui.horizontal_wrapped(|ui| {
+        for (color, name) in KNOCKOUT_COLORS {
+            ui.label(egui::RichText::new(format!("■ {}", name)).color(color));
+        }
+    });
+}
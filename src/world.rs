@@ -1,26 +1,75 @@
-use crate::execute::execute_instruction;
+use crate::birth_chamber::{BirthChamber, Recombination, WaitingOffspring};
+use crate::events::EventSchedule;
+use crate::execute::{execute_instruction, execute_parasite_instruction};
+use crate::genotype::GenotypeRegistry;
+use crate::instruction::Instruction;
+use crate::instset::InstSet;
 use crate::organism::Organism;
-use crate::tasks::{TaskDetector, TaskEnvironment};
-use rand::rngs::SmallRng;
+use crate::parasite::{self, Parasite, ParasiteSettings};
+use crate::tasks::{Task, TaskDetector, TaskEnvironment};
 use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 #[cfg(not(target_arch = "wasm32"))]
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicU32, Ordering};
 
-const WORLD_WIDTH: usize = 60;
-const WORLD_HEIGHT: usize = 60;
+/// Default world dimensions (Avida WORLD_X / WORLD_Y)
+pub const DEFAULT_WORLD_WIDTH: usize = 60;
+pub const DEFAULT_WORLD_HEIGHT: usize = 60;
+
+/// Number of grid cells summed per partial sum in floating-point statistics
+/// Partial sums are always combined in grid order, so results are identical
+/// regardless of thread count (and between native and wasm builds)
+const STATS_CHUNK_SIZE: usize = 256;
+
+/// Sum `value` over all organisms in a deterministic order
+/// Returns (sum, organism count)
+fn sum_organisms<F>(grid: &[Option<Organism>], value: F) -> (f64, usize)
+where
+    F: Fn(&Organism) -> f64 + Sync,
+{
+    let partial = |chunk: &[Option<Organism>]| {
+        chunk
+            .iter()
+            .flatten()
+            .fold((0.0, 0), |(total, count), org| {
+                (total + value(org), count + 1)
+            })
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let partials: Vec<(f64, usize)> = grid.par_chunks(STATS_CHUNK_SIZE).map(partial).collect();
+    #[cfg(target_arch = "wasm32")]
+    let partials: Vec<(f64, usize)> = grid.chunks(STATS_CHUNK_SIZE).map(partial).collect();
+
+    partials
+        .into_iter()
+        .fold((0.0, 0), |(total, count), (t, c)| (total + t, count + c))
+}
 
 /// World grid with toroidal topology (wraparound edges)
+/// Serializes to the complete simulation state (see `checkpoint`)
+#[derive(Serialize, Deserialize)]
 pub struct World {
     /// Grid of organisms (None = empty cell) - flat array for better cache locality
     pub grid: Vec<Option<Organism>>,
 
+    /// Grid dimensions (cells are indexed row-major: y * width + x)
+    width: usize,
+    height: usize,
+
     /// Task detectors for each organism - flat array
     task_detectors: Vec<Option<TaskDetector>>,
 
     /// Task environment configuration
     pub task_env: TaskEnvironment,
 
+    /// Instruction set: mutation alphabet and per-instruction cycle costs
+    #[serde(default)]
+    pub inst_set: InstSet,
+
     /// Mutation rates
     pub copy_mutation_rate: f64,
     pub insertion_rate: f64,
@@ -34,61 +83,157 @@ pub struct World {
     /// prefer_empty: if true, always prefer empty cells over occupied ones during birth
     pub prefer_empty: bool,
 
+    /// How offspring of divide-sex are recombined
+    #[serde(default)]
+    pub recombination: Recombination,
+
+    /// Offspring of divide-sex waiting for a mate
+    #[serde(default)]
+    birth_chamber: BirthChamber,
+
+    /// Host threads, virulence and task rewards of parasites
+    #[serde(default)]
+    pub parasites: ParasiteSettings,
+
     /// Statistics
     pub total_updates: u64,
     pub total_organisms: u64,
     pub total_births: u64,
     pub total_deaths: u64,
+    /// Parasites that infected a host (by inject or an InjectParasite event)
+    #[serde(default)]
+    pub total_infections: u64,
 
     /// Current population size
     pub population_size: usize,
 
-    /// Fast RNG for better performance (no synchronization overhead like thread_rng)
-    rng: SmallRng,
+    /// Seed of the world RNG (drawn from entropy unless one was given)
+    seed: u64,
+
+    /// The single RNG stream for the whole simulation: scheduling, placement,
+    /// mutations and task inputs all draw from it, so a seed reproduces a run exactly
+    /// ChaCha8 output is platform-independent (unlike SmallRng)
+    rng: ChaCha8Rng,
+
+    /// Scheduled events (events.cfg), run at the start of each update
+    /// Not saved in checkpoints; reload the events file when resuming
+    #[serde(skip)]
+    pub events: EventSchedule,
+
+    /// Genotypes of the living population and their extinct ancestors
+    genotypes: GenotypeRegistry,
 }
 
 impl World {
     pub fn new() -> Self {
-        let grid_size = WORLD_WIDTH * WORLD_HEIGHT;
+        Self::with_dimensions(DEFAULT_WORLD_WIDTH, DEFAULT_WORLD_HEIGHT)
+    }
+
+    /// Create a world whose RNG is seeded with a fixed value
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_dimensions_and_seed(DEFAULT_WORLD_WIDTH, DEFAULT_WORLD_HEIGHT, seed)
+    }
+
+    /// Create a world with the given grid dimensions
+    /// The seed is drawn from entropy in 1..=i64::MAX so it can be written as RANDOM_SEED
+    pub fn with_dimensions(width: usize, height: usize) -> Self {
+        use rand::Rng;
+        let seed = rand::thread_rng().gen_range(1..=i64::MAX as u64);
+        Self::with_dimensions_and_seed(width, height, seed)
+    }
+
+    /// Create a world with the given grid dimensions and a fixed RNG seed
+    pub fn with_dimensions_and_seed(width: usize, height: usize, seed: u64) -> Self {
+        assert!(
+            width > 0 && height > 0,
+            "World dimensions must be non-zero, got {}x{}",
+            width,
+            height
+        );
+        let grid_size = width * height;
         let grid = vec![None; grid_size];
         let task_detectors = vec![None; grid_size];
 
         Self {
             grid,
+            width,
+            height,
             task_detectors,
             task_env: TaskEnvironment::default_logic9(),
+            inst_set: InstSet::default(),
             copy_mutation_rate: 0.0075, // Default Avida copy mutation rate (0.75%)
             insertion_rate: 0.05,       // 5% chance of insertion per division
             deletion_rate: 0.05,        // 5% chance of deletion per division
             death_method: 2,            // Avida default: age limit × genome length
             age_limit: 20,              // Avida default: 20× genome length
             prefer_empty: true,         // Avida default: prefer empty cells
+            recombination: Recombination::default(),
+            birth_chamber: BirthChamber::default(),
+            parasites: ParasiteSettings::default(),
             total_updates: 0,
             total_organisms: 0,
             total_births: 0,
             total_deaths: 0,
+            total_infections: 0,
             population_size: 0,
-            rng: SmallRng::from_entropy(), // Faster than thread_rng
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            events: EventSchedule::default(),
+            genotypes: GenotypeRegistry::new(),
+        }
+    }
+
+    /// Seed the world RNG was created with; `World::with_seed` reproduces the run
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Offspring of divide-sex waiting for a mate
+    pub fn birth_chamber(&self) -> &BirthChamber {
+        &self.birth_chamber
+    }
+
+    /// Genotype registry of the population
+    pub fn genotypes(&self) -> &GenotypeRegistry {
+        &self.genotypes
+    }
+
+    /// Register an organism entering the world with its genotype
+    fn register_birth(&mut self, org: &mut Organism, parent_genotype: Option<u64>) {
+        org.genotype_id = Some(self.genotypes.add_organism(
+            &org.genome,
+            parent_genotype,
+            &org.mutations,
+            self.total_updates,
+            org.generation,
+        ));
+    }
+
+    /// Record an organism leaving the world in its genotype
+    /// Births are registered before the deaths they cause, so a genotype
+    /// replacing itself never looks extinct
+    fn register_death(&mut self, org: &Organism) {
+        if let Some(id) = org.genotype_id {
+            self.genotypes
+                .remove_organism(id, org.tasks_completed, org.merit, self.total_updates);
         }
     }
 
     /// Convert 2D coordinates to flat array index
     #[inline]
     fn grid_index(&self, x: usize, y: usize) -> usize {
-        y * WORLD_WIDTH + x
+        y * self.width + x
     }
 
     /// Get world dimensions
     pub fn dimensions(&self) -> (usize, usize) {
-        (WORLD_WIDTH, WORLD_HEIGHT)
+        (self.width, self.height)
     }
 
     /// Wrap coordinates for toroidal topology
     fn wrap_coord(&self, x: isize, y: isize) -> (usize, usize) {
-        let wx =
-            ((x % WORLD_WIDTH as isize + WORLD_WIDTH as isize) % WORLD_WIDTH as isize) as usize;
-        let wy =
-            ((y % WORLD_HEIGHT as isize + WORLD_HEIGHT as isize) % WORLD_HEIGHT as isize) as usize;
+        let wx = x.rem_euclid(self.width as isize) as usize;
+        let wy = y.rem_euclid(self.height as isize) as usize;
         (wx, wy)
     }
 
@@ -116,14 +261,18 @@ impl World {
     }
 
     /// Inject an organism at a specific position
-    pub fn inject_organism(&mut self, org: Organism, x: usize, y: usize) -> bool {
-        if x >= WORLD_WIDTH || y >= WORLD_HEIGHT {
+    pub fn inject_organism(&mut self, mut org: Organism, x: usize, y: usize) -> bool {
+        if x >= self.width || y >= self.height {
             return false;
         }
+        org.update_born = self.total_updates;
+        org.position = Some((x, y));
+        self.register_birth(&mut org, None);
 
         let idx = self.grid_index(x, y);
-        if self.grid[idx].is_some() {
+        if let Some(old) = self.grid[idx].take() {
             self.total_deaths += 1;
+            self.register_death(&old);
         }
 
         self.grid[idx] = Some(org);
@@ -136,8 +285,8 @@ impl World {
     /// Inject ancestor at center of world
     pub fn inject_ancestor(&mut self) {
         let ancestor = Organism::ancestor();
-        let center_x = WORLD_WIDTH / 2;
-        let center_y = WORLD_HEIGHT / 2;
+        let center_x = self.width / 2;
+        let center_y = self.height / 2;
         self.inject_organism(ancestor, center_x, center_y);
     }
 
@@ -145,8 +294,8 @@ impl World {
     /// This ancestor has I/O and arithmetic instructions for task evolution
     pub fn inject_ancestor_with_tasks(&mut self) {
         let ancestor = Organism::ancestor_with_tasks();
-        let center_x = WORLD_WIDTH / 2;
-        let center_y = WORLD_HEIGHT / 2;
+        let center_x = self.width / 2;
+        let center_y = self.height / 2;
         self.inject_organism(ancestor, center_x, center_y);
     }
 
@@ -215,26 +364,137 @@ impl World {
         None
     }
 
+    /// Place an offspring next to its parent's cell, replacing any organism there
+    /// Returns the birth cell, or None if no birth location was found
+    fn place_offspring(
+        &mut self,
+        mut offspring: Organism,
+        parent_genotype: Option<u64>,
+        parent_x: usize,
+        parent_y: usize,
+    ) -> Option<usize> {
+        let Some((birth_x, birth_y)) = self.find_birth_location(parent_x, parent_y) else {
+            crate::debug::log_event(format!(
+                "[WARN] No birth location found for offspring at ({}, {})",
+                parent_x, parent_y
+            ));
+            return None;
+        };
+        // Bounds check before placing
+        if birth_x >= self.width || birth_y >= self.height {
+            crate::debug::log_event(format!(
+                "[ERROR] Birth location out of bounds! ({}, {}) max:({}, {})",
+                birth_x, birth_y, self.width, self.height
+            ));
+            return None;
+        }
+
+        let birth_idx = self.grid_index(birth_x, birth_y);
+        self.register_birth(&mut offspring, parent_genotype);
+        if let Some(old) = self.grid[birth_idx].take() {
+            self.total_deaths += 1;
+            self.register_death(&old);
+        }
+        offspring.update_born = self.total_updates;
+        offspring.position = Some((birth_x, birth_y));
+        self.grid[birth_idx] = Some(offspring);
+        self.task_detectors[birth_idx] = Some(TaskDetector::new());
+        self.total_births += 1;
+        Some(birth_idx)
+    }
+
+    /// Give one host cycle to a parasite thread
+    /// Parasites are rewarded for tasks from their own table, and every offspring they
+    /// produce (by inject or any other divide) tries to infect a neighbor of the host.
+    /// Parasites do not age: DEATH_METHOD only applies to hosts, and a parasite lives
+    /// until its host dies.
+    fn run_parasite(&mut self, host: &mut Organism, thread: usize, x: usize, y: usize) {
+        let parasite = &mut host.parasites[thread];
+        if !parasite.org.pay_instruction_cost(&self.inst_set) {
+            return;
+        }
+        let (should_divide, _) = execute_parasite_instruction(
+            &mut parasite.org,
+            &mut parasite.detector,
+            &self.parasites,
+            self.copy_mutation_rate,
+            &self.inst_set,
+            &mut self.rng,
+        );
+        if should_divide {
+            if let Some(offspring) = parasite.org.divide(
+                self.insertion_rate,
+                self.deletion_rate,
+                &self.inst_set,
+                &mut self.rng,
+            ) {
+                self.inject_offspring(offspring, x, y);
+            }
+        }
+    }
+
+    /// Try to infect a random neighbor of (`x`, `y`) with an offspring
+    fn inject_offspring(&mut self, offspring: Organism, x: usize, y: usize) -> bool {
+        use rand::Rng;
+        let neighbors = self.get_neighbors(x, y);
+        let (nx, ny) = neighbors[self.rng.gen_range(0..neighbors.len())];
+        let idx = self.grid_index(nx, ny);
+        self.infect(offspring, idx)
+    }
+
+    /// Add a parasite to the host in cell `idx` as a new thread
+    /// Fails if the cell is empty or all of the host's threads are in use
+    fn infect(&mut self, mut parasite: Organism, idx: usize) -> bool {
+        let max_threads = self.parasites.max_threads;
+        match &mut self.grid[idx] {
+            Some(host) if host.parasites.len() + 1 < max_threads => {
+                parasite.update_born = self.total_updates;
+                parasite.position = host.position;
+                host.parasites.push(Parasite::new(parasite));
+                self.total_infections += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Infect the host at (`x`, `y`) with a parasite genome
+    pub fn inject_parasite(&mut self, genome: Vec<Instruction>, x: usize, y: usize) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let idx = self.grid_index(x, y);
+        self.infect(Organism::new(genome), idx)
+    }
+
+    /// Number of parasites in all hosts
+    pub fn parasite_count(&self) -> usize {
+        self.grid
+            .iter()
+            .flatten()
+            .map(|org| org.parasites.len())
+            .sum()
+    }
+
     /// Execute one update cycle
     /// An update is a time slice where all organisms get CPU cycles proportional to MERIT
     /// Total CPU cycles in an update scales with population size
     /// Organisms with higher merit execute more instructions and reproduce faster
     pub fn update(&mut self) {
+        // Scheduled events for this update run before any organism executes
+        let mut events = std::mem::take(&mut self.events);
+        events.process(self);
+        self.events = events;
+        if self.events.exit_requested {
+            return;
+        }
+
+        self.task_env.update_resources();
+
         let pop_before = self.population_size;
 
-        // Calculate total merit (parallel on native, sequential on wasm)
-        #[cfg(not(target_arch = "wasm32"))]
-        let total_merit: f64 = self
-            .grid
-            .par_iter()
-            .filter_map(|cell| cell.as_ref().map(|org| org.merit))
-            .sum();
-        #[cfg(target_arch = "wasm32")]
-        let total_merit: f64 = self
-            .grid
-            .iter()
-            .filter_map(|cell| cell.as_ref().map(|org| org.merit))
-            .sum();
+        // Calculate total merit (deterministic chunked sum, parallel on native)
+        let (total_merit, _) = sum_organisms(&self.grid, |org| org.merit);
 
         if total_merit == 0.0 {
             crate::debug::log_event(format!(
@@ -245,7 +505,7 @@ impl World {
         }
 
         // Log update start periodically
-        if self.total_updates % 100 == 0 {
+        if self.total_updates.is_multiple_of(100) {
             crate::debug::log_event(format!(
                 "[UPDATE #{}] pop:{} merit_total:{:.1} births:{} deaths:{}",
                 self.total_updates,
@@ -274,8 +534,8 @@ impl World {
 
         // Collect positions to process (to avoid borrow conflicts)
         let mut positions = Vec::new();
-        for y in 0..WORLD_HEIGHT {
-            for x in 0..WORLD_WIDTH {
+        for y in 0..self.height {
+            for x in 0..self.width {
                 let idx = self.grid_index(x, y);
                 if self.grid[idx].is_some() {
                     positions.push((x, y));
@@ -301,7 +561,9 @@ impl World {
                 };
 
                 if should_die {
-                    self.grid[idx] = None;
+                    if let Some(org) = self.grid[idx].take() {
+                        self.register_death(&org);
+                    }
                     self.total_deaths += 1;
                     continue; // Skip to next organism
                 }
@@ -339,8 +601,36 @@ impl World {
                         // Detect potential infinite loops (organism stuck at same IP)
                         let ip_before = org.cpu.ip;
 
-                        let (should_divide, completed_task) =
-                            execute_instruction(&mut org, &mut detector, self.copy_mutation_rate);
+                        // Parasite threads take their share of the host's cycles
+                        if !org.parasites.is_empty() {
+                            if let Some(thread) = parasite::choose_thread(
+                                &org.parasites,
+                                self.parasites.virulence,
+                                &mut self.rng,
+                            ) {
+                                org.gestation_cycles += 1;
+                                self.run_parasite(&mut org, thread, x, y);
+                                self.grid[parent_idx] = Some(org);
+                                self.task_detectors[parent_idx] = Some(detector);
+                                continue;
+                            }
+                        }
+
+                        // Instructions costing several cycles wait here until paid
+                        if !org.pay_instruction_cost(&self.inst_set) {
+                            self.grid[parent_idx] = Some(org);
+                            self.task_detectors[parent_idx] = Some(detector);
+                            continue;
+                        }
+                        let executed = org.current_instruction();
+                        let (should_divide, completed_task) = execute_instruction(
+                            &mut org,
+                            &mut detector,
+                            &mut self.task_env,
+                            self.copy_mutation_rate,
+                            &self.inst_set,
+                            &mut self.rng,
+                        );
 
                         // Check if IP is advancing (not stuck in infinite loop)
                         if cycle_num > 100 && org.cpu.ip == ip_before {
@@ -358,9 +648,12 @@ impl World {
 
                         if should_divide {
                             // Attempt division
-                            if let Some(offspring) =
-                                org.divide(self.insertion_rate, self.deletion_rate)
-                            {
+                            if let Some(offspring) = org.divide(
+                                self.insertion_rate,
+                                self.deletion_rate,
+                                &self.inst_set,
+                                &mut self.rng,
+                            ) {
                                 // Debug first few offspring genomes
                                 static OFFSPRING_LOG: AtomicU32 = AtomicU32::new(0);
                                 let log_index = OFFSPRING_LOG.fetch_add(1, Ordering::Relaxed) + 1;
@@ -373,35 +666,47 @@ impl World {
                                     ));
                                 }
 
-                                if let Some((birth_x, birth_y)) = self.find_birth_location(x, y) {
-                                    // Bounds check before placing
-                                    if birth_x >= WORLD_WIDTH || birth_y >= WORLD_HEIGHT {
-                                        crate::debug::log_event(format!(
-                                            "[ERROR] Birth location out of bounds! ({}, {}) max:({}, {})",
-                                            birth_x, birth_y, WORLD_WIDTH, WORLD_HEIGHT
-                                        ));
-                                        continue;
+                                if executed == Some(Instruction::Inject) {
+                                    // The offspring becomes a parasite of a neighbor
+                                    self.inject_offspring(offspring, x, y);
+                                } else if executed == Some(Instruction::DivideSex) {
+                                    // The parent goes back into the grid first, so offspring
+                                    // born next to their own parents can only replace it as
+                                    // an occupied cell
+                                    let parent_genotype = org.genotype_id;
+                                    self.grid[parent_idx] = Some(org);
+                                    self.task_detectors[parent_idx] = Some(detector);
+                                    let born = self.birth_chamber.submit(
+                                        WaitingOffspring {
+                                            offspring,
+                                            parent_position: (x, y),
+                                            parent_genotype,
+                                        },
+                                        &self.recombination,
+                                        &mut self.rng,
+                                    );
+                                    for entry in born {
+                                        let (px, py) = entry.parent_position;
+                                        let birth_idx = self.place_offspring(
+                                            entry.offspring,
+                                            entry.parent_genotype,
+                                            px,
+                                            py,
+                                        );
+                                        if birth_idx == Some(parent_idx) {
+                                            parent_alive = false;
+                                        }
                                     }
-
-                                    // Place offspring
-                                    let birth_idx = self.grid_index(birth_x, birth_y);
-                                    let replacing_parent = birth_idx == parent_idx;
-
-                                    if self.grid[birth_idx].is_some() || replacing_parent {
+                                    continue;
+                                } else {
+                                    let birth_idx =
+                                        self.place_offspring(offspring, org.genotype_id, x, y);
+                                    // The parent is out of the grid while it executes
+                                    if birth_idx == Some(parent_idx) {
                                         self.total_deaths += 1;
-                                    }
-                                    self.grid[birth_idx] = Some(offspring);
-                                    self.task_detectors[birth_idx] = Some(TaskDetector::new());
-                                    self.total_births += 1;
-
-                                    if replacing_parent {
+                                        self.register_death(&org);
                                         parent_alive = false;
                                     }
-                                } else {
-                                    crate::debug::log_event(format!(
-                                        "[WARN] No birth location found for offspring at ({}, {})",
-                                        x, y
-                                    ));
                                 }
                             }
                         }
@@ -411,9 +716,7 @@ impl World {
                             self.grid[parent_idx] = Some(org);
                             self.task_detectors[parent_idx] = Some(detector);
                         }
-                    }
-
-                    if !parent_alive {
+                    } else {
                         break;
                     }
                 }
@@ -453,28 +756,27 @@ impl World {
         self.grid.iter().filter(|cell| cell.is_some()).count()
     }
 
-    /// Get statistics about tasks completed (parallel)
+    /// Get statistics about tasks completed: organisms that performed each task, by
+    /// `Task::index` (parallel)
     #[cfg(not(target_arch = "wasm32"))]
-    pub fn task_statistics(&self) -> [usize; 9] {
+    pub fn task_statistics(&self) -> Vec<usize> {
         self.grid
             .par_iter()
             .filter_map(|cell| cell.as_ref())
             .fold(
-                || [0usize; 9],
+                || vec![0usize; Task::COUNT],
                 |mut acc, org| {
-                    for task_idx in 0u8..9 {
-                        if org.has_completed_task(task_idx) {
-                            acc[task_idx as usize] += 1;
-                        }
+                    for task in org.tasks_completed.iter() {
+                        acc[task.index()] += 1;
                     }
                     acc
                 },
             )
             .reduce(
-                || [0usize; 9],
+                || vec![0usize; Task::COUNT],
                 |mut acc, counts| {
-                    for i in 0..9 {
-                        acc[i] += counts[i];
+                    for (total, count) in acc.iter_mut().zip(counts) {
+                        *total += count;
                     }
                     acc
                 },
@@ -483,13 +785,11 @@ impl World {
 
     /// Get statistics about tasks completed (sequential on wasm)
     #[cfg(target_arch = "wasm32")]
-    pub fn task_statistics(&self) -> [usize; 9] {
-        let mut counts = [0usize; 9];
+    pub fn task_statistics(&self) -> Vec<usize> {
+        let mut counts = vec![0usize; Task::COUNT];
         for org in self.grid.iter().filter_map(|cell| cell.as_ref()) {
-            for task_idx in 0u8..9 {
-                if org.has_completed_task(task_idx) {
-                    counts[task_idx as usize] += 1;
-                }
+            for task in org.tasks_completed.iter() {
+                counts[task.index()] += 1;
             }
         }
         counts
@@ -529,32 +829,9 @@ impl World {
         }
     }
 
-    /// Get average merit (parallel)
-    #[cfg(not(target_arch = "wasm32"))]
+    /// Get average merit (deterministic chunked sum, parallel on native)
     pub fn average_merit(&self) -> f64 {
-        let (total, count) = self
-            .grid
-            .par_iter()
-            .filter_map(|cell| cell.as_ref())
-            .map(|org| (org.merit, 1))
-            .reduce(|| (0.0, 0), |acc, val| (acc.0 + val.0, acc.1 + val.1));
-
-        if count > 0 {
-            total / count as f64
-        } else {
-            0.0
-        }
-    }
-
-    /// Get average merit (sequential on wasm)
-    #[cfg(target_arch = "wasm32")]
-    pub fn average_merit(&self) -> f64 {
-        let mut total = 0.0f64;
-        let mut count = 0usize;
-        for org in self.grid.iter().filter_map(|cell| cell.as_ref()) {
-            total += org.merit;
-            count += 1;
-        }
+        let (total, count) = sum_organisms(&self.grid, |org| org.merit);
 
         if count > 0 {
             total / count as f64
@@ -563,17 +840,11 @@ impl World {
         }
     }
 
-    /// Get average fitness (parallel)
+    /// Get average fitness (deterministic chunked sum, parallel on native)
     /// Fitness = merit / gestation_cycles (higher is better)
     /// This is calculated for statistics only - scheduling uses merit directly
-    #[cfg(not(target_arch = "wasm32"))]
     pub fn average_fitness(&self) -> f64 {
-        let (total, count) = self
-            .grid
-            .par_iter()
-            .filter_map(|cell| cell.as_ref())
-            .map(|org| (org.fitness(), 1))
-            .reduce(|| (0.0, 0), |acc, val| (acc.0 + val.0, acc.1 + val.1));
+        let (total, count) = sum_organisms(&self.grid, |org| org.fitness());
 
         if count > 0 {
             total / count as f64
@@ -582,17 +853,9 @@ impl World {
         }
     }
 
-    /// Get average fitness (sequential on wasm)
-    /// Fitness = merit / gestation_cycles (higher is better)
-    /// This is calculated for statistics only - scheduling uses merit directly
-    #[cfg(target_arch = "wasm32")]
-    pub fn average_fitness(&self) -> f64 {
-        let mut total = 0.0f64;
-        let mut count = 0usize;
-        for org in self.grid.iter().filter_map(|cell| cell.as_ref()) {
-            total += org.fitness();
-            count += 1;
-        }
+    /// Get average generation (deterministic chunked sum, parallel on native)
+    pub fn average_generation(&self) -> f64 {
+        let (total, count) = sum_organisms(&self.grid, |org| org.generation as f64);
 
         if count > 0 {
             total / count as f64
@@ -601,9 +864,34 @@ impl World {
         }
     }
 
+    /// Indices of all occupied cells, in grid order
+    pub fn occupied_cells(&self) -> impl Iterator<Item = usize> + '_ {
+        self.grid
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, cell)| cell.as_ref().map(|_| idx))
+    }
+
+    /// Remove the organism in a cell (by index), counting it as a death
+    pub fn kill_organism(&mut self, idx: usize) -> bool {
+        let Some(org) = self.grid.get_mut(idx).and_then(Option::take) else {
+            return false;
+        };
+        self.register_death(&org);
+        self.task_detectors[idx] = None;
+        self.total_deaths += 1;
+        self.population_size -= 1;
+        true
+    }
+
+    /// The world RNG, for code outside the update loop that must stay reproducible
+    pub(crate) fn rng(&mut self) -> &mut ChaCha8Rng {
+        &mut self.rng
+    }
+
     /// Get organism at position
     pub fn get_organism(&self, x: usize, y: usize) -> Option<&Organism> {
-        if x < WORLD_WIDTH && y < WORLD_HEIGHT {
+        if x < self.width && y < self.height {
             let idx = self.grid_index(x, y);
             self.grid[idx].as_ref()
         } else {
@@ -611,6 +899,70 @@ impl World {
         }
     }
 
+    /// Check the invariants of a deserialized world (e.g. a loaded checkpoint)
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if self.width == 0 || self.height == 0 {
+            return Err(format!(
+                "world dimensions must be non-zero, got {}x{}",
+                self.width, self.height
+            ));
+        }
+        let grid_size = self.width * self.height;
+        if self.grid.len() != grid_size || self.task_detectors.len() != grid_size {
+            return Err(format!(
+                "grid has {} cells and {} task detectors, expected {} for {}x{}",
+                self.grid.len(),
+                self.task_detectors.len(),
+                grid_size,
+                self.width,
+                self.height
+            ));
+        }
+        if self.population_size != self.count_population() {
+            return Err(format!(
+                "population size {} does not match {} organisms on the grid",
+                self.population_size,
+                self.count_population()
+            ));
+        }
+        let mut abundances: HashMap<u64, usize> = HashMap::new();
+        for org in self.grid.iter().flatten() {
+            let id = org
+                .genotype_id
+                .ok_or_else(|| "organism without a genotype".to_string())?;
+            *abundances.entry(id).or_insert(0) += 1;
+        }
+        for (id, count) in &abundances {
+            let abundance = self.genotypes.get(*id).map_or(0, |g| g.abundance);
+            if abundance != *count {
+                return Err(format!(
+                    "genotype {} has abundance {} but {} organisms on the grid",
+                    id, abundance, count
+                ));
+            }
+        }
+        if abundances.len() != self.genotypes.living_count() {
+            return Err(format!(
+                "{} living genotypes registered but {} on the grid",
+                self.genotypes.living_count(),
+                abundances.len()
+            ));
+        }
+
+        // Stale detectors in empty cells are harmless, but an organism without one
+        // would be dropped by update() without being counted as a death
+        if let Some(idx) = (0..grid_size)
+            .find(|&idx| self.grid[idx].is_some() && self.task_detectors[idx].is_none())
+        {
+            return Err(format!(
+                "organism at ({}, {}) has no task detector",
+                idx % self.width,
+                idx / self.width
+            ));
+        }
+        Ok(())
+    }
+
     /// Clear the world
     pub fn clear(&mut self) {
         for cell in &mut self.grid {
@@ -619,6 +971,9 @@ impl World {
         for detector in &mut self.task_detectors {
             *detector = None;
         }
+        self.genotypes = GenotypeRegistry::new();
+        self.birth_chamber = BirthChamber::default();
+        self.task_env.reset_resources();
         self.population_size = 0;
     }
 }
@@ -669,7 +1024,7 @@ mod tests {
 
     #[test]
     fn test_population_growth_preserves_genomes() {
-        let mut world = World::new();
+        let mut world = World::with_seed(1);
         world.inject_ancestor();
 
         for _ in 0..200 {
@@ -681,20 +1036,18 @@ mod tests {
 
         // With insertion/deletion mutations, genome sizes will vary
         // but should remain in a reasonable range around the ancestor size (50)
-        for cell in &world.grid {
-            if let Some(org) = cell {
-                assert!(
-                    org.genome_size() >= 40 && org.genome_size() <= 60,
-                    "Genome size {} outside expected range [40, 60]",
-                    org.genome_size()
-                );
-            }
+        for org in world.grid.iter().flatten() {
+            assert!(
+                org.genome_size() >= 40 && org.genome_size() <= 60,
+                "Genome size {} outside expected range [40, 60]",
+                org.genome_size()
+            );
         }
     }
 
     #[test]
     fn test_extended_population_growth() {
-        let mut world = World::new();
+        let mut world = World::with_seed(1);
         world.inject_ancestor();
 
         for _ in 0..600 {
@@ -706,20 +1059,18 @@ mod tests {
 
         // With insertion/deletion mutations, genome sizes will vary
         // but should remain in a reasonable range around the ancestor size (50)
-        for cell in &world.grid {
-            if let Some(org) = cell {
-                assert!(
-                    org.genome_size() >= 40 && org.genome_size() <= 60,
-                    "Genome size {} outside expected range [40, 60]",
-                    org.genome_size()
-                );
-            }
+        for org in world.grid.iter().flatten() {
+            assert!(
+                org.genome_size() >= 40 && org.genome_size() <= 60,
+                "Genome size {} outside expected range [40, 60]",
+                org.genome_size()
+            );
         }
     }
 
     #[test]
     fn test_average_genome_size_after_long_run() {
-        let mut world = World::new();
+        let mut world = World::with_seed(1);
         world.inject_ancestor();
 
         for _ in 0..5000 {
@@ -832,8 +1183,8 @@ mod tests {
         let mut world = World::new();
         world.inject_ancestor();
 
-        let center_x = WORLD_WIDTH / 2;
-        let center_y = WORLD_HEIGHT / 2;
+        let center_x = DEFAULT_WORLD_WIDTH / 2;
+        let center_y = DEFAULT_WORLD_HEIGHT / 2;
 
         assert!(world.get_organism(center_x, center_y).is_some());
         assert!(world.get_organism(0, 0).is_none());
@@ -935,7 +1286,7 @@ mod tests {
 
     #[test]
     fn test_update_increments_counter() {
-        let mut world = World::new();
+        let mut world = World::with_seed(1);
         world.inject_ancestor();
 
         assert_eq!(world.total_updates, 0);
@@ -947,7 +1298,7 @@ mod tests {
 
     #[test]
     fn test_update_empty_world() {
-        let mut world = World::new();
+        let mut world = World::with_seed(1);
         world.update();
         assert_eq!(world.population_size, 0);
         assert_eq!(world.total_updates, 0); // Should not increment if no organisms
@@ -962,11 +1313,69 @@ mod tests {
     }
 
     #[test]
-    fn test_world_dimensions_constant() {
+    fn test_world_dimensions_default() {
         let world = World::new();
         let (width, height) = world.dimensions();
-        assert_eq!(width, WORLD_WIDTH);
-        assert_eq!(height, WORLD_HEIGHT);
+        assert_eq!(width, DEFAULT_WORLD_WIDTH);
+        assert_eq!(height, DEFAULT_WORLD_HEIGHT);
+    }
+
+    #[test]
+    fn test_world_custom_dimensions() {
+        let world = World::with_dimensions(100, 40);
+        assert_eq!(world.dimensions(), (100, 40));
+        assert_eq!(world.grid.len(), 4000);
+        assert_eq!(world.population_size, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-zero")]
+    fn test_world_zero_dimensions_panics() {
+        World::with_dimensions(0, 10);
+    }
+
+    #[test]
+    fn test_wrap_coord_custom_dimensions() {
+        let world = World::with_dimensions(10, 20);
+        assert_eq!(world.wrap_coord(-1, -1), (9, 19));
+        assert_eq!(world.wrap_coord(10, 20), (0, 0));
+        assert_eq!(world.wrap_coord(12, 45), (2, 5));
+    }
+
+    #[test]
+    fn test_get_neighbors_custom_dimensions() {
+        let world = World::with_dimensions(10, 20);
+        let neighbors = world.get_neighbors(0, 0);
+        assert!(neighbors.contains(&(9, 19)));
+        assert!(neighbors.contains(&(9, 0)));
+        assert!(neighbors.contains(&(0, 19)));
+    }
+
+    #[test]
+    fn test_custom_dimensions_bounds() {
+        let mut world = World::with_dimensions(10, 10);
+        assert!(world.inject_organism(Organism::ancestor(), 9, 9));
+        assert!(!world.inject_organism(Organism::ancestor(), 10, 5));
+        assert!(world.get_organism(9, 9).is_some());
+        assert!(world.get_organism(10, 0).is_none());
+
+        world.clear();
+        world.inject_ancestor();
+        assert!(world.get_organism(5, 5).is_some());
+    }
+
+    #[test]
+    fn test_small_world_fills_up() {
+        let mut world = World::with_dimensions_and_seed(10, 10, 4);
+        world.inject_ancestor();
+
+        for _ in 0..300 {
+            world.update();
+        }
+
+        assert!(world.population_size > 50);
+        assert!(world.population_size <= 100);
+        assert!(world.grid.iter().flatten().all(|org| org.genome_size() > 0));
     }
 
     #[test]
@@ -978,7 +1387,7 @@ mod tests {
 
     #[test]
     fn test_births_occur_after_updates() {
-        let mut world = World::new();
+        let mut world = World::with_seed(1);
         world.inject_ancestor();
 
         for _ in 0..50 {
@@ -990,13 +1399,13 @@ mod tests {
 
     #[test]
     fn test_population_growth_is_exponential_initially() {
-        let mut world = World::new();
+        let mut world = World::with_seed(1);
         world.inject_ancestor();
 
         let mut pop_history = vec![];
         for _ in 0..100 {
             world.update();
-            if world.total_updates % 20 == 0 {
+            if world.total_updates.is_multiple_of(20) {
                 pop_history.push(world.population_size);
             }
         }
@@ -1007,7 +1416,7 @@ mod tests {
 
     #[test]
     fn test_merit_affects_cpu_cycles() {
-        let mut world = World::new();
+        let mut world = World::with_seed(1);
         let mut org1 = Organism::ancestor();
         org1.merit = 1.0;
 
@@ -1023,6 +1432,207 @@ mod tests {
         assert!(world.total_updates > 0);
     }
 
+    #[test]
+    fn test_instruction_costs_slow_replication() {
+        let births = |inst_set: InstSet| {
+            let mut world = World::with_dimensions_and_seed(20, 20, 1);
+            world.inst_set = inst_set;
+            world.inject_ancestor();
+            for _ in 0..30 {
+                world.update();
+            }
+            world.total_births
+        };
+        let expensive_copy = InstSet::parse(
+            &InstSet::default()
+                .format()
+                .replace("INST h-copy:redundancy=1:cost=1", "INST h-copy:cost=3"),
+        )
+        .unwrap();
+        assert_eq!(expensive_copy.cost(Instruction::HCopy), 3);
+        assert!(births(expensive_copy) < births(InstSet::default()));
+
+        let expensive_alloc = InstSet::parse(&InstSet::default().format().replace(
+            "INST h-alloc:redundancy=1:cost=1:initial_cost=0",
+            "INST h-alloc:initial_cost=200",
+        ))
+        .unwrap();
+        assert_eq!(expensive_alloc.initial_cost(Instruction::HAlloc), 200);
+        assert!(births(expensive_alloc) < births(InstSet::default()));
+    }
+
+    #[test]
+    fn test_divide_sex_recombines_offspring() {
+        let sexual = |padding: &str| {
+            let genome = format!("rutyabUva{}bc", padding.repeat(39));
+            Organism::new(InstSet::full().parse_sequence(&genome).unwrap())
+        };
+        let mut world = World::with_dimensions_and_seed(20, 20, 1);
+        world.copy_mutation_rate = 0.0;
+        world.insertion_rate = 0.0;
+        world.deletion_rate = 0.0;
+        world.inject_organism(sexual("c"), 5, 10);
+        world.inject_organism(sexual("l"), 15, 10);
+        for _ in 0..40 {
+            world.update();
+        }
+        assert!(world.total_births > 0);
+
+        // Recombinants carry padding from both ancestors
+        let recombinant = world.grid.iter().flatten().any(|org| {
+            org.genome.contains(&Instruction::NopC) && org.genome.contains(&Instruction::Inc)
+        });
+        assert!(recombinant);
+        // Crossover is recorded as mutations from the parent's genotype
+        let genotypes = world.genotypes();
+        for genotype in genotypes.iter() {
+            if let Some(parent) = genotype.parent_id.and_then(|id| genotypes.get(id)) {
+                let mut genome = parent.genome.clone();
+                for mutation in &genotype.mutations {
+                    mutation.apply(&mut genome);
+                }
+                assert_eq!(genome, genotype.genome, "genotype {}", genotype.id);
+            }
+        }
+
+        world.clear();
+        assert!(world.birth_chamber().waiting().is_none());
+    }
+
+    #[test]
+    fn test_parasites_spread_between_hosts() {
+        let run = |max_threads| {
+            let mut world = World::with_dimensions_and_seed(20, 20, 1);
+            world.parasites.max_threads = max_threads;
+            world.parasites.virulence = 0.8;
+            world.inject_ancestor();
+            for _ in 0..40 {
+                world.update();
+            }
+            // Infect the youngest host, which is furthest from its age limit
+            let (width, _) = world.dimensions();
+            let host = world
+                .occupied_cells()
+                .min_by_key(|&idx| world.grid[idx].as_ref().map_or(0, |org| org.age()))
+                .unwrap();
+            let infected = world.inject_parasite(
+                Organism::parasite_ancestor().genome,
+                host % width,
+                host / width,
+            );
+            assert_eq!(infected, max_threads > 1);
+            let births = world.total_births;
+            for _ in 0..60 {
+                world.update();
+            }
+            (world.total_births - births, world)
+        };
+
+        let (clean_births, clean) = run(1);
+        assert_eq!(clean.parasite_count(), 0);
+
+        let (infected_births, infected) = run(2);
+        assert!(infected.total_infections > 1);
+        assert!(infected.parasite_count() > 1);
+        // Each host runs at most one parasite thread
+        assert!(infected
+            .grid
+            .iter()
+            .flatten()
+            .all(|org| org.parasites.len() <= 1));
+        // Parasites take cycles from their hosts
+        assert!(infected_births < clean_births);
+    }
+
+    #[test]
+    fn test_tasks_consume_global_resources() {
+        use crate::resources::Resource;
+        use crate::tasks::{Reaction, Requisite, RewardType, Task};
+
+        // NOT is rewarded once from a glucose pool that settles at 1000 when unused
+        let run = |organisms: bool| {
+            let mut world = World::with_dimensions_and_seed(20, 20, 5);
+            world
+                .task_env
+                .resources
+                .push(Resource::new("glucose", 0.0, 10.0, 0.01));
+            world.task_env.set_reactions(vec![Reaction {
+                resource: Some("glucose".to_string()),
+                reward_type: RewardType::Pow,
+                frac: 0.01,
+                max: 2.0,
+                requisites: vec![Requisite {
+                    max_count: 1,
+                    ..Requisite::default()
+                }],
+                ..Reaction::new(Task::Not, 1.0)
+            }]);
+            if organisms {
+                let genome =
+                    crate::instruction::parse_genome("qqgifpqrutyabsvacccccccccccccbc").unwrap();
+                world.inject_organism(Organism::new(genome), 10, 10);
+            }
+            for _ in 0..300 {
+                world.update();
+            }
+            world
+        };
+
+        let unused = run(false);
+        let consumed = run(true);
+        let amount = |world: &World| world.task_env.resource("glucose").unwrap().amount;
+        assert!(consumed.task_statistics()[Task::Not.index()] > 0);
+        assert!(amount(&consumed) < amount(&unused));
+        // Rewards scale with the amount consumed: at most 2^2
+        assert!(consumed.average_merit() > 1.0);
+        assert!(consumed.grid.iter().flatten().all(|org| org.merit <= 4.0));
+
+        let mut cleared = consumed;
+        cleared.clear();
+        assert_eq!(amount(&cleared), 0.0);
+    }
+
+    #[test]
+    fn test_tasks_consume_spatial_resources_from_own_cell() {
+        use crate::resources::{Resource, Spatial};
+        use crate::tasks::{Reaction, Task};
+
+        let mut world = World::with_dimensions_and_seed(20, 20, 3);
+        let mut spatial = Spatial::new(20, 20);
+        spatial.xdiffuse = 0.0;
+        spatial.ydiffuse = 0.0;
+        world.task_env.resources =
+            vec![Resource::new("glucose", 0.0, 400.0, 0.01).with_spatial(spatial)];
+        world.task_env.set_reactions(vec![Reaction {
+            resource: Some("glucose".to_string()),
+            frac: 0.5,
+            max: 10.0,
+            ..Reaction::new(Task::Not, 1.0)
+        }]);
+        let genome = crate::instruction::parse_genome("qqgifpqrutyabsvacccccccccccccbc").unwrap();
+        world.inject_organism(Organism::new(genome), 10, 10);
+        for _ in 0..60 {
+            world.update();
+        }
+
+        let spatial = world.task_env.resources[0].spatial.as_ref().unwrap();
+        let (mut occupied, mut empty) = (Vec::new(), Vec::new());
+        for y in 0..20 {
+            for x in 0..20 {
+                match world.get_organism(x, y) {
+                    Some(org) => {
+                        assert_eq!(org.position, Some((x, y)));
+                        occupied.push(spatial.get(x, y));
+                    }
+                    None => empty.push(spatial.get(x, y)),
+                }
+            }
+        }
+        let mean = |cells: &[f64]| cells.iter().sum::<f64>() / cells.len() as f64;
+        assert!(!occupied.is_empty() && !empty.is_empty());
+        assert!(mean(&occupied) < mean(&empty));
+    }
+
     #[test]
     fn test_average_fitness() {
         let mut world = World::new();
@@ -1044,7 +1654,7 @@ mod tests {
     #[test]
     fn test_merit_selection_pressure() {
         // High-merit organisms should dominate over time
-        let mut world = World::new();
+        let mut world = World::with_seed(1);
 
         // Inject a low-merit organism
         let mut low_merit = Organism::ancestor();
@@ -1067,4 +1677,69 @@ mod tests {
         // After 50 updates, should have more organisms due to reproduction
         assert!(world.population_size >= 2);
     }
+
+    /// Snapshot of the observable world state for reproducibility checks
+    fn snapshot(world: &World) -> Vec<Option<String>> {
+        world
+            .grid
+            .iter()
+            .map(|cell| {
+                cell.as_ref().map(|org| {
+                    format!(
+                        "{} {} {} {} {:?}",
+                        org.genome_string(),
+                        org.merit.to_bits(),
+                        org.generation,
+                        org.instruction_count,
+                        org.tasks_completed
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn run_seeded(seed: u64, updates: usize) -> World {
+        let mut world = World::with_dimensions_and_seed(30, 30, seed);
+        world.inject_ancestor_with_tasks();
+        for _ in 0..updates {
+            world.update();
+        }
+        world
+    }
+
+    #[test]
+    fn test_same_seed_reproduces_run() {
+        let a = run_seeded(99, 300);
+        let b = run_seeded(99, 300);
+
+        assert!(a.total_births > 0);
+        assert_eq!(snapshot(&a), snapshot(&b));
+        assert_eq!(a.total_births, b.total_births);
+        assert_eq!(a.total_deaths, b.total_deaths);
+        assert_eq!(a.average_merit().to_bits(), b.average_merit().to_bits());
+        assert_eq!(a.average_fitness().to_bits(), b.average_fitness().to_bits());
+        assert_eq!(a.task_statistics(), b.task_statistics());
+    }
+
+    #[test]
+    fn test_different_seeds_diverge() {
+        let a = run_seeded(1, 300);
+        let b = run_seeded(2, 300);
+        assert_ne!(snapshot(&a), snapshot(&b));
+    }
+
+    #[test]
+    fn test_entropy_seed_reproduces_run() {
+        let mut world = World::with_dimensions(30, 30);
+        world.inject_ancestor_with_tasks();
+        for _ in 0..300 {
+            world.update();
+        }
+
+        let replay = run_seeded(world.seed(), 300);
+        assert!(world.total_births > 0);
+        assert_eq!(snapshot(&replay), snapshot(&world));
+        assert_eq!(replay.total_births, world.total_births);
+        assert_eq!(replay.total_deaths, world.total_deaths);
+    }
 }